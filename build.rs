@@ -0,0 +1,35 @@
+use std::process::Command;
+
+// Embeds the commit hash and build date shown by `try-rs --build-info`.
+// Packagers building outside a git checkout can set TRY_RS_GIT_HASH, and
+// SOURCE_DATE_EPOCH is honored for reproducible builds.
+fn main() {
+    println!("cargo:rerun-if-env-changed=TRY_RS_GIT_HASH");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    let git_hash = std::env::var("TRY_RS_GIT_HASH")
+        .ok()
+        .or_else(|| {
+            Command::new("git")
+                .args(["rev-parse", "--short=12", "HEAD"])
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        })
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let build_date = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .unwrap_or_else(chrono::Utc::now)
+        .format("%Y-%m-%d")
+        .to_string();
+
+    println!("cargo:rustc-env=TRY_RS_GIT_HASH={}", git_hash);
+    println!("cargo:rustc-env=TRY_RS_BUILD_DATE={}", build_date);
+}
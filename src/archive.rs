@@ -0,0 +1,232 @@
+use crate::git;
+use crate::utils::{
+    ARCHIVE_DIR, TEMP_CLONE_PREFIX, first_free_name, rename_into_place, temp_clone_dir,
+};
+use anyhow::{Context, Result, bail};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+// How archived experiments are compressed, set with `archive_format` or `--format`
+#[derive(Clone, Copy, PartialEq, Debug, Default, clap::ValueEnum)]
+pub enum ArchiveFormat {
+    #[default]
+    #[value(name = "tar.gz")]
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    pub const NAMES: &str = "tar.gz, zip";
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "tar.gz" | "tgz" => Some(ArchiveFormat::TarGz),
+            "zip" => Some(ArchiveFormat::Zip),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+
+    fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy();
+        [ArchiveFormat::TarGz, ArchiveFormat::Zip]
+            .into_iter()
+            .find(|format| name.ends_with(&format!(".{}", format.extension())))
+    }
+}
+
+// One file in the archive directory
+#[derive(Clone)]
+pub struct Archive {
+    pub path: PathBuf,
+    pub name: String, // File name, e.g. "parser-2024-06-11.tar.gz"
+    pub bytes: u64,
+    pub modified: SystemTime,
+}
+
+// Archives of the experiments in `tries_dir`, newest first
+pub fn list(tries_dir: &Path) -> Vec<Archive> {
+    let Ok(read_dir) = fs::read_dir(tries_dir.join(ARCHIVE_DIR)) else {
+        return Vec::new();
+    };
+    let mut archives: Vec<Archive> = read_dir
+        .flatten()
+        .filter(|e| ArchiveFormat::of(&e.path()).is_some())
+        .filter(|e| {
+            !e.file_name()
+                .to_string_lossy()
+                .starts_with(TEMP_CLONE_PREFIX)
+        })
+        .filter_map(|e| {
+            let metadata = e.metadata().ok().filter(|m| m.is_file())?;
+            Some(Archive {
+                path: e.path(),
+                name: e.file_name().to_string_lossy().into_owned(),
+                bytes: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            })
+        })
+        .collect();
+    archives.sort_by_key(|a| std::cmp::Reverse(a.modified));
+    archives
+}
+
+// The archive called `name`, with or without its extension
+pub fn find(tries_dir: &Path, name: &str) -> Option<Archive> {
+    list(tries_dir).into_iter().find(|archive| {
+        let format = ArchiveFormat::of(&archive.path);
+        archive.name == name
+            || format.is_some_and(|f| archive.name == format!("{}.{}", name, f.extension()))
+    })
+}
+
+// Compresses the experiment at `path` into the archive directory of `tries_dir` (also
+// when it sits in a subfolder) as `<name>-<date>.<ext>` (suffixed when taken), then
+// removes the directory. Returns the archive written.
+pub fn archive(path: &Path, tries_dir: &Path, format: ArchiveFormat) -> Result<PathBuf> {
+    // The tools run inside the tries root, so relative paths would point elsewhere
+    let path = &std::path::absolute(path)?;
+    let (Some(root), Some(name)) = (path.parent(), path.file_name()) else {
+        bail!("{} is not an experiment directory", path.display());
+    };
+    let dir = std::path::absolute(tries_dir)?.join(ARCHIVE_DIR);
+    fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    let base = format!(
+        "{}-{}",
+        name.to_string_lossy(),
+        chrono::Local::now().format("%Y-%m-%d")
+    );
+    let file_name = |stem: &str| format!("{}.{}", stem, format.extension());
+    let stem = first_free_name(&base, |stem| Ok(!dir.join(file_name(stem)).exists()))?;
+    let target = dir.join(file_name(&stem));
+    // As `./name`, so a name starting with `-` can't be taken for an option
+    let name = &Path::new(".").join(name);
+    let name = name.as_os_str();
+
+    // Written under a temporary name, so an interrupted run never looks like a finished archive
+    let temp = dir.join(format!("{}{}", TEMP_CLONE_PREFIX, file_name(&stem)));
+    let written = match format {
+        ArchiveFormat::TarGz => run(
+            "tar",
+            &[
+                OsStr::new("-czf"),
+                temp.as_os_str(),
+                OsStr::new("-C"),
+                root.as_os_str(),
+                name,
+            ],
+            root,
+        ),
+        // Windows' bsdtar writes zip files itself; elsewhere `zip` is expected
+        ArchiveFormat::Zip if cfg!(windows) => run(
+            "tar",
+            &[OsStr::new("-a"), OsStr::new("-cf"), temp.as_os_str(), name],
+            root,
+        ),
+        ArchiveFormat::Zip => run("zip", &[OsStr::new("-qr"), temp.as_os_str(), name], root),
+    };
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    fs::rename(&temp, &target).with_context(|| format!("writing {}", target.display()))?;
+    // The archive holds everything, uncommitted changes included
+    git::remove_dir(path, true).with_context(|| {
+        format!(
+            "archived to {} but could not remove {}",
+            target.display(),
+            path.display()
+        )
+    })?;
+    Ok(target)
+}
+
+// Unpacks `archive` back into `tries_dir` under the directory name stored in it
+// (suffixed when taken) and deletes the archive. Returns the restored experiment.
+pub fn restore(archive: &Path, tries_dir: &Path) -> Result<PathBuf> {
+    let archive = &std::path::absolute(archive)?;
+    let Some(format) = ArchiveFormat::of(archive) else {
+        bail!("{} is not a tar.gz or zip archive", archive.display());
+    };
+    // Extracted next to the experiments first, hidden from the list like a clone in progress
+    let temp = temp_clone_dir(tries_dir, "unarchive");
+    fs::create_dir_all(&temp).with_context(|| format!("creating {}", temp.display()))?;
+    let restored = extract(archive, format, &temp).and_then(|()| {
+        let mut entries = fs::read_dir(&temp)?.flatten();
+        let (Some(inner), None) = (entries.next(), entries.next()) else {
+            bail!("{} does not hold a single directory", archive.display());
+        };
+        let name = rename_into_place(
+            &inner.path(),
+            tries_dir,
+            &inner.file_name().to_string_lossy(),
+        )?;
+        Ok(tries_dir.join(name))
+    });
+    let _ = fs::remove_dir_all(&temp);
+    let restored = restored?;
+    fs::remove_file(archive).with_context(|| format!("removing {}", archive.display()))?;
+    Ok(restored)
+}
+
+fn extract(archive: &Path, format: ArchiveFormat, dest: &Path) -> Result<()> {
+    match format {
+        ArchiveFormat::TarGz => run("tar", &[OsStr::new("-xzf"), archive.as_os_str()], dest),
+        ArchiveFormat::Zip if cfg!(windows) => {
+            run("tar", &[OsStr::new("-xf"), archive.as_os_str()], dest)
+        }
+        ArchiveFormat::Zip => run("unzip", &[OsStr::new("-q"), archive.as_os_str()], dest),
+    }
+}
+
+// Runs `program` in `dir`, turning a failure into its first line of stderr
+fn run(program: &str, args: &[&OsStr], dir: &Path) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("running {}", program))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    bail!(
+        "{}: {}",
+        program,
+        stderr
+            .lines()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or("failed")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_that_look_like_options_round_trip() {
+        for format in [ArchiveFormat::TarGz, ArchiveFormat::Zip] {
+            let dir = tempfile::tempdir().unwrap();
+            let experiment = dir.path().join("--checkpoint=1");
+            fs::create_dir(&experiment).unwrap();
+            fs::write(experiment.join("a.txt"), "a").unwrap();
+
+            let archived = archive(&experiment, dir.path(), format).unwrap();
+            assert!(!experiment.exists());
+            let restored = restore(&archived, dir.path()).unwrap();
+            assert_eq!(restored, experiment);
+            assert_eq!(fs::read_to_string(experiment.join("a.txt")).unwrap(), "a");
+        }
+    }
+}
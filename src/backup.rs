@@ -0,0 +1,241 @@
+use crate::config::{Settings, get_file_config_toml_name};
+use crate::meta::{self, META_FILE, Meta};
+use crate::paths::{EXPORTED_STATE_FILES, FAVORITES_FILE, VISITS_FILE, app_config_dir};
+use crate::tui::scan_entries;
+use crate::utils::stays_inside;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Version of the bundle layout, bumped on incompatible changes
+const BUNDLE_VERSION: u32 = 1;
+
+// Everything `try-rs export` saves: the config file, the state files verbatim and
+// the description and notes of each experiment. The experiment directories
+// themselves are never included.
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    version: u32,
+    exported_at: String,
+    config: Option<String>,
+    // Absolute, so favorites and visits can be pointed at the tries dir of another machine
+    #[serde(default)]
+    tries_dir: Option<PathBuf>,
+    #[serde(default)]
+    state: BTreeMap<String, String>,
+    // META_FILE of each experiment, by its name relative to the tries dir
+    #[serde(default)]
+    meta: BTreeMap<String, String>,
+}
+
+// `try-rs export <file>`: writes the config and state into a single TOML file
+pub fn run_export(settings: &Settings, file: &Path) -> Result<()> {
+    let config = match &settings.config_file {
+        Some(path) => {
+            Some(fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?)
+        }
+        None => None,
+    };
+    let state = EXPORTED_STATE_FILES
+        .iter()
+        .filter_map(|name| {
+            let contents = fs::read_to_string(settings.paths.state_file(name)).ok()?;
+            Some((name.to_string(), contents))
+        })
+        .collect::<BTreeMap<_, _>>();
+    let meta = scan_entries(
+        &settings.tries_dir,
+        settings.scan_depth,
+        &settings.detectors,
+    )
+    .into_iter()
+    .filter_map(|entry| {
+        let contents = fs::read_to_string(entry.path.join(META_FILE)).ok()?;
+        Some((entry.name, contents))
+    })
+    .collect::<BTreeMap<_, _>>();
+
+    let bundle = Bundle {
+        version: BUNDLE_VERSION,
+        exported_at: chrono::Local::now().to_rfc3339(),
+        config,
+        tries_dir: std::path::absolute(&settings.tries_dir).ok(),
+        state,
+        meta,
+    };
+    fs::write(file, toml::to_string(&bundle)?)
+        .with_context(|| format!("writing {}", file.display()))?;
+
+    eprintln!(
+        "Exported the config, {} state file(s) and the notes of {} experiment(s) to {}",
+        bundle.state.len(),
+        bundle.meta.len(),
+        file.display()
+    );
+    Ok(())
+}
+
+// `try-rs import <file>`: restores a bundle written by `export`.
+// Files that would be replaced are kept next to the original with a `.bak` suffix.
+pub fn run_import(settings: &Settings, file: &Path) -> Result<()> {
+    let contents =
+        fs::read_to_string(file).with_context(|| format!("reading {}", file.display()))?;
+    let bundle: Bundle =
+        toml::from_str(&contents).with_context(|| format!("parsing {}", file.display()))?;
+    if bundle.version > BUNDLE_VERSION {
+        bail!(
+            "{} was exported by a newer try-rs (bundle version {})",
+            file.display(),
+            bundle.version
+        );
+    }
+
+    if let Some(config) = &bundle.config {
+        let target = settings
+            .config_file
+            .clone()
+            .unwrap_or_else(|| app_config_dir().join(get_file_config_toml_name()));
+        restore(&target, config)?;
+    }
+    let tries_dir = std::path::absolute(&settings.tries_dir)?;
+    for (name, contents) in &bundle.state {
+        // Only known state files, so a crafted bundle can't write elsewhere
+        if !EXPORTED_STATE_FILES.contains(&name.as_str()) {
+            eprintln!("Skipping unknown state file '{}'", name);
+            continue;
+        }
+        let contents = match &bundle.tries_dir {
+            Some(from) if *from != tries_dir => relocate(name, contents, from, &tries_dir),
+            _ => contents.clone(),
+        };
+        restore(&settings.paths.state_file(name), &contents)?;
+    }
+    for (name, contents) in &bundle.meta {
+        import_meta(&tries_dir, name, contents)?;
+    }
+
+    eprintln!(
+        "Imported the data exported on {}",
+        bundle.exported_at.get(..10).unwrap_or(&bundle.exported_at)
+    );
+    Ok(())
+}
+
+// Points the paths in the favorites and visits files, written with `from` as the
+// tries dir, at `to`; paths outside of it and the other files are left alone
+fn relocate(name: &str, contents: &str, from: &Path, to: &Path) -> String {
+    if name != FAVORITES_FILE && name != VISITS_FILE {
+        return contents.to_string();
+    }
+    contents
+        .lines()
+        .map(|line| {
+            // A visit is `timestamp<TAB>path`, a favorite just the path
+            let (stamp, path) = match line.split_once('\t') {
+                Some((stamp, path)) if name == VISITS_FILE => (Some(stamp), path),
+                _ => (None, line),
+            };
+            let path = match Path::new(path).strip_prefix(from) {
+                Ok(rest) => to.join(rest).to_string_lossy().into_owned(),
+                Err(_) => path.to_string(),
+            };
+            match stamp {
+                Some(stamp) => format!("{}\t{}\n", stamp, path),
+                None => format!("{}\n", path),
+            }
+        })
+        .collect()
+}
+
+// Gives the experiment `name` its exported description and notes, if it exists
+// here. Notes it already has are kept when they differ: they are newer or unrelated.
+fn import_meta(tries_dir: &Path, name: &str, contents: &str) -> Result<()> {
+    let dir = tries_dir.join(name);
+    // Only names inside the tries dir, so a crafted bundle can't write elsewhere
+    if !stays_inside(name) || !dir.is_dir() {
+        eprintln!("Skipping the notes of '{}', which doesn't exist here", name);
+        return Ok(());
+    }
+    let imported: Meta =
+        toml::from_str(contents).with_context(|| format!("parsing the notes of '{}'", name))?;
+    let current = meta::load(&dir);
+    if current == imported {
+        return Ok(());
+    }
+    if !current.is_empty() {
+        eprintln!("Kept the notes '{}' already has", name);
+        return Ok(());
+    }
+    meta::save(&dir, &imported).with_context(|| format!("writing the notes of '{}'", name))?;
+    eprintln!("Restored the notes of '{}'", name);
+    Ok(())
+}
+
+// Writes `contents` to `target`, backing up a different existing file first
+fn restore(target: &Path, contents: &str) -> Result<()> {
+    if let Ok(existing) = fs::read_to_string(target) {
+        if existing == contents {
+            return Ok(());
+        }
+        let mut backup = target.as_os_str().to_owned();
+        backup.push(".bak");
+        fs::rename(target, &backup)?;
+        eprintln!(
+            "Backed up {} to {}",
+            target.display(),
+            Path::new(&backup).display()
+        );
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(target, contents).with_context(|| format!("writing {}", target.display()))?;
+    eprintln!("Restored {}", target.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::favorites;
+    use std::collections::HashSet;
+
+    #[test]
+    fn import_points_favorites_and_notes_at_the_new_tries_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = Settings::for_tests(&dir.path().join("old"));
+        let new = Settings::for_tests(&dir.path().join("new"));
+        for settings in [&old, &new] {
+            fs::create_dir_all(settings.tries_dir.join("clients/acme")).unwrap();
+        }
+        let acme = old.tries_dir.join("clients/acme");
+        let notes = Meta {
+            description: "client work".to_string(),
+            notes: "call back".to_string(),
+        };
+        meta::save(&acme, &notes).unwrap();
+        let outside = dir.path().join("elsewhere");
+        favorites::save(&old.paths, &HashSet::from([acme, outside.clone()])).unwrap();
+
+        let bundle = dir.path().join("bundle.toml");
+        let mut old = old;
+        old.scan_depth = 2;
+        run_export(&old, &bundle).unwrap();
+        run_import(&new, &bundle).unwrap();
+
+        let moved = new.tries_dir.join("clients/acme");
+        assert_eq!(meta::load(&moved), notes);
+        assert_eq!(favorites::load(&new.paths), HashSet::from([moved, outside]));
+    }
+
+    #[test]
+    fn import_skips_notes_outside_the_tries_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let tries = dir.path().join("tries");
+        fs::create_dir_all(&tries).unwrap();
+        import_meta(&tries, "..", "description = \"x\"").unwrap();
+        assert!(!dir.path().join(META_FILE).exists());
+    }
+}
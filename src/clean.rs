@@ -0,0 +1,365 @@
+use crate::config::Settings;
+use crate::detect::Detectors;
+use crate::favorites;
+use crate::git_state;
+use crate::hooks::Hooks;
+use crate::ops;
+use crate::tui::{TryEntry, scan_entries};
+use crate::utils::{SIZE_LIMITS, dir_sizes, format_size, parse_age};
+use anyhow::{Result, bail};
+use std::collections::HashSet;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// The `[retention]` section of config.toml: when an experiment counts as expired
+#[derive(Clone)]
+pub struct Retention {
+    pub max_age: Option<Duration>, // Not modified for this long
+    pub max_count: Option<usize>,  // Only the most recently modified ones are kept
+    pub exclude_git_dirty: bool,   // Never expire repositories with uncommitted changes
+    pub prompt: bool,              // Offer to clean up before the TUI opens
+}
+
+// Uncommitted changes are kept unless the config says otherwise
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            max_count: None,
+            exclude_git_dirty: true,
+            prompt: false,
+        }
+    }
+}
+
+impl Retention {
+    fn has_rules(&self) -> bool {
+        self.max_age.is_some() || self.max_count.is_some()
+    }
+}
+
+// Experiments `retention` expires, oldest first, each with the rule it broke.
+// Favorites never expire and don't count towards `max_count`.
+pub fn expired(
+    tries_dir: &Path,
+    scan_depth: usize,
+    detectors: &Detectors,
+    retention: &Retention,
+    favorites: &HashSet<PathBuf>,
+) -> Vec<(TryEntry, String)> {
+    let now = SystemTime::now();
+    let mut entries = scan_entries(tries_dir, scan_depth, detectors);
+    entries.retain(|e| !favorites.contains(&e.path));
+    entries.sort_by_key(|e| std::cmp::Reverse(e.modified));
+    let mut expired: Vec<(TryEntry, String)> = entries
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, entry)| {
+            let age = now.duration_since(entry.modified).unwrap_or(Duration::ZERO);
+            let reason = if let Some(max_age) = retention.max_age.filter(|&max| age >= max) {
+                format!("older than {}", age_label(max_age))
+            } else if let Some(max_count) = retention.max_count.filter(|&max| i >= max) {
+                format!("beyond the newest {}", max_count)
+            } else {
+                return None;
+            };
+            Some((entry, reason))
+        })
+        .filter(|(entry, _)| {
+            !(retention.exclude_git_dirty && entry.is_git() && git_dirty(&entry.path))
+        })
+        .collect();
+    expired.reverse();
+    expired
+}
+
+// Writes `age` back in the units `parse_age` reads, e.g. 30d or 12h
+fn age_label(age: Duration) -> String {
+    let secs = age.as_secs();
+    [(86400, "d"), (3600, "h"), (60, "m")]
+        .into_iter()
+        .find(|(unit, _)| secs.is_multiple_of(*unit))
+        .map_or(format!("{}s", secs), |(unit, suffix)| {
+            format!("{}{}", secs / unit, suffix)
+        })
+}
+
+// Whether the repository has uncommitted changes; unreadable ones count as dirty
+fn git_dirty(path: &Path) -> bool {
+    git_state::probe(path).is_none_or(|status| status.dirty)
+}
+
+// With `prompt` set in `[retention]`, asks on stderr whether to remove the
+// expired experiments before the TUI opens
+pub fn prompt_expired(
+    tries_dir: &Path,
+    scan_depth: usize,
+    detectors: &Detectors,
+    retention: &Retention,
+    favorites: &HashSet<PathBuf>,
+    hooks: &Hooks,
+) -> Result<()> {
+    if !retention.prompt || !retention.has_rules() {
+        return Ok(());
+    }
+    let expired = expired(tries_dir, scan_depth, detectors, retention, favorites);
+    if expired.is_empty() {
+        return Ok(());
+    }
+    eprint!(
+        "{} experiment(s) are past the retention policy (oldest: {}). Move them to the trash now? [y/N] ",
+        expired.len(),
+        expired[0].0.name
+    );
+    io::stderr().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().eq_ignore_ascii_case("y") {
+        remove(expired, tries_dir, hooks, false, false);
+    }
+    Ok(())
+}
+
+// `try-rs clean` in the tries dir of `settings`. `older_than` (from --older-than)
+// replaces the age and count rules of `[retention]`. Lists the candidates and asks
+// before deleting them, unless `yes`.
+pub fn run_clean(
+    settings: &Settings,
+    older_than: Option<&str>,
+    dry_run: bool,
+    sizes: bool,
+    yes: bool,
+) -> Result<()> {
+    let mut policy = settings.retention.clone();
+    if let Some(older_than) = older_than {
+        let Some(max_age) = parse_age(older_than) else {
+            bail!("Invalid age '{}' (expected e.g. 30d, 12h, 8w)", older_than);
+        };
+        policy.max_age = Some(max_age);
+        policy.max_count = None;
+    }
+    if !policy.has_rules() {
+        bail!(
+            "Nothing to clean by: pass --older-than or set max_age_days / max_count in [retention]"
+        );
+    }
+
+    let favorites = favorites::load(&settings.paths);
+    let candidates = expired(
+        &settings.tries_dir,
+        settings.scan_depth,
+        &settings.detectors,
+        &policy,
+        &favorites,
+    );
+    if candidates.is_empty() {
+        match older_than {
+            Some(older_than) => eprintln!("Nothing older than {}.", older_than),
+            None => eprintln!("Nothing past the retention policy."),
+        }
+        return Ok(());
+    }
+    if !dry_run && !yes {
+        if !io::stdin().is_terminal() {
+            bail!("Refusing to delete without confirmation: pass --yes (or --dry-run to list)");
+        }
+        for (entry, reason) in &candidates {
+            eprintln!("  {}  ({})", entry.name, reason);
+        }
+        eprint!("Delete these {} experiment(s)? [y/N] ", candidates.len());
+        io::stderr().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("y") {
+            return Ok(());
+        }
+    }
+    remove(
+        candidates,
+        &settings.tries_dir,
+        &settings.hooks,
+        dry_run,
+        sizes,
+    );
+    Ok(())
+}
+
+// Moves `candidates` to the trash of `tries_dir` (or with `dry_run` only lists them),
+// optionally with their sizes. Experiments whose delete hooks fail are kept, and so
+// are worktrees git refuses to remove.
+fn remove(
+    mut candidates: Vec<(TryEntry, String)>,
+    tries_dir: &Path,
+    hooks: &Hooks,
+    dry_run: bool,
+    sizes: bool,
+) {
+    let now = SystemTime::now();
+
+    // Pair every candidate with its size (if requested), largest first
+    let mut rows: Vec<_> = if sizes {
+        let paths: Vec<_> = candidates.iter().map(|(e, _)| e.path.clone()).collect();
+        let computed = dir_sizes(&paths, SIZE_LIMITS);
+        candidates
+            .drain(..)
+            .zip(computed.into_iter().map(Some))
+            .collect()
+    } else {
+        candidates.drain(..).map(|e| (e, None)).collect()
+    };
+    if sizes {
+        rows.sort_by_key(|(_, size)| std::cmp::Reverse(size.map(|s| s.bytes)));
+    }
+
+    let mut total = 0;
+    let mut partial = false;
+    for ((entry, reason), size) in &rows {
+        let days = now
+            .duration_since(entry.modified)
+            .unwrap_or_default()
+            .as_secs()
+            / 86400;
+        let size_col = match size {
+            Some(s) => {
+                total += s.bytes;
+                partial |= !s.complete;
+                let marker = if s.complete { "" } else { "+" };
+                format!("{:>10}{:1}  ", format_size(s.bytes), marker)
+            }
+            None => String::new(),
+        };
+
+        if dry_run {
+            eprintln!("{}{:>5}d  {}  ({})", size_col, days, entry.name, reason);
+            continue;
+        }
+        match ops::delete(hooks, tries_dir, &entry.path, false, false) {
+            Ok(Some(_)) => eprintln!(
+                "{}{:>5}d  Trashed: {}  ({})",
+                size_col, days, entry.name, reason
+            ),
+            Ok(None) => eprintln!(
+                "{}{:>5}d  Removed worktree: {}  ({})",
+                size_col, days, entry.name, reason
+            ),
+            Err(e) => eprintln!("{}{:>5}d  Kept {}: {:#}", size_col, days, entry.name, e),
+        }
+    }
+
+    if sizes {
+        let verb = if dry_run { "Would free" } else { "Freed" };
+        let approx = if partial { " (at least)" } else { "" };
+        eprintln!(
+            "{} {}{} across {} experiments.",
+            verb,
+            format_size(total),
+            approx,
+            rows.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TRASH_DIR;
+    use std::fs;
+    use std::process::Command;
+
+    const DAY: Duration = Duration::from_secs(86400);
+
+    // An experiment last modified `days` ago
+    fn experiment(root: &Path, name: &str, days: u32) -> PathBuf {
+        let dir = root.join(name);
+        fs::create_dir(&dir).unwrap();
+        let modified = SystemTime::now() - DAY * days;
+        fs::File::open(&dir)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        dir
+    }
+
+    fn names(expired: &[(TryEntry, String)]) -> Vec<&str> {
+        expired.iter().map(|(e, _)| e.name.as_str()).collect()
+    }
+
+    fn run(root: &Path, retention: &Retention, favorites: &HashSet<PathBuf>) -> Vec<String> {
+        let expired = expired(root, 1, &Detectors::default(), retention, favorites);
+        names(&expired).into_iter().map(String::from).collect()
+    }
+
+    #[test]
+    fn expires_by_age_and_count_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        experiment(dir.path(), "new", 1);
+        experiment(dir.path(), "mid", 10);
+        experiment(dir.path(), "old", 40);
+        let by_age = Retention {
+            max_age: Some(DAY * 30),
+            ..Retention::default()
+        };
+        assert_eq!(run(dir.path(), &by_age, &HashSet::new()), ["old"]);
+        let by_count = Retention {
+            max_count: Some(1),
+            ..Retention::default()
+        };
+        assert_eq!(run(dir.path(), &by_count, &HashSet::new()), ["old", "mid"]);
+    }
+
+    #[test]
+    fn favorites_never_expire_nor_count() {
+        let dir = tempfile::tempdir().unwrap();
+        let star = experiment(dir.path(), "star", 50);
+        experiment(dir.path(), "new", 1);
+        experiment(dir.path(), "old", 40);
+        let retention = Retention {
+            max_age: Some(DAY * 30),
+            max_count: Some(1),
+            ..Retention::default()
+        };
+        assert_eq!(run(dir.path(), &retention, &HashSet::from([star])), ["old"]);
+    }
+
+    #[test]
+    fn dirty_repositories_are_kept_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = experiment(dir.path(), "repo", 0);
+        let ok = Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(&repo)
+            .status()
+            .unwrap()
+            .success();
+        assert!(ok);
+        fs::write(repo.join("wip"), "").unwrap();
+        let mut retention = Retention {
+            max_count: Some(0),
+            ..Retention::default()
+        };
+        assert!(run(dir.path(), &retention, &HashSet::new()).is_empty());
+        retention.exclude_git_dirty = false;
+        assert_eq!(run(dir.path(), &retention, &HashSet::new()), ["repo"]);
+    }
+
+    #[test]
+    fn remove_moves_to_the_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        experiment(dir.path(), "old", 40);
+        let retention = Retention {
+            max_age: Some(DAY),
+            ..Retention::default()
+        };
+        let expired = expired(
+            dir.path(),
+            1,
+            &Detectors::default(),
+            &retention,
+            &HashSet::new(),
+        );
+        remove(expired, dir.path(), &Hooks::default(), false, false);
+        assert!(!dir.path().join("old").exists());
+        assert!(dir.path().join(TRASH_DIR).join("old").is_dir());
+    }
+}
@@ -1,30 +1,279 @@
-use clap::{Parser, ValueEnum};
+use crate::completions::experiment_names;
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::engine::ArgValueCompleter;
+use std::path::PathBuf;
+use try_rs::archive::ArchiveFormat;
+use try_rs::tui::SortMode;
 
 #[derive(Parser)]
 #[command(name = "try-rs")]
 #[command(about = format!("🦀 try-rs {} 🦀\nA blazing fast, Rust-based workspace manager for your temporary experiments.", env!("CARGO_PKG_VERSION")), long_about = None)]
 #[command(version = env!("CARGO_PKG_VERSION"))]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Cli {
     /// Create or jump to an experiment / Clone a repo. Starts the TUI (Terminal User Interface) if omitted.
-    #[arg(value_name = "NAME_OR_URL")]
+    #[arg(value_name = "NAME_OR_URL", add = ArgValueCompleter::new(experiment_names))]
     pub name_or_url: Option<String>,
 
-    /// Generate shell integration code
+    /// Install the shell integration and load it from the shell's startup file (see `init`
+    /// to only print it)
     #[arg(long)]
     pub setup: Option<Shell>,
 
-    /// Shallow clone
+    /// Shell the output is meant for (appended by the shell integration, valid after subcommands)
+    #[arg(long, value_name = "SHELL", global = true)]
+    pub shell: Option<Shell>,
+
+    /// Shallow clone (depth 1, overriding `clone_depth`)
     #[arg(short, long)]
     pub shallow_clone: bool,
+
+    /// Branch or tag to clone (only that branch is fetched)
+    #[arg(short, long, value_name = "REF")]
+    pub branch: Option<String>,
+
+    /// Partial clone filter passed to git, e.g. `blob:none` to fetch file contents on demand
+    #[arg(long, value_name = "SPEC")]
+    pub filter: Option<String>,
+
+    /// Check out only this directory of the repository (repeatable) and enter the first one
+    #[arg(long, value_name = "DIR", requires = "name_or_url")]
+    pub sparse: Vec<String>,
+
+    /// Open the experiment (or the repository just cloned) in the editor instead of changing into it
+    #[arg(short, long, requires = "name_or_url", conflicts_with = "print_path")]
+    pub open: bool,
+
+    /// Print the bare path of the chosen experiment instead of a command for the shell,
+    /// e.g. `code "$(try-rs -p)"`
+    #[arg(short, long, global = true)]
+    pub print_path: bool,
+
+    /// Log config discovery, path resolution and scans to stderr (-vv for more detail)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Directory holding the experiments for this run, overriding TRY_PATH and the config
+    #[arg(long, value_name = "DIR", global = true)]
+    pub tries_path: Option<PathBuf>,
+
+    /// Use the `[[roots]]` entry of this name as the experiments directory
+    #[arg(
+        long,
+        value_name = "NAME",
+        global = true,
+        conflicts_with = "tries_path"
+    )]
+    pub root: Option<String>,
+
+    /// Color theme for this run (see `try-rs themes`), overriding `theme` in the config
+    #[arg(long, value_name = "NAME", global = true)]
+    pub theme: Option<String>,
+
+    /// Editor command to use for this run, overriding TRY_EDITOR and the config
+    #[arg(long, value_name = "CMD")]
+    pub editor: Option<String>,
+
+    /// Create an auto-named scratch experiment (see `auto_name` in config.toml)
+    #[arg(long, conflicts_with = "name_or_url")]
+    pub scratch: bool,
+
+    /// Pick from a numbered list instead of the TUI (automatic when TERM=dumb)
+    #[arg(long, conflicts_with = "name_or_url")]
+    pub plain: bool,
+
+    /// Print the experiment names instead of opening the TUI
+    #[arg(long, conflicts_with = "name_or_url")]
+    pub list: bool,
+
+    /// Order of the --list output
+    #[arg(long, value_name = "MODE", requires = "list")]
+    pub sort: Option<SortMode>,
+
+    /// Print version, commit hash and build date as JSON
+    #[arg(long)]
+    pub build_info: bool,
+
+    /// Print the resolved configuration, state and cache locations
+    #[arg(long)]
+    pub doctor: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Delete experiments past the [retention] policy, or not modified recently
+    Clean {
+        /// Minimum age of the experiments to remove (e.g. 30d, 12h, 8w), instead of [retention]
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+
+        /// Only list what would be removed
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Compute the size of each candidate and the total to be freed
+        #[arg(long)]
+        sizes: bool,
+
+        /// Delete without asking first
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Print the absolute path of an experiment (exit code 1 if not found, 2 if ambiguous)
+    Path {
+        /// Exact name, or a fuzzy query matching a single experiment
+        #[arg(add = ArgValueCompleter::new(experiment_names))]
+        name: String,
+
+        /// Create the experiment if no exact match exists
+        #[arg(long)]
+        create: bool,
+    },
+    /// Print the path of the experiment the TUI search would rank first (exit code 1 if
+    /// nothing matches; ties are offered as a numbered choice, or exit with 2 without a terminal)
+    Pick {
+        /// Fuzzy query, with the same filters as the search box (e.g. `lang:rust api`)
+        query: Vec<String>,
+    },
+    /// Create a new experiment, optionally filled from a template, and enter it
+    New {
+        /// Name of the experiment (must not exist yet)
+        name: String,
+
+        /// Template from [templates] in config.toml or a directory under templates/
+        #[arg(short, long, value_name = "NAME")]
+        template: Option<String>,
+    },
+    /// Create an experiment as a git worktree of an existing repository and enter it
+    Worktree {
+        /// Repository to add the worktree to
+        repo: PathBuf,
+
+        /// Branch to check out, created from the repository's HEAD if it doesn't exist
+        branch: String,
+
+        /// Name of the experiment (defaults to <repo>-<branch>)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Enter the experiment with exactly this name; never clones or creates anything
+    Jump {
+        /// Exact experiment name
+        #[arg(add = ArgValueCompleter::new(experiment_names))]
+        name: String,
+    },
+    /// Move a deleted experiment back out of the trash, or list the trash
+    Restore {
+        /// Name in the trash; without it, the trash is listed
+        name: Option<String>,
+
+        /// Delete everything in the trash for good instead
+        #[arg(long, conflicts_with = "name")]
+        empty: bool,
+
+        /// Empty the trash without asking first
+        #[arg(short, long, requires = "empty")]
+        yes: bool,
+    },
+    /// Print the experiments, one name per line or as JSON
+    List {
+        /// Print a JSON array with the path, dates and project types of each experiment
+        #[arg(long)]
+        json: bool,
+
+        /// Order of the output
+        #[arg(long, value_name = "MODE")]
+        sort: Option<SortMode>,
+    },
+    /// Rename an experiment (follows it with a `cd` when the shell is inside it)
+    Rename {
+        /// Exact name of the experiment
+        #[arg(add = ArgValueCompleter::new(experiment_names))]
+        old: String,
+
+        /// New name, which must not exist yet
+        new: String,
+    },
+    /// Compress an experiment into .archive/<name>-<date> and remove the directory
+    Archive {
+        /// Exact experiment name
+        #[arg(add = ArgValueCompleter::new(experiment_names))]
+        name: String,
+
+        /// Archive format (defaults to `archive_format` in config.toml, else tar.gz)
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<ArchiveFormat>,
+    },
+    /// Unpack an archived experiment back into place, or list the archives
+    Unarchive {
+        /// Archive file name, with or without its extension; without it, the archives are listed
+        name: Option<String>,
+    },
+    /// Save the config and state (clone history, ...) to a file, without the experiments
+    Export {
+        /// File to write
+        file: PathBuf,
+    },
+    /// Restore the config and state saved by `export` (replaced files are kept as .bak)
+    Import {
+        /// File written by `export`
+        file: PathBuf,
+    },
+    /// List past clones, or reopen one (re-cloning it if the directory was deleted)
+    Clones {
+        /// Directory name or URL of a past clone
+        name: Option<String>,
+
+        /// Shallow clone when re-cloning
+        #[arg(short, long)]
+        shallow_clone: bool,
+    },
+    /// List the experiments entered most recently, or enter one of them again
+    Recent {
+        /// Position in the list (1 is the last one entered); without it, the list is printed
+        number: Option<usize>,
+
+        /// How many experiments to list
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Print a script completing commands, options and experiment names, e.g.
+    /// `source <(command try-rs completions bash)`
+    Completions { shell: Shell },
+    /// Print the names of the built-in color themes
+    Themes,
+    /// Print the shell integration to stdout instead of installing it like --setup,
+    /// e.g. `eval "$(try-rs init zsh)"`
+    Init { shell: Shell },
+    /// Print the experiment names for shell completion, without probing them
+    #[command(hide = true)]
+    CompleteNames,
 }
 
 #[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Shell {
     Fish,
     Zsh,
+    #[value(alias = "posix", alias = "sh")]
     Bash,
     #[allow(clippy::enum_variant_names)]
+    #[value(alias = "nu")]
     NuShell,
     #[allow(clippy::enum_variant_names)]
+    #[value(alias = "powershell", alias = "pwsh")]
     PowerShell,
+    Elvish,
+    Xonsh,
+    #[value(alias = "csh")]
+    Tcsh,
+}
+
+impl Shell {
+    // Whether the wrapper evals a command line; Nushell and xonsh get a JSON record instead
+    pub fn evals(self) -> bool {
+        !matches!(self, Shell::NuShell | Shell::Xonsh)
+    }
 }
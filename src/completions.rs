@@ -0,0 +1,132 @@
+use crate::cli::{Cli, Shell};
+use anyhow::{Context, Result, bail};
+use clap::CommandFactory;
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::{Bash, CompleteEnv, Elvish, EnvCompleter, Fish, Powershell, Zsh};
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::Path;
+use try_rs::Scanner;
+use try_rs::config::peek_tries_dir;
+
+// Completions are dynamic: the script printed by `try-rs completions <shell>` calls
+// the binary back with COMPLETE set, and the binary answers from its own CLI
+// definition, listing the experiments on disk for name arguments.
+const COMPLETE_VAR: &str = "COMPLETE";
+
+// Nushell isn't covered by clap_complete's scripts; its wrapper from `--setup nu-shell`
+// completes its arguments with this command
+pub const NUSHELL_COMPLETER: &str = r#"# Experiment names for the try-rs wrapper
+def "nu-complete try-rs" [] {
+    ^try-rs complete-names | lines
+}
+"#;
+
+// Answers a completion request and exits; a regular run returns right away.
+// Must run before anything is printed.
+pub fn handle_request() {
+    CompleteEnv::with_factory(Cli::command)
+        .var(COMPLETE_VAR)
+        .complete();
+}
+
+// `try-rs completions <shell>`: prints the script registering the completions
+pub fn print_script(shell: Shell) -> Result<()> {
+    let registration: &dyn EnvCompleter = match shell {
+        Shell::Bash => &Bash,
+        Shell::Zsh => &Zsh,
+        Shell::Fish => &Fish,
+        Shell::PowerShell => &Powershell,
+        Shell::Elvish => &Elvish,
+        Shell::NuShell => {
+            print!("{}", NUSHELL_COMPLETER);
+            return Ok(());
+        }
+        Shell::Xonsh | Shell::Tcsh => bail!("No completions for {:?} yet", shell),
+    };
+    // The wrapper function is called try-rs too, so the script calls the binary by path
+    let exe = std::env::current_exe().context("locating the try-rs binary")?;
+    let mut script = Vec::new();
+    registration.write_registration(
+        COMPLETE_VAR,
+        "try-rs",
+        "try-rs",
+        &exe.to_string_lossy(),
+        &mut script,
+    )?;
+    std::io::stdout().write_all(&script)?;
+    Ok(())
+}
+
+// `try-rs complete-names`: every experiment name, one per line, without probing
+// the directories
+pub fn print_names(tries_path: Option<&Path>, root: Option<&str>) {
+    for name in names(tries_path, root) {
+        println!("{}", name);
+    }
+}
+
+// Candidates for an experiment name argument starting with `current`. The command
+// line being completed is in our own arguments, --tries-path and --root included.
+pub fn experiment_names(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    let args: Vec<String> = std::env::args().collect();
+    let tries_path = flag_value(&args, "--tries-path");
+    let root = flag_value(&args, "--root");
+    names(tries_path.as_deref().map(Path::new), root.as_deref())
+        .into_iter()
+        .filter(|name| name.starts_with(current.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+// The experiment names, read without touching the config or the state
+fn names(tries_path: Option<&Path>, root: Option<&str>) -> Vec<String> {
+    let Some((tries_dir, depth)) = peek_tries_dir(tries_path, root) else {
+        return Vec::new();
+    };
+    Scanner::new(&tries_dir).with_depth(depth).names()
+}
+
+// The value of the last `--flag value` or `--flag=value` in `args`
+fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    let mut value = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            value = args.next().cloned();
+        } else if let Some(v) = arg
+            .strip_prefix(flag)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            value = Some(v.to_string());
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn flag_value_reads_both_forms() {
+        let line = args("try-rs -- try-rs --tries-path /a rename fo");
+        assert_eq!(flag_value(&line, "--tries-path").as_deref(), Some("/a"));
+        assert_eq!(flag_value(&line, "--root"), None);
+
+        let line = args("try-rs -- try-rs --root=work path");
+        assert_eq!(flag_value(&line, "--root").as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn flag_value_ignores_lookalikes_and_takes_the_last() {
+        let line = args("try-rs --rooted x --root a --root b");
+        assert_eq!(flag_value(&line, "--root").as_deref(), Some("b"));
+        assert_eq!(flag_value(&args("try-rs --root"), "--root"), None);
+    }
+}
@@ -1,12 +1,44 @@
-use crate::tui::Theme;
-use crate::utils::expand_path;
+use crate::archive::ArchiveFormat;
+use crate::clean::Retention;
+use crate::detect::{DetectorConfig, Detectors};
+use crate::git::CloneOptions;
+use crate::hooks::{Hooks, hooks_dir};
+use crate::icons::{IconMode, Icons, IconsConfig};
+use crate::integrations::{Custom, Integration, Placement};
+use crate::keys::{Chords, KeyMap};
+use crate::paths::{Paths, app_config_dir, config_candidates};
+use crate::templates::{Templates, templates_dir};
+use crate::theme::{ColorDepth, DEFAULT_THEME, Theme};
+use crate::tmux::{Tmux, TmuxMode};
+use crate::tui::{Column, EscBehavior, SortMode};
+use crate::update::DEFAULT_UPDATE_URL;
+use crate::utils::{DEFAULT_AUTO_NAME, expand_path, project_editor};
+use anyhow::{Context, Result, bail};
+use log::debug;
 use ratatui::style::Color;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
+use toml_edit::DocumentMut;
 
-#[derive(Deserialize)]
+// How often the TUI redraws while idle, unless `tick_rate` (in ms) says otherwise
+const DEFAULT_TICK_RATE: Duration = Duration::from_secs(1);
+const MIN_TICK_RATE_MS: u64 = 50;
+
+// How long footer messages stay up, unless `status_timeout` (in s, 0 = forever) says otherwise
+const DEFAULT_STATUS_TIMEOUT: Duration = Duration::from_secs(4);
+
+// Marker of the selected list row, unless `highlight_symbol` says otherwise
+const DEFAULT_HIGHLIGHT_SYMBOL: &str = "→ ";
+const ASCII_HIGHLIGHT_SYMBOL: &str = "> "; // With `icons = "ascii"` or "none"
+
+// Command that compares two marked experiments, unless `diff_tool` says otherwise
+const DEFAULT_DIFF_TOOL: &str = "git diff --no-index";
+
+#[derive(Deserialize, Clone, Default)]
 pub struct ThemeConfig {
     pub title_try: Option<String>,
     pub title_rs: Option<String>,
@@ -20,125 +52,873 @@ pub struct ThemeConfig {
     pub popup_text: Option<String>,
 }
 
+impl ThemeConfig {
+    /// The preset `name` with these colors replaced, for a terminal of `depth`
+    pub fn theme(&self, name: &str, depth: ColorDepth) -> Theme {
+        let mut theme = Theme::from_preset(name).unwrap_or_default();
+        self.apply(&mut theme);
+        theme.adapted(depth)
+    }
+
+    /// Replaces the colors of `theme` that are set here (and valid)
+    pub fn apply(&self, theme: &mut Theme) {
+        let set = |color: &mut Color, value: &Option<String>| {
+            if let Some(parsed) = value.as_deref().and_then(|s| Color::from_str(s).ok()) {
+                *color = parsed;
+            }
+        };
+        set(&mut theme.title_try, &self.title_try);
+        set(&mut theme.title_rs, &self.title_rs);
+        set(&mut theme.search_box, &self.search_box);
+        set(&mut theme.list_date, &self.list_date);
+        set(&mut theme.list_highlight_bg, &self.list_highlight_bg);
+        set(&mut theme.list_highlight_fg, &self.list_highlight_fg);
+        set(&mut theme.help_text, &self.help_text);
+        set(&mut theme.status_message, &self.status_message);
+        set(&mut theme.popup_bg, &self.popup_bg);
+        set(&mut theme.popup_text, &self.popup_text);
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RetentionConfig {
+    pub max_age_days: Option<u64>,
+    pub max_count: Option<usize>,
+    pub exclude_git_dirty: Option<bool>,
+    pub prompt: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct TmuxConfig {
+    pub mode: Option<String>,
+    pub session_prefix: Option<String>,
+}
+
+/// `[integrations.zellij]` and `[integrations.wezterm]`
+#[derive(Deserialize)]
+pub struct PlacementConfig {
+    pub mode: Option<String>,
+}
+
+/// `[[roots]]`: a named tries directory
+#[derive(Deserialize)]
+pub struct RootConfig {
+    pub name: String,
+    pub path: String,
+}
+
+/// `[integrations.<name>]` for any other name: a custom integration
+#[derive(Deserialize)]
+pub struct StepsConfig {
+    pub steps: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct IntegrationsConfig {
+    pub tmux: Option<TmuxConfig>,
+    pub zellij: Option<PlacementConfig>,
+    pub wezterm: Option<PlacementConfig>,
+    #[serde(flatten)]
+    pub custom: HashMap<String, StepsConfig>,
+}
+
 #[derive(Deserialize)]
 pub struct Config {
     pub tries_path: Option<String>,
+    pub theme: Option<String>,
     pub colors: Option<ThemeConfig>,
+    pub ascii_icons: Option<bool>,
+    pub icons: Option<IconsConfig>,
     pub editor: Option<String>,
+    pub editor_url_template: Option<String>,
+    pub diff_tool: Option<String>,
+    pub git_init_commit: Option<bool>,
+    pub highlight_symbol: Option<String>,
+    pub indent: Option<usize>,
+    pub state_dir: Option<String>,
+    pub cache_dir: Option<String>,
+    pub normalize_names: Option<bool>,
+    pub date_prefix: Option<bool>,
+    pub set_title: Option<bool>,
+    pub osc7: Option<bool>,
+    pub columns: Option<Vec<String>>,
+    pub auto_name: Option<String>,
+    pub on_select: Option<String>,
+    pub on_create: Option<String>,
+    pub on_enter: Option<String>,
+    pub on_delete: Option<String>,
+    pub esc_behavior: Option<String>,
+    pub sort: Option<String>,
+    pub update_check: Option<bool>,
+    pub update_url: Option<String>,
+    pub tick_rate: Option<u64>,
+    pub quick_select: Option<bool>,
+    pub status_timeout: Option<u64>,
+    pub confirm_clone: Option<bool>,
+    pub clone_host: Option<String>,
+    pub clone_depth: Option<u32>,
+    pub keys: Option<HashMap<String, Chords>>,
+    pub templates: Option<HashMap<String, String>>,
+    pub archive_format: Option<String>,
+    pub retention: Option<RetentionConfig>,
+    pub terminal_integration: Option<String>,
+    pub integrations: Option<IntegrationsConfig>,
+    pub roots: Option<Vec<RootConfig>>,
+    pub scan_depth: Option<usize>,
+    pub detectors: Option<Vec<DetectorConfig>>,
 }
 
 pub fn get_file_config_toml_name() -> String {
     std::env::var("TRY_CONFIG").unwrap_or("config.toml".to_string())
 }
 
-pub fn load_file_config_toml_if_exists() -> Option<Config> {
-    // 1. Check TRY_CONFIG_DIR environment variable
-    if let Some(env_dir) = std::env::var_os("TRY_CONFIG_DIR") {
-        let config_path = PathBuf::from(env_dir).join(get_file_config_toml_name());
-        if config_path.exists() {
-            if let Ok(contents) = fs::read_to_string(&config_path)
-                && let Ok(config) = toml::from_str::<Config>(&contents)
-            {
-                return Some(config);
+/// Returns the first config file found along with its location
+pub fn load_file_config_toml_if_exists() -> Option<(PathBuf, Config)> {
+    for config_path in config_candidates(&get_file_config_toml_name()) {
+        if !config_path.exists() {
+            debug!("{} not found", config_path.display());
+            continue;
+        }
+        let contents = match fs::read_to_string(&config_path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                debug!("skipping {}: {}", config_path.display(), e);
+                continue;
+            }
+        };
+        match toml::from_str::<Config>(&contents) {
+            Ok(config) => {
+                debug!("using {}", config_path.display());
+                return Some((config_path, config));
             }
+            Err(e) => debug!("skipping {}: {}", config_path.display(), e),
         }
     }
+    debug!("no config file found");
+    None
+}
 
-    // 2. Check XDG config dir (~/.config/try-rs/config.toml)
-    let config_dir_config_toml = dirs::config_dir()
-        .expect("Folder not found")
-        .join("try-rs")
-        .join(get_file_config_toml_name());
-
-    if config_dir_config_toml.exists() {
-        if let Ok(contents) = fs::read_to_string(&config_dir_config_toml)
-            && let Ok(config) = toml::from_str::<Config>(&contents)
-        {
-            return Some(config);
+/// Sets the top-level string `key` of the config file at `path` (creating the file if
+/// needed). Only that value changes: comments (also the one after it), formatting and
+/// every other key stay as written.
+pub fn set_config_string(path: &Path, key: &str, value: &str) -> Result<()> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+    };
+    let mut doc: DocumentMut = content
+        .parse()
+        .with_context(|| format!("parsing {}", path.display()))?;
+    match doc.get_mut(key).and_then(|item| item.as_value_mut()) {
+        Some(old) => {
+            let decor = old.decor().clone();
+            *old = value.into();
+            *old.decor_mut() = decor;
         }
+        None => doc[key] = toml_edit::value(value),
     }
+    fs::write(path, doc.to_string()).with_context(|| format!("writing {}", path.display()))
+}
 
-    // 3. Check ~/.try-rs/config.toml (legacy/alternative)
-    let home_dir_config_toml = dirs::home_dir()
-        .expect("Folder not found")
-        .join(".config")
-        .join("try-rs")
-        .join(get_file_config_toml_name());
+// The `mode` of a Zellij or WezTerm section, warning about unknown ones
+fn parse_placement(section: PlacementConfig, name: &str) -> Placement {
+    let Some(value) = section.mode else {
+        return Placement::default();
+    };
+    Placement::parse(&value).unwrap_or_else(|| {
+        eprintln!(
+            "Warning: unknown {} mode '{}' in config (expected one of: {})",
+            name,
+            value,
+            Placement::NAMES
+        );
+        Placement::default()
+    })
+}
 
-    if home_dir_config_toml.exists() {
-        if let Ok(contents) = fs::read_to_string(&home_dir_config_toml)
-            && let Ok(config) = toml::from_str::<Config>(&contents)
-        {
-            return Some(config);
-        }
+// Parses the `columns` list, warning about (and skipping) unknown names
+fn parse_columns(names: &[String]) -> Vec<Column> {
+    let columns: Vec<Column> = names
+        .iter()
+        .filter_map(|name| {
+            let column = Column::parse(name);
+            if column.is_none() {
+                eprintln!(
+                    "Warning: unknown column '{}' in config (expected one of: {})",
+                    name,
+                    Column::NAMES
+                );
+            }
+            column
+        })
+        .collect();
+    if columns.is_empty() {
+        Column::DEFAULT.to_vec()
+    } else {
+        columns
     }
+}
 
-    None
+/// A tries directory that --root picks or the TUI switches to
+#[derive(Clone)]
+pub struct Root {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Everything resolved from the environment and config file at startup
+pub struct Settings {
+    pub tries_dir: PathBuf,
+    /// `[[roots]]`, led by the tries path when it isn't one of them
+    pub roots: Vec<Root>,
+    /// Levels of subfolders searched for experiments (1: direct children)
+    pub scan_depth: usize,
+    /// Project kinds, built-in and `[[detectors]]`
+    pub detectors: Detectors,
+    pub theme: Theme,
+    /// The preset `theme` is built from
+    pub theme_name: String,
+    /// `[colors]`, applied on top of the preset
+    pub colors: ThemeConfig,
+    /// What the terminal can show; `theme` is already brought down to it
+    pub color_depth: ColorDepth,
+    /// Glyphs drawn in the list, preview and title
+    pub icons: Icons,
+    pub editor_cmd: Option<String>,
+    /// `editor_cmd` is from --editor or TRY_EDITOR, above `.try-rs-editor`
+    pub editor_forced: bool,
+    /// E.g. "vscode://file/{path}", opened with Ctrl+O
+    pub editor_url_template: Option<String>,
+    /// Runs with the two marked experiments appended
+    pub diff_tool: String,
+    /// Ctrl+G also commits everything as "initial"
+    pub git_init_commit: bool,
+    /// Drawn before the selected list row
+    pub highlight_symbol: String,
+    /// Spaces before every list row
+    pub indent: usize,
+    pub is_first_run: bool,
+    /// The config file that was loaded (or just created), if any
+    pub config_file: Option<PathBuf>,
+    pub normalize_names: bool,
+    /// New experiments are named `YYYY-MM-DD-<name>`
+    pub date_prefix: bool,
+    /// Name the terminal after the selected experiment
+    pub set_title: bool,
+    /// Report the new working directory to the terminal
+    pub osc7: bool,
+    pub columns: Vec<Column>,
+    /// Strftime template for scratch experiments
+    pub auto_name: String,
+    /// Evaluated instead of `cd {path}` when entering an experiment
+    pub on_select: Option<String>,
+    /// --print-path: the bare path instead of a command to eval
+    pub print_path: bool,
+    /// On_create / on_enter / on_delete commands
+    pub hooks: Hooks,
+    pub esc_behavior: EscBehavior,
+    /// Initial TUI order and default of --list/--sort
+    pub sort_mode: SortMode,
+    /// Alt+1..9 picks one of the first nine entries
+    pub quick_select: bool,
+    /// Opt-in daily check for a newer release
+    pub update_check: bool,
+    pub update_url: String,
+    /// Idle refresh interval of the TUI
+    pub tick_rate: Duration,
+    /// None keeps status messages until typing
+    pub status_timeout: Option<Duration>,
+    /// Ask before cloning a URL picked in the TUI
+    pub confirm_clone: bool,
+    /// Where bare `owner/repo` shorthands are cloned from; without it they name experiments
+    pub clone_host: Option<String>,
+    /// Depth (from `clone_depth`), branch and filter of clones
+    pub clone_options: CloneOptions,
+    /// Key chords of the list actions, from the `[keys]` section
+    pub keymap: KeyMap,
+    /// `[templates]` commands and the skeleton directories next to the config file
+    pub templates: Templates,
+    /// How Ctrl+A compresses experiments
+    pub archive_format: ArchiveFormat,
+    /// When `clean` (and the startup prompt) expire experiments
+    pub retention: Retention,
+    /// What Ctrl+T opens experiments in
+    pub terminal_integration: Integration,
+    pub tmux: Tmux,
+    pub zellij: Placement,
+    pub wezterm: Placement,
+    pub paths: Paths,
+}
+
+// Default Path: ~/work/tries; without a home directory there is none
+fn default_tries_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join("work").join("tries"))
+}
+
+// A tries path is unusable when it still starts with `~`: there was no home to expand
+// it to. Experiments must never quietly end up somewhere like the temp dir.
+fn resolved(path: Option<PathBuf>) -> Option<PathBuf> {
+    path.filter(|p| !p.starts_with("~"))
+}
+
+/// The tries directory and scan depth, resolved like `load_configuration` and
+/// `select_root` (`root` is --root) but without writing a first-run config or
+/// printing warnings: shell completion asks for them on every Tab. None
+/// when the tries directory can't be resolved (see `load_configuration`).
+pub fn peek_tries_dir(tries_path: Option<&Path>, root: Option<&str>) -> Option<(PathBuf, usize)> {
+    let config = load_file_config_toml_if_exists().map(|(_, config)| config);
+    let config = config.as_ref();
+    let mut path = match (tries_path, std::env::var_os("TRY_PATH")) {
+        (Some(path), _) => Some(expand_path(&path.to_string_lossy())),
+        (None, Some(path)) => Some(PathBuf::from(path)),
+        (None, None) => config
+            .and_then(|c| c.tries_path.as_deref())
+            .map_or_else(default_tries_path, |p| Some(expand_path(p))),
+    };
+    if let Some(name) = root
+        && let Some(root) = config
+            .and_then(|c| c.roots.as_ref())
+            .and_then(|roots| roots.iter().find(|r| r.name == name))
+    {
+        path = Some(expand_path(&root.path));
+    }
+    let depth = config.and_then(|c| c.scan_depth).unwrap_or(1).max(1);
+    Some((resolved(path)?, depth))
 }
 
-pub fn load_configuration() -> (PathBuf, Theme, Option<String>, bool) {
-    // Default Path: Work/tries
-    let default_path = dirs::home_dir()
-        .expect("Folder not found")
-        .join("work")
-        .join("tries");
+/// `tries_path` is the --tries-path flag, which beats TRY_PATH and the config.
+/// Fails when no tries directory can be resolved: without a home directory, the
+/// default and `~` paths have nowhere to point.
+pub fn load_configuration(tries_path: Option<&Path>) -> Result<Settings> {
+    let default_path = default_tries_path();
 
-    let mut theme = Theme::default();
+    let mut theme_name = DEFAULT_THEME.to_string();
+    let mut icons = Icons::default();
+    let mut colors = ThemeConfig::default();
     let try_path = std::env::var_os("TRY_PATH");
     let try_path_specified = try_path.is_some();
-    let mut final_path = try_path.map(PathBuf::from).unwrap_or(default_path);
-    let mut editor_cmd = std::env::var("VISUAL")
-        .ok()
-        .or_else(|| std::env::var("EDITOR").ok());
+    let mut path_source = if try_path_specified {
+        "TRY_PATH"
+    } else {
+        "default"
+    };
+    let mut final_path = try_path.map(PathBuf::from).or(default_path);
+    let mut editor_source = "VISUAL";
+    let mut editor_cmd = std::env::var("VISUAL").ok().or_else(|| {
+        editor_source = "EDITOR";
+        std::env::var("EDITOR").ok()
+    });
+    let mut editor_forced = false;
+    let mut editor_url_template = None;
+    let mut diff_tool = DEFAULT_DIFF_TOOL.to_string();
     let mut is_first_run = false;
+    let mut config_file = None;
+    let mut state_dir = None;
+    let mut cache_dir = None;
+    let mut normalize_names = false;
+    let mut date_prefix = false;
+    let mut set_title = false;
+    let mut osc7 = false;
+    let mut columns = Column::DEFAULT.to_vec();
+    let mut auto_name = DEFAULT_AUTO_NAME.to_string();
+    let mut on_select = None;
+    let mut hooks = Hooks::default();
+    let mut esc_behavior = EscBehavior::default();
+    let mut sort_mode = SortMode::default();
+    let mut archive_format = ArchiveFormat::default();
+    let mut retention = Retention::default();
+    let mut terminal_integration = Integration::default();
+    let mut tmux = Tmux::default();
+    let mut zellij = Placement::default();
+    let mut wezterm = Placement::default();
+    let mut quick_select = false;
+    let mut update_check = false;
+    let mut update_url = DEFAULT_UPDATE_URL.to_string();
+    let mut tick_rate = DEFAULT_TICK_RATE;
+    let mut status_timeout = Some(DEFAULT_STATUS_TIMEOUT);
+    let mut confirm_clone = false;
+    let mut clone_host = None;
+    let mut clone_options = CloneOptions::default();
+    let mut keymap = KeyMap::default();
+    let mut templates = Templates::default();
+    let mut git_init_commit = false;
+    let mut highlight_symbol = DEFAULT_HIGHLIGHT_SYMBOL.to_string();
+    let mut indent = 0;
+    let mut roots = Vec::new();
+    let mut scan_depth = 1;
+    let mut detectors = Detectors::default();
 
     // Try to load any existing config
-    if let Some(config) = load_file_config_toml_if_exists() {
+    if let Some((path, config)) = load_file_config_toml_if_exists() {
+        config_file = Some(path);
+        state_dir = config.state_dir;
+        cache_dir = config.cache_dir;
+        normalize_names = config.normalize_names.unwrap_or(false);
+        date_prefix = config.date_prefix.unwrap_or(false);
+        set_title = config.set_title.unwrap_or(false);
+        osc7 = config.osc7.unwrap_or(false);
+        quick_select = config.quick_select.unwrap_or(false);
+        update_check = config.update_check.unwrap_or(false);
+        confirm_clone = config.confirm_clone.unwrap_or(false);
+        if let Some(keys) = &config.keys {
+            keymap = KeyMap::from_config(keys);
+        }
+        templates.commands = config.templates.unwrap_or_default();
+        git_init_commit = config.git_init_commit.unwrap_or(false);
+        indent = config.indent.unwrap_or(0);
+        let icon_mode = if config.ascii_icons.unwrap_or(false) {
+            IconMode::Ascii
+        } else {
+            IconMode::default()
+        };
+        icons = match &config.icons {
+            Some(icons) => Icons::from_config(icons, icon_mode),
+            None => Icons::new(icon_mode),
+        };
+        if let Some(symbol) = config.highlight_symbol {
+            highlight_symbol = symbol;
+        } else if matches!(icons.mode(), IconMode::Ascii | IconMode::None) {
+            highlight_symbol = ASCII_HIGHLIGHT_SYMBOL.to_string();
+        }
+        if let Some(secs) = config.status_timeout {
+            status_timeout = (secs > 0).then(|| Duration::from_secs(secs));
+        }
+        if let Some(ms) = config.tick_rate {
+            // Keep a floor so a typo can't turn the TUI into a busy loop
+            tick_rate = Duration::from_millis(ms.max(MIN_TICK_RATE_MS));
+        }
+        if let Some(url) = config.update_url {
+            update_url = url;
+        }
+        // 0 keeps the full history, like leaving it out
+        clone_options.depth = config.clone_depth.filter(|&depth| depth > 0);
+        if let Some(host) = config.clone_host.filter(|h| !h.trim().is_empty()) {
+            clone_host = Some(host);
+        }
+        if let Some(template) = config.auto_name {
+            auto_name = template;
+        }
+        on_select = config.on_select.filter(|t| !t.trim().is_empty());
+        let command = |c: Option<String>| c.filter(|t| !t.trim().is_empty());
+        hooks = Hooks {
+            on_create: command(config.on_create),
+            on_enter: command(config.on_enter),
+            on_delete: command(config.on_delete),
+            scripts: None,
+        };
+        if let Some(value) = config.esc_behavior {
+            match EscBehavior::parse(&value) {
+                Some(behavior) => esc_behavior = behavior,
+                None => eprintln!(
+                    "Warning: unknown esc_behavior '{}' in config (expected one of: {})",
+                    value,
+                    EscBehavior::NAMES
+                ),
+            }
+        }
+        let mut integrations = config.integrations.unwrap_or_default();
+        if let Some(value) = config.terminal_integration {
+            let custom = integrations
+                .custom
+                .remove(value.trim())
+                .filter(|section| !section.steps.is_empty())
+                .map(|section| {
+                    Integration::Custom(Custom {
+                        name: value.trim().to_string(),
+                        steps: section.steps,
+                    })
+                });
+            match Integration::parse(&value).or(custom) {
+                Some(integration) => terminal_integration = integration,
+                None => eprintln!(
+                    "Warning: unknown terminal_integration '{}' in config (expected one of: {})",
+                    value,
+                    Integration::NAMES
+                ),
+            }
+        }
+        if let Some(section) = integrations.zellij {
+            zellij = parse_placement(section, "zellij");
+        }
+        if let Some(section) = integrations.wezterm {
+            wezterm = parse_placement(section, "wezterm");
+        }
+        if let Some(section) = integrations.tmux {
+            if let Some(value) = section.mode {
+                match TmuxMode::parse(&value) {
+                    Some(mode) => tmux.mode = mode,
+                    None => eprintln!(
+                        "Warning: unknown tmux mode '{}' in config (expected one of: {})",
+                        value,
+                        TmuxMode::NAMES
+                    ),
+                }
+            }
+            if let Some(prefix) = section.session_prefix {
+                tmux.session_prefix = prefix;
+            }
+        }
+        if let Some(policy) = config.retention {
+            retention = Retention {
+                // 0 leaves a rule off
+                max_age: policy
+                    .max_age_days
+                    .filter(|&days| days > 0)
+                    .map(|days| Duration::from_secs(days * 86400)),
+                max_count: policy.max_count.filter(|&n| n > 0),
+                exclude_git_dirty: policy.exclude_git_dirty.unwrap_or(true),
+                prompt: policy.prompt.unwrap_or(false),
+            };
+        }
+        if let Some(value) = config.sort {
+            match SortMode::parse(&value) {
+                Some(mode) => sort_mode = mode,
+                None => eprintln!(
+                    "Warning: unknown sort '{}' in config (expected one of: {})",
+                    value,
+                    SortMode::NAMES
+                ),
+            }
+        }
+        if let Some(value) = config.archive_format {
+            match ArchiveFormat::parse(&value) {
+                Some(format) => archive_format = format,
+                None => eprintln!(
+                    "Warning: unknown archive_format '{}' in config (expected one of: {})",
+                    value,
+                    ArchiveFormat::NAMES
+                ),
+            }
+        }
+        // 0 behaves like 1: the tries root itself is never an experiment
+        scan_depth = config.scan_depth.unwrap_or(1).max(1);
+        if let Some(configs) = config.detectors {
+            detectors = Detectors::with_config(configs);
+        }
+        for root in config.roots.unwrap_or_default() {
+            roots.push(Root {
+                name: root.name,
+                path: expand_path(&root.path),
+            });
+        }
+        if let Some(names) = config.columns {
+            columns = parse_columns(&names);
+        }
         if let Some(path_str) = config.tries_path
             && !try_path_specified
         {
-            final_path = expand_path(&path_str);
+            final_path = Some(expand_path(&path_str));
+            path_source = "config";
         }
         if let Some(editor) = config.editor {
             editor_cmd = Some(editor);
+            editor_source = "config";
         }
-        if let Some(colors) = config.colors {
-            // Helper to parse color string to Color enum
-            let parse = |opt: Option<String>, def: Color| -> Color {
-                opt.and_then(|s| Color::from_str(&s).ok()).unwrap_or(def)
-            };
-
-            let def = Theme::default();
-            theme = Theme {
-                title_try: parse(colors.title_try, def.title_try),
-                title_rs: parse(colors.title_rs, def.title_rs),
-                search_box: parse(colors.search_box, def.search_box),
-                list_date: parse(colors.list_date, def.list_date),
-                list_highlight_bg: parse(colors.list_highlight_bg, def.list_highlight_bg),
-                list_highlight_fg: parse(colors.list_highlight_fg, def.list_highlight_fg),
-                help_text: parse(colors.help_text, def.help_text),
-                status_message: parse(colors.status_message, def.status_message),
-                popup_bg: parse(colors.popup_bg, def.popup_bg),
-                popup_text: parse(colors.popup_text, def.popup_text),
-            };
+        editor_url_template = config.editor_url_template.filter(|t| !t.is_empty());
+        if let Some(tool) = config.diff_tool.filter(|t| !t.is_empty()) {
+            diff_tool = tool;
         }
-    } else {
+        if let Some(name) = config.theme {
+            if let Some(preset) = Theme::preset_name(&name) {
+                theme_name = preset.to_string();
+            } else {
+                eprintln!(
+                    "Warning: unknown theme '{}' in config (expected one of: {})",
+                    name,
+                    Theme::preset_names()
+                );
+            }
+        }
+        colors = config.colors.unwrap_or_default();
+    } else if let Some(final_path) = &final_path {
         // No config found. We should create the default one.
         // Calculate the default location to write to: ~/.config/try-rs/config.toml
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| dirs::home_dir().expect("Folder not found").join(".config"));
-        let app_config_dir = config_dir.join("try-rs");
-        let config_file = app_config_dir.join("config.toml");
+        let app_config_dir = app_config_dir();
+        let new_config_file = app_config_dir.join("config.toml");
 
-        if fs::create_dir_all(&app_config_dir).is_ok() {
-            let default_content = format!("tries_path = {final_path:?}");
+        // A read-only or missing config dir isn't fatal: we just run on defaults
+        let default_content = format!("tries_path = {final_path:?}");
+        let written = fs::create_dir_all(&app_config_dir).and_then(|_| {
             // We only write if the file really doesn't exist (double check to be safe)
-            if !config_file.exists() {
-                let _ = fs::write(&config_file, default_content);
-                is_first_run = true;
+            if new_config_file.exists() {
+                return Ok(false);
             }
+            fs::write(&new_config_file, default_content).map(|_| true)
+        });
+        match written {
+            Ok(created) => {
+                is_first_run = created;
+                if created {
+                    config_file = Some(new_config_file);
+                }
+            }
+            Err(e) => eprintln!(
+                "Warning: could not create {}: {} (using defaults)",
+                new_config_file.display(),
+                e
+            ),
         }
     }
 
-    (final_path, theme, editor_cmd, is_first_run)
+    // Applied after the first-run config was written, so a one-off path isn't saved
+    if let Some(path) = tries_path {
+        final_path = Some(expand_path(&path.to_string_lossy()));
+        path_source = "--tries-path";
+    }
+    let Some(final_path) = resolved(final_path) else {
+        bail!(
+            "Could not resolve the tries directory without a home directory \
+             (set TRY_PATH, --tries-path or tries_path in config.toml)"
+        );
+    };
+    debug!(
+        "tries path: {} (from {})",
+        final_path.display(),
+        path_source
+    );
+    if !roots.is_empty() && !roots.iter().any(|r: &Root| r.path == final_path) {
+        roots.insert(
+            0,
+            Root {
+                name: "default".to_string(),
+                path: final_path.clone(),
+            },
+        );
+    }
+
+    // TRY_EDITOR picks an editor for try-rs alone, above the config and VISUAL/EDITOR
+    if let Some(editor) = std::env::var("TRY_EDITOR").ok().filter(|e| !e.is_empty()) {
+        editor_cmd = Some(editor);
+        editor_source = "TRY_EDITOR";
+        editor_forced = true;
+    }
+    match &editor_cmd {
+        Some(editor) => debug!("editor: {} (from {})", editor, editor_source),
+        None => debug!("editor: none configured"),
+    }
+
+    let paths = Paths::resolve(state_dir.as_deref(), cache_dir.as_deref());
+    hooks.scripts = Some(hooks_dir(config_file.as_deref()));
+    templates.skeletons = Some(templates_dir(config_file.as_deref()));
+
+    let color_depth = ColorDepth::detect();
+    debug!("color depth: {:?}", color_depth);
+    let theme = colors.theme(&theme_name, color_depth);
+
+    Ok(Settings {
+        tries_dir: final_path,
+        roots,
+        scan_depth,
+        detectors,
+        theme,
+        theme_name,
+        colors,
+        color_depth,
+        icons,
+        editor_cmd,
+        editor_forced,
+        editor_url_template,
+        diff_tool,
+        git_init_commit,
+        highlight_symbol,
+        indent,
+        is_first_run,
+        config_file,
+        normalize_names,
+        date_prefix,
+        set_title,
+        osc7,
+        columns,
+        auto_name,
+        on_select,
+        hooks,
+        esc_behavior,
+        sort_mode,
+        quick_select,
+        update_check,
+        update_url,
+        tick_rate,
+        status_timeout,
+        confirm_clone,
+        clone_host,
+        clone_options,
+        keymap,
+        templates,
+        archive_format,
+        retention,
+        terminal_integration,
+        tmux,
+        zellij,
+        wezterm,
+        paths,
+        print_path: false,
+    })
+}
+
+impl Settings {
+    /// The editor to open `dir` with: --editor and TRY_EDITOR, then the experiment's
+    /// `.try-rs-editor`, then the config's `editor` and VISUAL/EDITOR
+    pub fn editor_for(&self, dir: &Path) -> Option<String> {
+        if self.editor_forced {
+            return self.editor_cmd.clone();
+        }
+        project_editor(dir).or_else(|| self.editor_cmd.clone())
+    }
+
+    /// Makes the `[[roots]]` entry `name` the tries directory (--root)
+    pub fn select_root(&mut self, name: &str) -> Result<()> {
+        let Some(root) = self.roots.iter().find(|r| r.name == name) else {
+            if self.roots.is_empty() {
+                bail!("No root named '{}' (add [[roots]] to config.toml)", name);
+            }
+            let names: Vec<&str> = self.roots.iter().map(|r| r.name.as_str()).collect();
+            bail!("No root named '{}' (available: {})", name, names.join(", "));
+        };
+        debug!("tries path: {} (from --root {})", root.path.display(), name);
+        self.tries_dir = root.path.clone();
+        Ok(())
+    }
+
+    /// Rebuilds the theme from the preset `name` (--theme), keeping the `[colors]` overrides
+    pub fn select_theme(&mut self, name: &str) -> Result<()> {
+        let Some(preset) = Theme::preset_name(name) else {
+            bail!(
+                "No theme named '{}' (available: {})",
+                name,
+                Theme::preset_names()
+            );
+        };
+        self.theme = self.colors.theme(preset, self.color_depth);
+        self.theme_name = preset.to_string();
+        Ok(())
+    }
+
+    // The defaults of an empty config with `dir` as the tries dir and everything on
+    // disk kept below it, untouched by the environment
+    #[cfg(test)]
+    pub(crate) fn for_tests(dir: &Path) -> Settings {
+        Settings {
+            tries_dir: dir.to_path_buf(),
+            roots: Vec::new(),
+            scan_depth: 1,
+            detectors: Detectors::default(),
+            theme: Theme::default(),
+            theme_name: DEFAULT_THEME.to_string(),
+            colors: ThemeConfig::default(),
+            color_depth: ColorDepth::default(),
+            icons: Icons::default(),
+            editor_cmd: None,
+            editor_forced: false,
+            editor_url_template: None,
+            diff_tool: DEFAULT_DIFF_TOOL.to_string(),
+            git_init_commit: false,
+            highlight_symbol: DEFAULT_HIGHLIGHT_SYMBOL.to_string(),
+            indent: 0,
+            is_first_run: false,
+            config_file: None,
+            normalize_names: false,
+            date_prefix: false,
+            set_title: false,
+            osc7: false,
+            columns: Column::DEFAULT.to_vec(),
+            auto_name: DEFAULT_AUTO_NAME.to_string(),
+            on_select: None,
+            print_path: false,
+            hooks: Hooks::default(),
+            esc_behavior: EscBehavior::default(),
+            sort_mode: SortMode::default(),
+            quick_select: false,
+            update_check: false,
+            update_url: DEFAULT_UPDATE_URL.to_string(),
+            tick_rate: DEFAULT_TICK_RATE,
+            status_timeout: Some(DEFAULT_STATUS_TIMEOUT),
+            confirm_clone: false,
+            clone_host: None,
+            clone_options: CloneOptions::default(),
+            keymap: KeyMap::default(),
+            templates: Templates::default(),
+            archive_format: ArchiveFormat::default(),
+            retention: Retention::default(),
+            terminal_integration: Integration::default(),
+            tmux: Tmux::default(),
+            zellij: Placement::default(),
+            wezterm: Placement::default(),
+            paths: Paths {
+                config_dir: dir.join(".config"),
+                state_dir: dir.join(".state"),
+                cache_dir: dir.join(".cache"),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(content: &str, key: &str, value: &str) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, content).unwrap();
+        set_config_string(&path, key, value).unwrap();
+        fs::read_to_string(&path).unwrap()
+    }
+
+    #[test]
+    fn set_config_string_keeps_comments() {
+        let content = "# mine\ntheme = \"Old\" # picked with F2\nindent = 2\n";
+        assert_eq!(
+            set(content, "theme", "New"),
+            "# mine\ntheme = \"New\" # picked with F2\nindent = 2\n"
+        );
+    }
+
+    #[test]
+    fn set_config_string_only_touches_the_top_level_key() {
+        let content = "columns = [\n  \"name\",\n  # theme = \"x\"\n]\nabout = \"theme = x\"\n\n[colors]\ntheme = \"Other\"\n";
+        let updated = set(content, "theme", "New");
+        let config: toml::Table = toml::from_str(&updated).unwrap();
+        assert_eq!(config["theme"].as_str(), Some("New"));
+        assert_eq!(config["about"].as_str(), Some("theme = x"));
+        assert_eq!(config["colors"]["theme"].as_str(), Some("Other"));
+        assert!(updated.starts_with(content.split("\n\n").next().unwrap()));
+    }
+
+    #[test]
+    fn set_config_string_creates_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        set_config_string(&path, "theme", "New").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "theme = \"New\"\n");
+    }
+
+    #[test]
+    fn unexpanded_home_paths_do_not_resolve() {
+        assert_eq!(resolved(None), None);
+        assert_eq!(resolved(Some(PathBuf::from("~/work/tries"))), None);
+        let path = PathBuf::from("/srv/tries");
+        assert_eq!(resolved(Some(path.clone())), Some(path));
+    }
+
+    #[test]
+    fn other_integration_sections_are_custom() {
+        let config: Config = toml::from_str(
+            r#"
+            [integrations.tmux]
+            mode = "window"
+
+            [integrations.kitty]
+            steps = ["kitty @ launch --cwd {path}"]
+            "#,
+        )
+        .unwrap();
+        let integrations = config.integrations.unwrap();
+        assert_eq!(integrations.tmux.unwrap().mode.as_deref(), Some("window"));
+        assert_eq!(integrations.custom.len(), 1);
+        assert_eq!(
+            integrations.custom["kitty"].steps,
+            ["kitty @ launch --cwd {path}"]
+        );
+    }
 }
@@ -0,0 +1,428 @@
+use crate::icons::{Icon, IconMode, Icons};
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// What a marker file has to be for the kind to be detected
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MarkerType {
+    Any,
+    Dir,  // `.git` of a repository
+    File, // `.git` of a worktree or submodule, pointing elsewhere
+}
+
+/// A kind of project, detected by the presence of any of its marker files;
+/// `*.ext` stands for any file ending that way
+#[derive(Debug)]
+pub struct ProjectKind {
+    /// Value of `lang:` filters
+    pub name: String,
+    /// Shown in the preview and the `types` of `list --json`
+    pub label: String,
+    pub color: Color,
+    files: Vec<String>,
+    marker_type: MarkerType,
+    icon: Option<Icon>, // Built-in kinds follow the icon set
+    glyph: String,      // Icon of a `[[detectors]]` kind
+    aliases: &'static [&'static str],
+}
+
+impl ProjectKind {
+    // Whether the marker files are in `dir`; `listing` holds the names of its files,
+    // read once for all `*.ext` markers
+    fn is_detected(&self, dir: &Path, listing: &[String]) -> bool {
+        self.files.iter().any(|file| {
+            // `*.tf`: any file with that extension
+            if let Some(suffix) = file.strip_prefix('*') {
+                return listing
+                    .iter()
+                    .any(|name| name.len() > suffix.len() && name.ends_with(suffix));
+            }
+            let path = dir.join(file);
+            match self.marker_type {
+                MarkerType::Any => path.exists(),
+                MarkerType::Dir => path.is_dir(),
+                MarkerType::File => path.is_file(),
+            }
+        })
+    }
+
+    /// Whether `lang:name` selects the kind
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+            || self.aliases.iter().any(|a| a.eq_ignore_ascii_case(name))
+    }
+
+    /// The glyph of the markers column; configured kinds show their name in ascii mode
+    pub fn glyph<'a>(&'a self, icons: &'a Icons) -> &'a str {
+        match (self.icon, icons.mode()) {
+            (Some(icon), _) => icons.get(icon),
+            (None, IconMode::None) => "",
+            (None, IconMode::Ascii) => &self.name,
+            (None, _) => &self.glyph,
+        }
+    }
+}
+
+/// `[[detectors]]`: a project kind of your own, e.g.
+/// `name = "packer"`, `file = "*.pkr.hcl"`, `icon = "P"`, `color = "#02A8EF"`
+#[derive(Deserialize)]
+pub struct DetectorConfig {
+    pub name: String,
+    pub file: Files,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub label: Option<String>,
+}
+
+/// One marker file name or a list of them
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Files {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Every kind an entry is checked for, in the order of the markers column
+#[derive(Clone)]
+pub struct Detectors {
+    kinds: Vec<Arc<ProjectKind>>,
+}
+
+impl Default for Detectors {
+    fn default() -> Self {
+        let builtin = |name: &str, label: &str, files: &[&str], icon, color, aliases| ProjectKind {
+            name: name.to_string(),
+            label: label.to_string(),
+            color,
+            files: files.iter().map(|f| f.to_string()).collect(),
+            marker_type: MarkerType::Any,
+            icon: Some(icon),
+            glyph: String::new(),
+            aliases,
+        };
+        let kinds = vec![
+            builtin(
+                "rust",
+                "Rust",
+                &["Cargo.toml"],
+                Icon::Rust,
+                Color::Rgb(230, 100, 50),
+                &["cargo"],
+            ),
+            builtin(
+                "maven",
+                "Maven",
+                &["pom.xml"],
+                Icon::Maven,
+                Color::Rgb(255, 150, 50),
+                &["java"],
+            ),
+            builtin(
+                "flutter",
+                "Flutter",
+                &["pubspec.yaml"],
+                Icon::Flutter,
+                Color::Rgb(2, 123, 222),
+                &["dart"],
+            ),
+            builtin(
+                "go",
+                "Go",
+                &["go.mod"],
+                Icon::Go,
+                Color::Rgb(0, 173, 216),
+                &[],
+            ),
+            builtin(
+                "python",
+                "Python",
+                &["pyproject.toml", "requirements.txt"],
+                Icon::Python,
+                Color::Yellow,
+                &["py"],
+            ),
+            builtin(
+                "mise",
+                "mise",
+                &["mise.toml"],
+                Icon::Mise,
+                Color::Rgb(250, 179, 135),
+                &[],
+            ),
+            builtin(
+                "direnv",
+                "direnv",
+                &[".envrc"],
+                Icon::Direnv,
+                Color::Rgb(166, 227, 161),
+                &[],
+            ),
+            builtin(
+                "asdf",
+                "asdf",
+                &[".tool-versions"],
+                Icon::Asdf,
+                Color::Rgb(180, 190, 254),
+                &[],
+            ),
+            builtin(
+                "node",
+                "Node.js",
+                &["package.json"],
+                Icon::Node,
+                Color::Rgb(104, 160, 99),
+                &["js", "npm"],
+            ),
+            builtin(
+                "deno",
+                "Deno",
+                &["deno.json", "deno.jsonc"],
+                Icon::Deno,
+                Color::Rgb(200, 200, 200),
+                &[],
+            ),
+            builtin(
+                "zig",
+                "Zig",
+                &["build.zig"],
+                Icon::Zig,
+                Color::Rgb(247, 164, 29),
+                &[],
+            ),
+            builtin(
+                "nix",
+                "Nix",
+                &["flake.nix", "shell.nix"],
+                Icon::Nix,
+                Color::Rgb(126, 186, 228),
+                &["flake"],
+            ),
+            builtin(
+                "docker",
+                "Docker",
+                &[
+                    "Dockerfile",
+                    "compose.yaml",
+                    "compose.yml",
+                    "docker-compose.yaml",
+                    "docker-compose.yml",
+                ],
+                Icon::Docker,
+                Color::Rgb(29, 99, 237),
+                &["compose"],
+            ),
+            builtin(
+                "terraform",
+                "Terraform",
+                &["*.tf"],
+                Icon::Terraform,
+                Color::Rgb(132, 79, 186),
+                &["tf"],
+            ),
+            builtin(
+                "elixir",
+                "Elixir",
+                &["mix.exs"],
+                Icon::Elixir,
+                Color::Rgb(110, 74, 126),
+                &["ex"],
+            ),
+            ProjectKind {
+                marker_type: MarkerType::Dir,
+                ..builtin(
+                    "git",
+                    "Git",
+                    &[".git"],
+                    Icon::Git,
+                    Color::Rgb(240, 80, 50),
+                    &[],
+                )
+            },
+            ProjectKind {
+                marker_type: MarkerType::File,
+                ..builtin(
+                    "worktree",
+                    "Git worktree/submodule",
+                    &[".git"],
+                    Icon::Worktree,
+                    Color::Rgb(240, 80, 50),
+                    &["submodule"],
+                )
+            },
+        ];
+        Self {
+            kinds: kinds.into_iter().map(Arc::new).collect(),
+        }
+    }
+}
+
+impl Detectors {
+    /// The built-in kinds followed by the `[[detectors]]` ones. A detector named like a
+    /// built-in kind replaces it.
+    pub fn with_config(configs: Vec<DetectorConfig>) -> Self {
+        let mut detectors = Self::default();
+        for config in configs {
+            let color = config.color.as_deref().map_or(Color::Reset, |value| {
+                Color::from_str(value).unwrap_or_else(|_| {
+                    eprintln!(
+                        "Warning: unknown color '{}' for detector '{}'",
+                        value, config.name
+                    );
+                    Color::Reset
+                })
+            });
+            let files = match config.file {
+                Files::One(file) => vec![file],
+                Files::Many(files) => files,
+            };
+            let kind = Arc::new(ProjectKind {
+                label: config.label.unwrap_or_else(|| config.name.clone()),
+                glyph: config.icon.unwrap_or_default(),
+                name: config.name,
+                color,
+                files,
+                marker_type: MarkerType::Any,
+                icon: None,
+                aliases: &[],
+            });
+            match detectors.kinds.iter().position(|k| k.name == kind.name) {
+                Some(i) => detectors.kinds[i] = kind,
+                None => detectors.kinds.push(kind),
+            }
+        }
+        detectors
+    }
+
+    /// Whether `lang:name` selects any kind
+    pub fn knows(&self, name: &str) -> bool {
+        self.kinds.iter().any(|kind| kind.is_named(name))
+    }
+
+    /// The names `lang:` accepts, for error messages
+    pub fn names(&self) -> Vec<&str> {
+        self.kinds.iter().map(|kind| kind.name.as_str()).collect()
+    }
+
+    /// The kinds whose marker files are in `dir`. The directory is only listed when
+    /// a `*.ext` marker needs it, and then only once.
+    pub fn detect(&self, dir: &Path) -> Vec<Arc<ProjectKind>> {
+        let globs = self
+            .kinds
+            .iter()
+            .any(|kind| kind.files.iter().any(|f| f.starts_with('*')));
+        let listing: Vec<String> = if globs {
+            fs::read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .map(|e| e.file_name().to_string_lossy().into_owned())
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        self.kinds
+            .iter()
+            .filter(|kind| kind.is_detected(dir, &listing))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(kinds: &[Arc<ProjectKind>]) -> Vec<&str> {
+        kinds.iter().map(|k| k.name.as_str()).collect()
+    }
+
+    fn config(name: &str, file: &str) -> DetectorConfig {
+        DetectorConfig {
+            name: name.to_string(),
+            file: Files::One(file.to_string()),
+            icon: Some("P".to_string()),
+            color: None,
+            label: None,
+        }
+    }
+
+    #[test]
+    fn names_and_aliases_are_known() {
+        let detectors = Detectors::default();
+        for name in ["rust", "Cargo", "py", "tf", "js", "worktree", "submodule"] {
+            assert!(detectors.knows(name), "{}", name);
+        }
+        assert!(!detectors.knows("cobol"));
+        assert_eq!(detectors.names().first(), Some(&"rust"));
+    }
+
+    #[test]
+    fn git_repositories_and_worktrees_differ_by_marker_type() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let worktree = dir.path().join("wt");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir(&worktree).unwrap();
+        fs::write(worktree.join(".git"), "gitdir: /x/.git/worktrees/wt").unwrap();
+        let detectors = Detectors::default();
+        assert_eq!(names(&detectors.detect(&repo)), ["git"]);
+        assert_eq!(names(&detectors.detect(&worktree)), ["worktree"]);
+    }
+
+    #[test]
+    fn configured_detectors_are_added_or_replace_built_ins() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("main.pkr.hcl"), "").unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        let detectors = Detectors::with_config(vec![
+            config("packer", "*.pkr.hcl"),
+            config("rust", "rust-toolchain.toml"),
+        ]);
+        assert!(detectors.knows("packer"));
+        assert_eq!(names(&detectors.detect(dir.path())), ["packer"]);
+        // The replaced kind keeps its place in the markers column
+        assert_eq!(detectors.names().first(), Some(&"rust"));
+    }
+
+    #[test]
+    fn configured_glyphs_follow_the_icon_mode() {
+        let detectors = Detectors::with_config(vec![config("packer", "*.pkr.hcl")]);
+        let packer = detectors.kinds.last().unwrap();
+        assert_eq!(packer.glyph(&Icons::new(IconMode::Ascii)), "packer");
+        assert_eq!(packer.glyph(&Icons::new(IconMode::None)), "");
+    }
+
+    #[test]
+    fn new_kinds_are_detected_by_their_markers() {
+        let cases: &[(&str, &str)] = &[
+            ("package.json", "node"),
+            ("deno.jsonc", "deno"),
+            ("build.zig", "zig"),
+            ("flake.nix", "nix"),
+            ("shell.nix", "nix"),
+            ("Dockerfile", "docker"),
+            ("compose.yml", "docker"),
+            ("main.tf", "terraform"),
+            ("mix.exs", "elixir"),
+        ];
+        let detectors = Detectors::default();
+        for (file, kind) in cases {
+            let dir = tempfile::tempdir().unwrap();
+            fs::write(dir.path().join(file), "").unwrap();
+            assert_eq!(names(&detectors.detect(dir.path())), [*kind], "{}", file);
+        }
+    }
+
+    #[test]
+    fn glob_markers_need_more_than_the_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".tf"), "").unwrap();
+        assert!(Detectors::default().detect(dir.path()).is_empty());
+    }
+}
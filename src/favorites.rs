@@ -0,0 +1,91 @@
+use crate::paths::{FAVORITES_FILE, Paths};
+use crate::tui::TryEntry;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Favorite experiments are kept in FAVORITES_FILE in the state dir, one absolute
+// path per line. They sort to the top of the list and are never cleaned up.
+pub fn load(paths: &Paths) -> HashSet<PathBuf> {
+    let Ok(contents) = fs::read_to_string(paths.state_file(FAVORITES_FILE)) else {
+        return HashSet::new();
+    };
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+// Flags the scanned entries that are favorites; every listing goes through here
+pub fn mark(entries: &mut [TryEntry], favorites: &HashSet<PathBuf>) {
+    for entry in entries {
+        entry.is_favorite = favorites.contains(&entry.path);
+    }
+}
+
+// Rewrites the favorites file
+pub fn save(paths: &Paths, favorites: &HashSet<PathBuf>) -> io::Result<()> {
+    let mut lines: Vec<String> = favorites
+        .iter()
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+    lines.sort();
+    let contents: String = lines.iter().map(|l| format!("{}\n", l)).collect();
+    fs::create_dir_all(&paths.state_dir)?;
+    fs::write(paths.state_file(FAVORITES_FILE), contents)
+}
+
+// Carries a favorite over to the experiment's new path after a rename
+pub fn rename(paths: &Paths, old: &Path, new: &Path) -> io::Result<()> {
+    let mut favorites = load(paths);
+    if !favorites.remove(old) {
+        return Ok(());
+    }
+    favorites.insert(new.to_path_buf());
+    save(paths, &favorites)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meta::Meta;
+    use crate::scan::RawEntry;
+    use std::time::SystemTime;
+
+    #[test]
+    fn favorites_survive_a_round_trip_and_mark_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths {
+            config_dir: dir.path().join("config"),
+            state_dir: dir.path().join("state"),
+            cache_dir: dir.path().join("cache"),
+        };
+        let (kept, other) = (dir.path().join("kept"), dir.path().join("other"));
+        save(&paths, &HashSet::from([kept.clone()])).unwrap();
+        rename(&paths, &other, &dir.path().join("nowhere")).unwrap();
+        let favorites = load(&paths);
+        assert_eq!(favorites, HashSet::from([kept.clone()]));
+
+        let mut entries: Vec<TryEntry> = [&kept, &other]
+            .into_iter()
+            .map(|path| {
+                TryEntry::from_raw(RawEntry {
+                    path: path.clone(),
+                    name: String::new(),
+                    modified: SystemTime::UNIX_EPOCH,
+                    created: SystemTime::UNIX_EPOCH,
+                    kinds: Vec::new(),
+                    git: false,
+                    meta: Meta::default(),
+                })
+            })
+            .collect();
+        entries[1].is_favorite = true;
+        mark(&mut entries, &favorites);
+        assert!(entries[0].is_favorite);
+        // A favorite dropped since the last listing is unmarked again
+        assert!(!entries[1].is_favorite);
+    }
+}
@@ -0,0 +1,285 @@
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+// The git side of cloning: which `git clone` variant to run for a set of options,
+// and what it prints while it works. `ops` puts the result in place. Also the
+// worktrees experiments can be made of.
+
+// How much of a repository a clone fetches
+#[derive(Clone, Default)]
+pub struct CloneOptions {
+    pub depth: Option<u32>,     // --depth; None fetches the full history
+    pub branch: Option<String>, // Checked out, and the only branch fetched
+    pub filter: Option<String>, // --filter for a partial clone, e.g. "blob:none"
+    pub sparse: Vec<String>,    // Only these directories are checked out
+}
+
+impl CloneOptions {
+    // A sparse clone without a filter of its own skips the blobs it won't check out
+    fn filter(&self) -> Option<&str> {
+        match &self.filter {
+            Some(filter) => Some(filter),
+            None if !self.sparse.is_empty() => Some("blob:none"),
+            None => None,
+        }
+    }
+
+    // What gets fetched, for the clone popup, e.g. "depth 1 · branch main"
+    pub fn describe(&self) -> String {
+        let mut parts = vec![match self.depth {
+            Some(depth) => format!("depth {}", depth),
+            None => "full history".to_string(),
+        }];
+        if let Some(branch) = &self.branch {
+            parts.push(format!("branch {}", branch));
+        }
+        if let Some(filter) = self.filter() {
+            parts.push(format!("filter {}", filter));
+        }
+        if !self.sparse.is_empty() {
+            parts.push(format!("only {}", self.sparse.join(", ")));
+        }
+        parts.join(" · ")
+    }
+}
+
+// `git clone` of `url` into `dest`. Sparse clones only get their top-level files
+// here; `finish_clone` checks out the directories.
+pub fn clone_command(url: &str, dest: &Path, options: &CloneOptions) -> Command {
+    let mut cmd = Command::new("git");
+    cmd.arg("clone");
+    if let Some(depth) = options.depth {
+        cmd.arg("--depth").arg(depth.to_string());
+    }
+    if let Some(filter) = options.filter() {
+        cmd.arg(format!("--filter={}", filter));
+    }
+    match &options.branch {
+        Some(branch) => cmd.arg("--branch").arg(branch).arg("--single-branch"),
+        None => cmd.arg("--no-single-branch"),
+    };
+    if options.sparse.is_empty() {
+        cmd.arg("--recurse-submodules");
+    } else {
+        cmd.arg("--sparse");
+    }
+    cmd.arg(url).arg(dest);
+    cmd
+}
+
+// Runs what `clone_command` leaves to do in the fresh clone at `dir`
+pub fn finish_clone(dir: &Path, options: &CloneOptions) -> Result<()> {
+    if options.sparse.is_empty() {
+        return Ok(());
+    }
+    let mut cmd = Command::new("git");
+    cmd.arg("-C")
+        .arg(dir)
+        .args(["sparse-checkout", "set"])
+        .args(&options.sparse);
+    run(&mut cmd, "git sparse-checkout set")
+}
+
+// One line of git's progress output, e.g. "Receiving objects:  45% (450/1000)"
+pub struct CloneProgress {
+    pub stage: String, // "Receiving objects", "Resolving deltas", ...
+    pub percent: Option<u8>,
+}
+
+impl CloneProgress {
+    // Parses a progress line; anything without a "stage: " part (like "Cloning into")
+    // is skipped
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let line = line.strip_prefix("remote: ").unwrap_or(line);
+        let (stage, rest) = line.split_once(": ")?;
+        if stage.is_empty() || stage.contains(['\'', '/']) || matches!(stage, "warning" | "hint") {
+            return None;
+        }
+        let percent = rest
+            .trim_start()
+            .split_once('%')
+            .and_then(|(n, _)| n.trim().parse().ok());
+        Some(CloneProgress {
+            stage: stage.to_string(),
+            percent,
+        })
+    }
+}
+
+// Whether `dir` is a linked worktree: its `.git` is a file pointing into the
+// worktrees of another repository
+pub fn is_worktree(dir: &Path) -> bool {
+    fs::read_to_string(dir.join(".git"))
+        .is_ok_and(|s| s.starts_with("gitdir:") && s.contains("worktrees"))
+}
+
+// Adds a worktree of `repo` at `dest` with `branch` checked out; a branch that
+// doesn't exist yet is created from the repository's HEAD
+pub fn worktree_add(repo: &Path, dest: &Path, branch: &str) -> Result<()> {
+    let exists = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("refs/heads/{}", branch))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+    let mut cmd = Command::new("git");
+    cmd.arg("-C").arg(repo).args(["worktree", "add"]);
+    if exists {
+        cmd.arg(dest).arg(branch);
+    } else {
+        cmd.arg("-b").arg(branch).arg(dest);
+    }
+    run(&mut cmd, "git worktree add")
+}
+
+// Removes the worktree at `dir` through git, so its repository forgets it too.
+// Without `force` git refuses to drop uncommitted changes; only pass it once the
+// user confirmed losing them.
+pub fn worktree_remove(dir: &Path, force: bool) -> Result<()> {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C").arg(dir).args(["worktree", "remove"]);
+    if force {
+        cmd.arg("--force");
+    }
+    cmd.arg(dir);
+    run(&mut cmd, "git worktree remove")
+}
+
+// Moves the worktree at `dir` to `dest` through git, so its repository keeps
+// track of it
+pub fn worktree_move(dir: &Path, dest: &Path) -> Result<()> {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C")
+        .arg(dir)
+        .args(["worktree", "move"])
+        .arg(dir)
+        .arg(dest);
+    run(&mut cmd, "git worktree move")
+}
+
+// Deletes `dir` for good: worktrees through git (refusing ones with uncommitted
+// changes unless `force`), anything else from disk
+pub fn remove_dir(dir: &Path, force: bool) -> Result<()> {
+    if is_worktree(dir) {
+        return worktree_remove(dir, force);
+    }
+    fs::remove_dir_all(dir).with_context(|| format!("removing {}", dir.display()))
+}
+
+// Lists `/name` in the local excludes of the repository at `dir`, so a file try-rs
+// keeps there never shows up as untracked. Worktrees share their repository's.
+pub fn exclude(dir: &Path, name: &str) -> Result<()> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--git-path", "info/exclude"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .context("running git rev-parse")?;
+    if !output.status.success() {
+        bail!("{} is not a git repository", dir.display());
+    }
+    let exclude = dir.join(String::from_utf8_lossy(&output.stdout).trim());
+    let pattern = format!("/{}", name);
+    let mut contents = fs::read_to_string(&exclude).unwrap_or_default();
+    if contents.lines().any(|l| l.trim() == pattern) {
+        return Ok(());
+    }
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str(&pattern);
+    contents.push('\n');
+    if let Some(parent) = exclude.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&exclude, contents).with_context(|| format!("writing {}", exclude.display()))
+}
+
+// Runs `cmd`, turning a failure into an error with git's last words
+fn run(cmd: &mut Command, what: &str) -> Result<()> {
+    let output = cmd
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("running {}", what))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let last = stderr.lines().rfind(|l| !l.trim().is_empty()).unwrap_or("");
+        bail!("{} failed: {}", what, last.trim());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?}", args);
+    }
+
+    // A repository with one commit and a worktree of it on branch "wt"
+    fn repo_with_worktree(root: &Path) -> (PathBuf, PathBuf) {
+        let repo = root.join("repo");
+        fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "-q"]);
+        fs::write(repo.join("a.txt"), "a").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-qm", "init"]);
+        let worktree = root.join("tries").join("wt");
+        fs::create_dir_all(worktree.parent().unwrap()).unwrap();
+        worktree_add(&repo, &worktree, "wt").unwrap();
+        (repo, worktree)
+    }
+
+    #[test]
+    fn worktree_remove_keeps_uncommitted_changes_unless_forced() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, worktree) = repo_with_worktree(dir.path());
+        assert!(is_worktree(&worktree));
+        fs::write(worktree.join("a.txt"), "changed").unwrap();
+
+        assert!(remove_dir(&worktree, false).is_err());
+        assert!(worktree.join("a.txt").exists());
+
+        worktree_remove(&worktree, true).unwrap();
+        assert!(!worktree.exists());
+    }
+
+    #[test]
+    fn worktree_move_keeps_the_repository_in_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, worktree) = repo_with_worktree(dir.path());
+        let moved = worktree.with_file_name("renamed");
+        worktree_move(&worktree, &moved).unwrap();
+        assert!(!worktree.exists());
+        assert!(is_worktree(&moved));
+
+        let list = Command::new("git")
+            .arg("-C")
+            .arg(&repo)
+            .args(["worktree", "list", "--porcelain"])
+            .output()
+            .unwrap();
+        let list = String::from_utf8_lossy(&list.stdout);
+        assert!(list.contains("renamed"), "{}", list);
+        worktree_remove(&moved, false).unwrap();
+    }
+}
@@ -0,0 +1,224 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Uncommitted work of a repository: changed or untracked files, commits ahead of /
+/// behind its upstream, and commits no remote has. Shared by the git column, the
+/// delete guard and `clean`.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct GitStatus {
+    pub dirty: bool,
+    pub ahead: u32,
+    pub behind: u32,
+    /// On any local branch but no remote, upstream or not
+    pub unpushed: u32,
+}
+
+impl GitStatus {
+    /// Work that only exists in this directory: deleting it would lose it
+    pub fn at_risk(&self) -> bool {
+        self.dirty || self.ahead > 0 || self.unpushed > 0
+    }
+
+    // Unpushed commits beyond those `ahead` already counts: other branches, or
+    // branches without an upstream
+    fn unpushed_elsewhere(&self) -> bool {
+        self.unpushed > self.ahead
+    }
+
+    /// Compact form for the list, e.g. "✚ ↑2"; empty when there is nothing to report
+    pub fn indicators(&self) -> String {
+        let mut parts = Vec::new();
+        if self.dirty {
+            parts.push("✚".to_string());
+        }
+        if self.ahead > 0 {
+            parts.push(format!("↑{}", self.ahead));
+        }
+        if self.behind > 0 {
+            parts.push(format!("↓{}", self.behind));
+        }
+        if self.unpushed_elsewhere() {
+            parts.push(format!("⇡{}", self.unpushed));
+        }
+        parts.join(" ")
+    }
+
+    /// Long form for the preview, e.g. "uncommitted changes, 2 ahead"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.dirty {
+            parts.push("uncommitted changes".to_string());
+        }
+        if self.ahead > 0 {
+            parts.push(format!("{} ahead", self.ahead));
+        }
+        if self.behind > 0 {
+            parts.push(format!("{} behind", self.behind));
+        }
+        if self.unpushed_elsewhere() {
+            parts.push(format!("{} not pushed anywhere", self.unpushed));
+        }
+        if parts.is_empty() {
+            return "clean".to_string();
+        }
+        parts.join(", ")
+    }
+}
+
+/// Reads the status of the repository in `dir` with `git status`, plus a count of
+/// the commits on local branches no remote has; None when git fails (not a
+/// repository, git missing, ...)
+pub fn probe(dir: &Path) -> Option<GitStatus> {
+    let output = git(dir, &["status", "--porcelain=v2", "--branch"])?;
+    let mut status = parse_porcelain(&output);
+    // A repository without commits or remotes still counts: `--not --remotes` is empty
+    status.unpushed = git(
+        dir,
+        &["rev-list", "--count", "--branches", "--not", "--remotes"],
+    )
+    .and_then(|out| out.trim().parse().ok())
+    .unwrap_or(0);
+    Some(status)
+}
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|out| out.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Reads `git status --porcelain=v2 --branch` output
+fn parse_porcelain(output: &str) -> GitStatus {
+    let mut status = GitStatus::default();
+    for line in output.lines() {
+        // "# branch.ab +2 -1" only appears when the branch has an upstream
+        if let Some(ab) = line.strip_prefix("# branch.ab ") {
+            for part in ab.split_whitespace() {
+                if let Some(n) = part.strip_prefix('+') {
+                    status.ahead = n.parse().unwrap_or(0);
+                } else if let Some(n) = part.strip_prefix('-') {
+                    status.behind = n.parse().unwrap_or(0);
+                }
+            }
+        } else if !line.starts_with('#') {
+            status.dirty = true;
+        }
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn porcelain_clean_branch_with_upstream() {
+        let output = "# branch.oid 1234abcd\n# branch.head main\n# branch.upstream origin/main\n# branch.ab +0 -0\n";
+        assert_eq!(parse_porcelain(output), GitStatus::default());
+    }
+
+    #[test]
+    fn porcelain_ahead_behind_and_changes() {
+        let output = "\
+# branch.oid 1234abcd
+# branch.head main
+# branch.upstream origin/main
+# branch.ab +2 -3
+1 .M N... 100644 100644 100644 aaaa bbbb src/main.rs
+? notes.txt
+";
+        let status = parse_porcelain(output);
+        assert_eq!(
+            status,
+            GitStatus {
+                dirty: true,
+                ahead: 2,
+                behind: 3,
+                unpushed: 0,
+            }
+        );
+        assert!(status.at_risk());
+        assert_eq!(status.indicators(), "✚ ↑2 ↓3");
+    }
+
+    #[test]
+    fn porcelain_untracked_only_and_no_upstream() {
+        let output = "# branch.oid (initial)\n# branch.head main\n? a.txt\n";
+        let status = parse_porcelain(output);
+        assert!(status.dirty);
+        assert_eq!((status.ahead, status.behind), (0, 0));
+    }
+
+    #[test]
+    fn porcelain_renames_conflicts_and_detached_head() {
+        for change in [
+            "2 R. N... 100644 100644 100644 aaaa bbbb R100 new.rs\told.rs",
+            "u UU N... 100644 100644 100644 100644 aaaa bbbb cccc both.rs",
+        ] {
+            let output = format!(
+                "# branch.oid 1234abcd\n# branch.head (detached)\n{}\n",
+                change
+            );
+            let status = parse_porcelain(&output);
+            assert!(status.dirty, "{}", change);
+            assert_eq!((status.ahead, status.behind), (0, 0));
+        }
+        // Only headers: a clean detached head
+        let output = "# branch.oid 1234abcd\n# branch.head (detached)\n";
+        assert_eq!(parse_porcelain(output), GitStatus::default());
+    }
+
+    #[test]
+    fn unpushed_commits_without_upstream_are_at_risk() {
+        let status = GitStatus {
+            unpushed: 4,
+            ..GitStatus::default()
+        };
+        assert!(status.at_risk());
+        assert_eq!(status.indicators(), "⇡4");
+        assert_eq!(status.describe(), "4 not pushed anywhere");
+
+        // Already shown as ahead of the upstream
+        let status = GitStatus {
+            ahead: 2,
+            unpushed: 2,
+            ..GitStatus::default()
+        };
+        assert_eq!(status.indicators(), "↑2");
+    }
+
+    #[test]
+    fn probe_counts_commits_of_a_repository_without_remote() {
+        let dir = tempfile::tempdir().unwrap();
+        let run = |args: &[&str]| {
+            let ok = Command::new("git")
+                .arg("-C")
+                .arg(dir.path())
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status
+                .success();
+            assert!(ok, "git {:?}", args);
+        };
+        run(&["init", "-q"]);
+        assert_eq!(probe(dir.path()), Some(GitStatus::default()));
+
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        run(&["add", "."]);
+        run(&["commit", "-qm", "one"]);
+        let status = probe(dir.path()).unwrap();
+        assert!(!status.dirty);
+        assert_eq!(status.unpushed, 1);
+        assert!(status.at_risk());
+    }
+}
@@ -0,0 +1,477 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use std::path::Path;
+use unicode_width::UnicodeWidthChar;
+
+// Light syntax highlighting for the preview: comments, strings, numbers and
+// keywords of common languages, and the block structure of Markdown. It works
+// line by line with a little state (code fences, block comments), so a file
+// is always highlighted from its first line.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Syntax {
+    Plain,
+    Markdown,
+    Code(&'static Lang),
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Lang {
+    line_comment: &'static str,
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static str, // Characters that open a string
+    keywords: &'static [&'static str],
+}
+
+const RUST: Lang = Lang {
+    line_comment: "//",
+    block_comment: Some(("/*", "*/")),
+    quotes: "\"",
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+        "type", "unsafe", "use", "where", "while",
+    ],
+};
+
+const GO: Lang = Lang {
+    line_comment: "//",
+    block_comment: Some(("/*", "*/")),
+    quotes: "\"`'",
+    keywords: &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "false",
+        "for",
+        "func",
+        "go",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "nil",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "true",
+        "type",
+        "var",
+    ],
+};
+
+const C_LIKE: Lang = Lang {
+    line_comment: "//",
+    block_comment: Some(("/*", "*/")),
+    quotes: "\"'`",
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "else",
+        "enum",
+        "export",
+        "extends",
+        "false",
+        "final",
+        "finally",
+        "for",
+        "function",
+        "if",
+        "implements",
+        "import",
+        "interface",
+        "let",
+        "new",
+        "null",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "struct",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typedef",
+        "var",
+        "void",
+        "while",
+    ],
+};
+
+const PYTHON: Lang = Lang {
+    line_comment: "#",
+    block_comment: None,
+    quotes: "\"'",
+    keywords: &[
+        "and", "as", "async", "await", "break", "class", "continue", "def", "elif", "else",
+        "except", "False", "finally", "for", "from", "if", "import", "in", "is", "lambda", "None",
+        "not", "or", "pass", "raise", "return", "True", "try", "while", "with", "yield",
+    ],
+};
+
+const SHELL: Lang = Lang {
+    line_comment: "#",
+    block_comment: None,
+    quotes: "\"'",
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "then", "while",
+    ],
+};
+
+// TOML, YAML and friends: comments and strings only
+const CONFIG: Lang = Lang {
+    line_comment: "#",
+    block_comment: None,
+    quotes: "\"'",
+    keywords: &["true", "false"],
+};
+
+const JSON: Lang = Lang {
+    line_comment: "",
+    block_comment: None,
+    quotes: "\"",
+    keywords: &["true", "false", "null"],
+};
+
+const KEYWORD: Color = Color::Magenta;
+const STRING: Color = Color::Green;
+const NUMBER: Color = Color::Yellow;
+const COMMENT: Color = Color::DarkGray;
+const HEADING: Color = Color::Cyan;
+
+impl Syntax {
+    // Guessed from the file name; unknown files are shown as they are
+    pub fn for_path(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase();
+        if matches!(name.as_str(), "dockerfile" | "makefile" | ".envrc") {
+            return Syntax::Code(&SHELL);
+        }
+        let ext = name.rsplit_once('.').map_or("", |(_, ext)| ext);
+        match ext {
+            "md" | "markdown" => Syntax::Markdown,
+            "rs" => Syntax::Code(&RUST),
+            "go" => Syntax::Code(&GO),
+            "c" | "h" | "cc" | "cpp" | "hpp" | "java" | "kt" | "js" | "jsx" | "ts" | "tsx"
+            | "dart" | "swift" | "cs" | "scala" => Syntax::Code(&C_LIKE),
+            "py" | "rb" => Syntax::Code(&PYTHON),
+            "sh" | "bash" | "zsh" | "fish" => Syntax::Code(&SHELL),
+            "toml" | "yaml" | "yml" | "ini" | "cfg" | "conf" => Syntax::Code(&CONFIG),
+            "json" => Syntax::Code(&JSON),
+            _ => Syntax::Plain,
+        }
+    }
+}
+
+// Highlights `lines`, cutting each one to `width` columns
+pub fn highlight(lines: &[String], syntax: Syntax, width: usize) -> Vec<Line<'static>> {
+    let mut in_block = false; // Inside a code fence or a block comment
+    lines
+        .iter()
+        .map(|line| {
+            let spans = match syntax {
+                Syntax::Plain => vec![Span::raw(line.clone())],
+                Syntax::Markdown => markdown_line(line, &mut in_block),
+                Syntax::Code(lang) => code_line(line, lang, &mut in_block),
+            };
+            truncate(spans, width)
+        })
+        .collect()
+}
+
+fn markdown_line(line: &str, in_fence: &mut bool) -> Vec<Span<'static>> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+        *in_fence = !*in_fence;
+        return vec![Span::styled(line.to_string(), Style::default().fg(COMMENT))];
+    }
+    if *in_fence {
+        return vec![Span::styled(line.to_string(), Style::default().fg(NUMBER))];
+    }
+    if trimmed.starts_with('#') {
+        let style = Style::default().fg(HEADING).add_modifier(Modifier::BOLD);
+        return vec![Span::styled(line.to_string(), style)];
+    }
+    if trimmed.starts_with('>') {
+        return vec![Span::styled(line.to_string(), Style::default().fg(COMMENT))];
+    }
+
+    let mut spans = Vec::new();
+    let mut rest = line;
+    // List bullets
+    let indent = line.len() - trimmed.len();
+    if let Some(item) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| trimmed.strip_prefix(bullet))
+    {
+        spans.push(Span::raw(line[..indent].to_string()));
+        spans.push(Span::styled("• ", Style::default().fg(HEADING)));
+        rest = item;
+    }
+    // Inline `code`
+    for (i, part) in rest.split('`').enumerate() {
+        if i % 2 == 1 {
+            spans.push(Span::styled(part.to_string(), Style::default().fg(NUMBER)));
+        } else if !part.is_empty() {
+            spans.push(Span::raw(part.to_string()));
+        }
+    }
+    spans
+}
+
+fn code_line(line: &str, lang: &Lang, in_comment: &mut bool) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = line;
+    let flush = |plain: &mut String, spans: &mut Vec<Span<'static>>| {
+        if !plain.is_empty() {
+            spans.push(Span::raw(std::mem::take(plain)));
+        }
+    };
+
+    while !rest.is_empty() {
+        // The rest of a block comment opened on an earlier line
+        if *in_comment {
+            let (_, end) = lang.block_comment.unwrap_or(("", ""));
+            let len = rest.find(end).map_or(rest.len(), |i| {
+                *in_comment = false;
+                i + end.len()
+            });
+            spans.push(Span::styled(
+                rest[..len].to_string(),
+                Style::default().fg(COMMENT),
+            ));
+            rest = &rest[len..];
+            continue;
+        }
+        if !lang.line_comment.is_empty() && rest.starts_with(lang.line_comment) {
+            flush(&mut plain, &mut spans);
+            spans.push(Span::styled(rest.to_string(), Style::default().fg(COMMENT)));
+            break;
+        }
+        if let Some((start, _)) = lang.block_comment
+            && rest.starts_with(start)
+        {
+            flush(&mut plain, &mut spans);
+            *in_comment = true;
+            spans.push(Span::styled(start, Style::default().fg(COMMENT)));
+            rest = &rest[start.len()..];
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap_or_default();
+        if lang.quotes.contains(c) {
+            // Up to the closing quote, skipping escaped ones; unterminated runs to the end
+            let mut escaped = false;
+            let len = rest[c.len_utf8()..]
+                .char_indices()
+                .find(|&(_, ch)| {
+                    let close = ch == c && !escaped;
+                    escaped = ch == '\\' && !escaped;
+                    close
+                })
+                .map_or(rest.len(), |(i, _)| c.len_utf8() + i + c.len_utf8());
+            flush(&mut plain, &mut spans);
+            spans.push(Span::styled(
+                rest[..len].to_string(),
+                Style::default().fg(STRING),
+            ));
+            rest = &rest[len..];
+        } else if c.is_alphanumeric() || c == '_' {
+            let len = rest
+                .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..len];
+            let color = if c.is_ascii_digit() {
+                Some(NUMBER)
+            } else if lang.keywords.contains(&word) {
+                Some(KEYWORD)
+            } else {
+                None
+            };
+            match color {
+                Some(color) => {
+                    flush(&mut plain, &mut spans);
+                    spans.push(Span::styled(word.to_string(), Style::default().fg(color)));
+                }
+                None => plain.push_str(word),
+            }
+            rest = &rest[len..];
+        } else {
+            plain.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    flush(&mut plain, &mut spans);
+    spans
+}
+
+// Keeps what fits in the first `width` columns of a line; a wide character that
+// would straddle the edge is left out
+fn truncate(spans: Vec<Span<'static>>, width: usize) -> Line<'static> {
+    let mut left = width;
+    let mut kept = Vec::new();
+    for span in spans {
+        if left == 0 {
+            break;
+        }
+        if span.width() <= left {
+            left -= span.width();
+            kept.push(span);
+        } else {
+            let cut: String = span
+                .content
+                .chars()
+                .take_while(|c| {
+                    let w = c.width().unwrap_or(0);
+                    let fits = w <= left;
+                    if fits {
+                        left -= w;
+                    }
+                    fits
+                })
+                .collect();
+            kept.push(Span::styled(cut, span.style));
+            left = 0;
+        }
+    }
+    Line::from(kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The text of each span with its color, None for plain text
+    fn colored(line: &Line) -> Vec<(String, Option<Color>)> {
+        line.spans
+            .iter()
+            .map(|span| (span.content.to_string(), span.style.fg))
+            .collect()
+    }
+
+    fn one(line: &str, syntax: Syntax) -> Vec<(String, Option<Color>)> {
+        colored(&highlight(&[line.to_string()], syntax, 200)[0])
+    }
+
+    fn piece(text: &str, color: Option<Color>) -> (String, Option<Color>) {
+        (text.to_string(), color)
+    }
+
+    #[test]
+    fn syntax_is_guessed_from_the_file_name() {
+        let syntax = |name: &str| Syntax::for_path(Path::new(name));
+        assert_eq!(syntax("README.md"), Syntax::Markdown);
+        assert_eq!(syntax("src/main.RS"), Syntax::Code(&RUST));
+        assert_eq!(syntax("Makefile"), Syntax::Code(&SHELL));
+        assert_eq!(syntax("Cargo.toml"), Syntax::Code(&CONFIG));
+        assert_eq!(syntax("notes.txt"), Syntax::Plain);
+        assert_eq!(syntax("LICENSE"), Syntax::Plain);
+    }
+
+    #[test]
+    fn code_keywords_strings_numbers_and_comments() {
+        assert_eq!(
+            one(r#"let s = "a \"b\"" + 42; // done"#, Syntax::Code(&RUST)),
+            [
+                piece("let", Some(KEYWORD)),
+                piece(" s = ", None),
+                piece(r#""a \"b\"""#, Some(STRING)),
+                piece(" + ", None),
+                piece("42", Some(NUMBER)),
+                piece("; ", None),
+                piece("// done", Some(COMMENT)),
+            ]
+        );
+        // Keywords only count as whole words
+        assert_eq!(one("letter", Syntax::Code(&RUST)), [piece("letter", None)]);
+        // An unterminated string runs to the end of the line
+        assert_eq!(
+            one("x = 'open", Syntax::Code(&PYTHON)),
+            [piece("x = ", None), piece("'open", Some(STRING))]
+        );
+    }
+
+    #[test]
+    fn block_comments_carry_over_to_the_next_lines() {
+        let lines = [
+            "a /* one".to_string(),
+            "two".to_string(),
+            "*/ fn".to_string(),
+        ];
+        let lines = highlight(&lines, Syntax::Code(&RUST), 200);
+        assert_eq!(
+            colored(&lines[0]),
+            [
+                piece("a ", None),
+                piece("/*", Some(COMMENT)),
+                piece(" one", Some(COMMENT))
+            ]
+        );
+        assert_eq!(colored(&lines[1]), [piece("two", Some(COMMENT))]);
+        assert_eq!(
+            colored(&lines[2]),
+            [
+                piece("*/", Some(COMMENT)),
+                piece(" ", None),
+                piece("fn", Some(KEYWORD))
+            ]
+        );
+    }
+
+    #[test]
+    fn markdown_headings_fences_bullets_and_inline_code() {
+        let lines: Vec<String> = ["# Title", "```", "# not a heading", "```", "  - use `x`"]
+            .map(String::from)
+            .to_vec();
+        let lines = highlight(&lines, Syntax::Markdown, 200);
+        assert_eq!(colored(&lines[0]), [piece("# Title", Some(HEADING))]);
+        assert_eq!(colored(&lines[2]), [piece("# not a heading", Some(NUMBER))]);
+        assert_eq!(
+            colored(&lines[4]),
+            [
+                piece("  ", None),
+                piece("• ", Some(HEADING)),
+                piece("use ", None),
+                piece("x", Some(NUMBER))
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_are_cut_by_display_width() {
+        let lines = ["日本語 text".to_string()];
+        for width in 0..12 {
+            let line = &highlight(&lines, Syntax::Plain, width)[0];
+            assert!(line.width() <= width, "{} > {}", line.width(), width);
+        }
+        assert_eq!(highlight(&lines, Syntax::Plain, 5)[0].to_string(), "日本");
+        assert_eq!(
+            highlight(&lines, Syntax::Plain, 8)[0].to_string(),
+            "日本語 t"
+        );
+    }
+}
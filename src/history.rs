@@ -0,0 +1,178 @@
+use crate::paths::{CLONES_FILE, Paths, VISITS_FILE};
+use crate::preview::format_age;
+use chrono::{DateTime, Local};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// Every successful clone is appended to CLONES_FILE in the state dir,
+// one `timestamp<TAB>url<TAB>name` line per clone
+pub struct CloneRecord {
+    pub cloned_at: DateTime<Local>,
+    pub url: String,
+    pub name: String,
+}
+
+// Appends a clone to the history. Failures only warn: the clone itself succeeded.
+pub fn record_clone(paths: &Paths, url: &str, name: &str) {
+    let result = fs::create_dir_all(&paths.state_dir).and_then(|_| {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(paths.state_file(CLONES_FILE))?;
+        writeln!(file, "{}\t{}\t{}", Local::now().to_rfc3339(), url, name)
+    });
+    if let Err(e) = result {
+        eprintln!("Warning: could not update the clone history: {}", e);
+    }
+}
+
+// Past clones, newest first, keeping only the latest clone of each directory name
+pub fn load_clones(paths: &Paths) -> Vec<CloneRecord> {
+    let Ok(contents) = fs::read_to_string(paths.state_file(CLONES_FILE)) else {
+        return Vec::new();
+    };
+    let mut records: Vec<CloneRecord> = Vec::new();
+    for line in contents.lines().rev() {
+        let mut fields = line.splitn(3, '\t');
+        let (Some(date), Some(url), Some(name)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let Ok(cloned_at) = DateTime::parse_from_rfc3339(date) else {
+            continue;
+        };
+        if records.iter().any(|r| r.name == name) {
+            continue;
+        }
+        records.push(CloneRecord {
+            cloned_at: cloned_at.with_timezone(&Local),
+            url: url.to_string(),
+            name: name.to_string(),
+        });
+    }
+    records
+}
+
+// Lists the clone history on stderr, flagging directories that no longer exist
+pub fn print_clones(records: &[CloneRecord], tries_dir: &Path) {
+    if records.is_empty() {
+        eprintln!("No clones recorded yet.");
+        return;
+    }
+    let width = records.iter().map(|r| r.name.len()).max().unwrap_or(0);
+    for record in records {
+        let status = if tries_dir.join(&record.name).exists() {
+            ""
+        } else {
+            "  (deleted)"
+        };
+        eprintln!(
+            "{}  {:<width$}  {}{}",
+            record.cloned_at.format("%Y-%m-%d %H:%M"),
+            record.name,
+            record.url,
+            status,
+        );
+    }
+}
+
+// Every time the shell is sent into an experiment, a `timestamp<TAB>path` line is
+// appended to VISITS_FILE in the state dir
+pub struct VisitRecord {
+    pub visited_at: DateTime<Local>,
+    pub path: PathBuf,
+}
+
+// The visits file is cut back to its newest half once it grows past this many lines
+const MAX_VISIT_LINES: usize = 1000;
+
+// Appends a visit to the history. Failures only warn: the jump itself goes ahead.
+pub fn record_visit(paths: &Paths, path: &Path) {
+    let file = paths.state_file(VISITS_FILE);
+    let line = format!("{}\t{}", Local::now().to_rfc3339(), path.display());
+    let result = fs::create_dir_all(&paths.state_dir).and_then(|_| {
+        let contents = fs::read_to_string(&file).unwrap_or_default();
+        let lines: Vec<&str> = contents.lines().collect();
+        if lines.len() < MAX_VISIT_LINES {
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&file)?;
+            return writeln!(file, "{}", line);
+        }
+        let mut kept = lines[lines.len() - MAX_VISIT_LINES / 2..].join("\n");
+        kept.push('\n');
+        kept.push_str(&line);
+        kept.push('\n');
+        fs::write(&file, kept)
+    });
+    if let Err(e) = result {
+        eprintln!("Warning: could not update the visit history: {}", e);
+    }
+}
+
+// Past visits, newest first, keeping only the latest visit of each directory
+pub fn load_visits(paths: &Paths) -> Vec<VisitRecord> {
+    let Ok(contents) = fs::read_to_string(paths.state_file(VISITS_FILE)) else {
+        return Vec::new();
+    };
+    let mut records: Vec<VisitRecord> = Vec::new();
+    for line in contents.lines().rev() {
+        let Some((date, path)) = line.split_once('\t') else {
+            continue;
+        };
+        let Ok(visited_at) = DateTime::parse_from_rfc3339(date) else {
+            continue;
+        };
+        let path = PathBuf::from(path);
+        if records.iter().any(|r| r.path == path) {
+            continue;
+        }
+        records.push(VisitRecord {
+            visited_at: visited_at.with_timezone(&Local),
+            path,
+        });
+    }
+    records
+}
+
+// Where a visited experiment is, for display: relative to `tries_dir` when inside it
+pub fn visit_label(path: &Path, tries_dir: &Path) -> String {
+    match path.strip_prefix(tries_dir) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative.display().to_string(),
+        _ => path.display().to_string(),
+    }
+}
+
+// Lists the visit history on stderr, numbered for `try-rs recent <N>`, flagging
+// directories that no longer exist
+pub fn print_visits(records: &[VisitRecord], tries_dir: &Path) {
+    if records.is_empty() {
+        eprintln!("No visits recorded yet.");
+        return;
+    }
+    let labels: Vec<String> = records
+        .iter()
+        .map(|r| visit_label(&r.path, tries_dir))
+        .collect();
+    let width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let number_width = records.len().to_string().len();
+    let now = Local::now();
+    for (i, (record, label)) in records.iter().zip(&labels).enumerate() {
+        let status = if record.path.is_dir() {
+            ""
+        } else {
+            "  (deleted)"
+        };
+        let age = (now - record.visited_at).to_std().unwrap_or_default();
+        eprintln!(
+            "{:>number_width$})  {}  {:<width$}  {} ago{}",
+            i + 1,
+            record.visited_at.format("%Y-%m-%d %H:%M"),
+            label,
+            format_age(age),
+            status,
+        );
+    }
+}
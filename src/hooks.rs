@@ -0,0 +1,250 @@
+use crate::paths::app_config_dir;
+use crate::templates;
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+// `on_select` from config.toml is a command with `{path}` and `{name}` placeholders
+// that replaces the `cd` the shell wrapper evals.
+//
+// Lifecycle hooks run when an experiment is created, entered or deleted: first the
+// `on_create` / `on_enter` / `on_delete` command from config.toml (same placeholders),
+// then the executable `on-create` / `on-enter` / `on-delete` in the hooks directory.
+// Both run inside the experiment with TRY_ENTRY_PATH and TRY_ENTRY_NAME set.
+
+// Scripts run by the lifecycle hooks: `hooks/` next to the config file that was
+// loaded, or in the default config directory without one
+pub fn hooks_dir(config_file: Option<&Path>) -> PathBuf {
+    match config_file.and_then(Path::parent) {
+        Some(dir) => dir.join("hooks"),
+        None => app_config_dir().join("hooks"),
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Hook {
+    Create,
+    Enter,
+    Delete,
+}
+
+impl Hook {
+    // Name of the script in the hooks directory
+    pub fn script_name(self) -> &'static str {
+        match self {
+            Hook::Create => "on-create",
+            Hook::Enter => "on-enter",
+            Hook::Delete => "on-delete",
+        }
+    }
+}
+
+// The hook commands from config.toml, and where the hook scripts live
+#[derive(Clone, Default)]
+pub struct Hooks {
+    pub on_create: Option<String>,
+    pub on_enter: Option<String>,
+    pub on_delete: Option<String>,
+    pub scripts: Option<PathBuf>, // None runs no scripts
+}
+
+impl Hooks {
+    fn command(&self, hook: Hook) -> Option<&str> {
+        match hook {
+            Hook::Create => self.on_create.as_deref(),
+            Hook::Enter => self.on_enter.as_deref(),
+            Hook::Delete => self.on_delete.as_deref(),
+        }
+    }
+
+    // Runs the command and script of `hook` for the experiment at `path`, stopping
+    // at the first failure. With `quiet` (inside the TUI) their output is dropped
+    // instead of going to stderr, and stderr only shows up in the error.
+    pub fn run(&self, hook: Hook, path: &Path, quiet: bool) -> Result<()> {
+        let name = hook.script_name();
+        if let Some(template) = self.command(hook) {
+            log::debug!("{}: running `{}` in {}", name, template, path.display());
+            run_command(template, path, quiet).with_context(|| name.to_string())?;
+        }
+        let Some(script) = self.scripts.as_ref().map(|dir| dir.join(name)) else {
+            return Ok(());
+        };
+        if script.is_file() && !is_executable(&script) {
+            // Likely a missing chmod +x; the command above already ran
+            let warning = format!("skipping {}: it is not executable", script.display());
+            if quiet {
+                log::warn!("{}", warning);
+            } else {
+                eprintln!("Warning: {}", warning);
+            }
+        } else if script.is_file() {
+            log::debug!("{}: running {}", name, script.display());
+            let mut cmd = Command::new(&script);
+            execute(&mut cmd, path, quiet)
+                .with_context(|| format!("{} hook {}", name, script.display()))?;
+        }
+        Ok(())
+    }
+
+    // Runs the create hooks in the new experiment `dir`. A failure only warns:
+    // the experiment exists either way.
+    pub fn created(&self, dir: &Path) {
+        if let Err(e) = self.run(Hook::Create, dir, false) {
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+
+    // Runs the enter hooks before the shell changes into `dir`; a failure only warns
+    pub fn entered(&self, dir: &Path) {
+        if let Err(e) = self.run(Hook::Enter, dir, false) {
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+}
+
+// Runs a config command with `{path}` and `{name}` placeholders inside the
+// experiment at `path`, like the hook commands (also used by custom integrations)
+pub fn run_command(template: &str, path: &Path, quiet: bool) -> Result<()> {
+    let command = command_line(template, path);
+    let mut cmd = templates::shell_command(&command);
+    execute(&mut cmd, path, quiet).with_context(|| format!("`{}`", command))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+fn execute(cmd: &mut Command, path: &Path, quiet: bool) -> Result<()> {
+    let name = path.file_name().unwrap_or_default();
+    cmd.current_dir(path)
+        .env("TRY_ENTRY_PATH", path)
+        .env("TRY_ENTRY_NAME", name)
+        .stdin(Stdio::null());
+    if quiet {
+        let output = cmd.stdout(Stdio::null()).output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            match stderr.lines().rfind(|l| !l.trim().is_empty()) {
+                Some(line) => bail!("failed ({}): {}", output.status, line.trim()),
+                None => bail!("failed ({})", output.status),
+            }
+        }
+    } else {
+        // Stdout belongs to the shell wrapper
+        let status = cmd.stdout(Stdio::from(std::io::stderr())).status()?;
+        if !status.success() {
+            bail!("failed ({})", status);
+        }
+    }
+    Ok(())
+}
+
+// Fills the placeholders of `template`, each value quoted with `quote`
+pub fn expand(template: &str, path: &Path, quote: impl Fn(&str) -> String) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    template
+        .replace("{path}", &quote(&path.to_string_lossy()))
+        .replace("{name}", &quote(&name))
+}
+
+// Fills the placeholders of a config command for the shell `templates::shell_command`
+// goes through. sh gets the values quoted; cmd on Windows has no quoting that
+// survives `"` and `%`, so it reads them from the variables `execute` sets instead.
+fn command_line(template: &str, path: &Path) -> String {
+    if cfg!(windows) {
+        template
+            .replace("{path}", "\"%TRY_ENTRY_PATH%\"")
+            .replace("{name}", "\"%TRY_ENTRY_NAME%\"")
+    } else {
+        expand(template, path, |s| {
+            format!("'{}'", s.replace('\'', "'\\''"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+
+    // The hooks below are written for sh
+    #[cfg(unix)]
+    fn script(dir: &Path, name: &str, body: &str, mode: u32) {
+        let path = dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    fn scripts_come_from_next_to_the_config_file() {
+        let config = Path::new("/etc/try/config.toml");
+        assert_eq!(hooks_dir(Some(config)), Path::new("/etc/try/hooks"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_the_command_then_the_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let (scripts, entry) = (dir.path().join("hooks"), dir.path().join("entry"));
+        fs::create_dir(&scripts).unwrap();
+        fs::create_dir(&entry).unwrap();
+        script(&scripts, "on-create", "echo script >> log", 0o755);
+        let hooks = Hooks {
+            on_create: Some("echo command {name} > log".to_string()),
+            scripts: Some(scripts),
+            ..Hooks::default()
+        };
+        hooks.run(Hook::Create, &entry, true).unwrap();
+        let log = fs::read_to_string(entry.join("log")).unwrap();
+        assert_eq!(log, "command entry\nscript\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn placeholders_survive_shell_characters() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = dir.path().join("it's $HOME \"%x%\" & ;");
+        fs::create_dir(&entry).unwrap();
+        let hooks = Hooks {
+            on_enter: Some("printf %s {name} > log".to_string()),
+            ..Hooks::default()
+        };
+        hooks.run(Hook::Enter, &entry, true).unwrap();
+        let log = fs::read_to_string(entry.join("log")).unwrap();
+        assert_eq!(log, "it's $HOME \"%x%\" & ;");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_script_that_is_not_executable_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        script(dir.path(), "on-delete", "exit 1", 0o644);
+        let hooks = Hooks {
+            scripts: Some(dir.path().to_path_buf()),
+            ..Hooks::default()
+        };
+        hooks.run(Hook::Delete, dir.path(), true).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_failing_hook_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        script(dir.path(), "on-delete", "echo nope >&2; exit 3", 0o755);
+        let hooks = Hooks {
+            scripts: Some(dir.path().to_path_buf()),
+            ..Hooks::default()
+        };
+        let err = hooks.run(Hook::Delete, dir.path(), true).unwrap_err();
+        assert!(format!("{:#}", err).ends_with("nope"), "{:#}", err);
+    }
+}
@@ -0,0 +1,203 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Which set of glyphs the TUI draws, chosen with `icons` in config.toml
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum IconMode {
+    #[default]
+    Nerd, // Nerd Font glyphs for project types, emoji for the rest
+    Emoji,
+    Ascii, // Plain text, for fonts and consoles without either
+    None,
+}
+
+impl IconMode {
+    pub const NAMES: &str = "nerd, emoji, ascii, none";
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "nerd" => Some(IconMode::Nerd),
+            "emoji" => Some(IconMode::Emoji),
+            "ascii" => Some(IconMode::Ascii),
+            "none" => Some(IconMode::None),
+            _ => None,
+        }
+    }
+}
+
+// Every glyph the TUI draws
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Icon {
+    Logo,     // Around the title
+    Folder,   // Icon column of an entry
+    Favorite, // Icon column of a favorite
+    Marked,   // Icon column of a marked entry
+    Dir,      // Folders in the preview's file list
+    File,     // Files in the preview's file list
+    Rust,
+    Maven,
+    Flutter,
+    Go,
+    Python,
+    Mise,
+    Direnv,
+    Asdf,
+    Node,
+    Deno,
+    Zig,
+    Nix,
+    Docker,
+    Terraform,
+    Elixir,
+    Git,
+    Worktree, // Git worktree or submodule
+}
+
+// Config name of every icon and its glyph in the nerd, emoji and ascii sets
+const GLYPHS: &[(Icon, &str, [&str; 3])] = &[
+    (Icon::Logo, "logo", ["🦀", "🦀", ""]),
+    (Icon::Folder, "folder", ["📁", "📁", "-"]),
+    (Icon::Favorite, "favorite", ["⭐", "⭐", "*"]),
+    (Icon::Marked, "marked", ["✅", "✅", "x"]),
+    (Icon::Dir, "dir", ["📁", "📁", "+"]),
+    (Icon::File, "file", ["📄", "📄", " "]),
+    (Icon::Rust, "rust", ["\u{e7a8}", "🦀", "rs"]),
+    (Icon::Maven, "maven", ["\u{e674}", "☕", "mvn"]),
+    (Icon::Flutter, "flutter", ["\u{e798}", "🐦", "dart"]),
+    (Icon::Go, "go", ["\u{e627}", "🐹", "go"]),
+    (Icon::Python, "python", ["\u{e73c}", "🐍", "py"]),
+    (Icon::Mise, "mise", ["\u{f0b14}", "🔧", "mise"]),
+    (Icon::Direnv, "direnv", ["\u{f0493}", "🔑", "env"]),
+    (Icon::Asdf, "asdf", ["\u{f0328}", "🧰", "asdf"]),
+    (Icon::Node, "node", ["\u{e718}", "🟢", "js"]),
+    (Icon::Deno, "deno", ["\u{e7c0}", "🦕", "deno"]),
+    (Icon::Zig, "zig", ["\u{e6a9}", "⚡", "zig"]),
+    (Icon::Nix, "nix", ["\u{f313}", "🧊", "nix"]),
+    (Icon::Docker, "docker", ["\u{e7b0}", "🐳", "dock"]),
+    (Icon::Terraform, "terraform", ["\u{f1062}", "🪐", "tf"]),
+    (Icon::Elixir, "elixir", ["\u{e62d}", "💧", "ex"]),
+    (Icon::Git, "git", ["\u{e702}", "🌿", "git"]),
+    (Icon::Worktree, "worktree", ["\u{f062c}", "🌱", "wt"]),
+];
+
+// `icons = "ascii"`, or an `[icons]` table with `mode` and glyphs by icon name,
+// e.g. `rust = "R"`
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum IconsConfig {
+    Mode(String),
+    Table(HashMap<String, String>),
+}
+
+// The glyphs of the configured set, with the `[icons]` overrides
+#[derive(Clone, Default)]
+pub struct Icons {
+    mode: IconMode,
+    overrides: HashMap<Icon, String>,
+}
+
+impl Icons {
+    pub fn new(mode: IconMode) -> Self {
+        Self {
+            mode,
+            overrides: HashMap::new(),
+        }
+    }
+
+    // Unknown modes and icon names are reported and skipped; without a mode, `mode` is used
+    pub fn from_config(config: &IconsConfig, mode: IconMode) -> Self {
+        let parse_mode = |value: &str| {
+            IconMode::parse(value).or_else(|| {
+                eprintln!(
+                    "Warning: unknown icons mode '{}' in config (expected one of: {})",
+                    value,
+                    IconMode::NAMES
+                );
+                None
+            })
+        };
+        let mut icons = Icons::new(mode);
+        match config {
+            IconsConfig::Mode(value) => icons.mode = parse_mode(value).unwrap_or(mode),
+            IconsConfig::Table(table) => {
+                for (name, glyph) in table {
+                    if name == "mode" {
+                        icons.mode = parse_mode(glyph).unwrap_or(mode);
+                    } else if let Some((icon, ..)) = GLYPHS.iter().find(|(_, n, _)| n == name) {
+                        icons.overrides.insert(*icon, glyph.clone());
+                    } else {
+                        eprintln!("Warning: unknown icon '{}' in [icons]", name);
+                    }
+                }
+            }
+        }
+        icons
+    }
+
+    pub fn mode(&self) -> IconMode {
+        self.mode
+    }
+
+    // The glyph drawn for `icon`; empty when nothing should be drawn
+    pub fn get(&self, icon: Icon) -> &str {
+        if let Some(glyph) = self.overrides.get(&icon) {
+            return glyph;
+        }
+        let set = match self.mode {
+            IconMode::Nerd => 0,
+            IconMode::Emoji => 1,
+            IconMode::Ascii => 2,
+            IconMode::None => return "",
+        };
+        GLYPHS
+            .iter()
+            .find(|(i, ..)| *i == icon)
+            .map_or("", |(_, _, glyphs)| glyphs[set])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_set_draws_the_plain_text_markers() {
+        let icons = Icons::new(IconMode::Ascii);
+        let glyphs = [
+            (Icon::Logo, ""),
+            (Icon::Folder, "-"),
+            (Icon::Favorite, "*"),
+            (Icon::Marked, "x"),
+            (Icon::Dir, "+"),
+            (Icon::Rust, "rs"),
+            (Icon::Git, "git"),
+            (Icon::Worktree, "wt"),
+        ];
+        for (icon, glyph) in glyphs {
+            assert_eq!(icons.get(icon), glyph, "{:?}", icon);
+        }
+    }
+
+    #[test]
+    fn overrides_win_over_the_mode_set() {
+        let config: IconsConfig = toml::from_str::<HashMap<String, IconsConfig>>(
+            "icons = { mode = \"emoji\", rust = \"R\", git = \"\", bogus = \"?\" }",
+        )
+        .unwrap()
+        .remove("icons")
+        .unwrap();
+        let icons = Icons::from_config(&config, IconMode::Ascii);
+        assert_eq!(icons.mode(), IconMode::Emoji);
+        assert_eq!(icons.get(Icon::Rust), "R");
+        assert_eq!(icons.get(Icon::Git), "");
+        assert_eq!(icons.get(Icon::Go), "🐹");
+    }
+
+    #[test]
+    fn unknown_mode_keeps_the_given_one() {
+        let icons = Icons::from_config(&IconsConfig::Mode("fancy".into()), IconMode::Ascii);
+        assert_eq!(icons.mode(), IconMode::Ascii);
+        let icons = Icons::from_config(&IconsConfig::Mode(" None ".into()), IconMode::Ascii);
+        assert_eq!(icons.get(Icon::Folder), "");
+    }
+}
@@ -0,0 +1,200 @@
+use crate::config::Settings;
+use crate::hooks;
+use crate::tmux;
+use crate::utils::on_path;
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+// Terminal multiplexer or emulator that Ctrl+T opens experiments in,
+// chosen with `terminal_integration` in config.toml
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum Integration {
+    #[default]
+    Tmux,
+    Zellij,
+    WezTerm,
+    Custom(Custom),
+}
+
+// An `[integrations.<name>]` section with `steps`: commands with `{path}` and
+// `{name}` placeholders run in order inside the experiment
+#[derive(Clone, PartialEq, Debug)]
+pub struct Custom {
+    pub name: String,
+    pub steps: Vec<String>,
+}
+
+// Where Zellij and WezTerm put the experiment (`mode` in their config section)
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Placement {
+    #[default]
+    Tab,
+    Pane,
+}
+
+impl Integration {
+    pub const NAMES: &str = "tmux, zellij, wezterm, or an [integrations.<name>] with steps";
+
+    // A built-in integration by name; custom ones come from their config section
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "tmux" => Some(Integration::Tmux),
+            "zellij" => Some(Integration::Zellij),
+            "wezterm" => Some(Integration::WezTerm),
+            _ => None,
+        }
+    }
+
+    // The program the integration drives; for a custom one, the first word of its
+    // first step
+    pub fn program(&self) -> &str {
+        match self {
+            Integration::Tmux => "tmux",
+            Integration::Zellij => "zellij",
+            Integration::WezTerm => "wezterm",
+            Integration::Custom(custom) => custom
+                .steps
+                .first()
+                .and_then(|step| step.split_whitespace().next())
+                .unwrap_or(&custom.name),
+        }
+    }
+
+    pub fn installed(&self) -> bool {
+        let flag = match self {
+            Integration::Tmux => "-V",
+            Integration::Custom(_) => return on_path(self.program()),
+            _ => "--version",
+        };
+        Command::new(self.program())
+            .arg(flag)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    }
+}
+
+impl Placement {
+    pub const NAMES: &str = "tab, pane";
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "tab" => Some(Placement::Tab),
+            "pane" => Some(Placement::Pane),
+            _ => None,
+        }
+    }
+}
+
+// Opens `dir` with the configured integration. What can run from here runs
+// right away and gives None; a command that needs the terminal (attaching to
+// a session) is handed back for the shell wrapper to run.
+pub fn open(settings: &Settings, dir: &Path) -> Result<Option<Vec<String>>> {
+    match &settings.terminal_integration {
+        Integration::Tmux => tmux::open(&settings.tmux, dir),
+        Integration::Zellij => zellij(settings.zellij, dir),
+        Integration::WezTerm => wezterm(settings.wezterm, dir),
+        Integration::Custom(custom) => steps(custom, dir),
+    }
+}
+
+// Runs the steps of a custom integration, stopping at the first that fails
+fn steps(custom: &Custom, dir: &Path) -> Result<Option<Vec<String>>> {
+    for step in &custom.steps {
+        hooks::run_command(step, dir, false).with_context(|| custom.name.clone())?;
+    }
+    Ok(None)
+}
+
+fn zellij(placement: Placement, dir: &Path) -> Result<Option<Vec<String>>> {
+    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+    let dir = dir.to_string_lossy();
+    if std::env::var_os("ZELLIJ").is_none() {
+        let args = [
+            "zellij",
+            "attach",
+            "--create",
+            &name,
+            "options",
+            "--default-cwd",
+            &dir,
+        ];
+        return Ok(Some(args.iter().map(|s| s.to_string()).collect()));
+    }
+    match placement {
+        Placement::Tab => run(
+            "zellij",
+            &["action", "new-tab", "--name", &name, "--cwd", &dir],
+        )?,
+        Placement::Pane => run("zellij", &["action", "new-pane", "--cwd", &dir])?,
+    }
+    Ok(None)
+}
+
+// `wezterm cli` talks to the running GUI; outside WezTerm a new window is started
+fn wezterm(placement: Placement, dir: &Path) -> Result<Option<Vec<String>>> {
+    let dir = dir.to_string_lossy();
+    if std::env::var_os("WEZTERM_PANE").is_none() {
+        Command::new("wezterm")
+            .args(["start", "--cwd", &dir])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        return Ok(None);
+    }
+    match placement {
+        Placement::Tab => run("wezterm", &["cli", "spawn", "--cwd", &dir])?,
+        Placement::Pane => run("wezterm", &["cli", "split-pane", "--cwd", &dir])?,
+    }
+    Ok(None)
+}
+
+// Runs a command of the integration, turning a failure into its stderr
+pub fn run(program: &str, args: &[&str]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "{} {} failed: {}",
+            program,
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn custom_steps_run_in_order_until_one_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let custom = |steps: &[&str]| Custom {
+            name: "kitty".to_string(),
+            steps: steps.iter().map(|s| s.to_string()).collect(),
+        };
+        steps(&custom(&["echo {name} > log", "echo 2 >> log"]), dir.path()).unwrap();
+        let name = dir.path().file_name().unwrap().to_string_lossy();
+        let log = fs::read_to_string(dir.path().join("log")).unwrap();
+        assert_eq!(log, format!("{}\n2\n", name));
+        assert!(steps(&custom(&["false", "echo 3 >> log"]), dir.path()).is_err());
+        assert_eq!(fs::read_to_string(dir.path().join("log")).unwrap(), log);
+    }
+
+    #[test]
+    fn a_custom_integration_needs_its_first_program() {
+        let integration = Integration::Custom(Custom {
+            name: "kitty".to_string(),
+            steps: vec!["kitty @ launch --cwd {path}".to_string()],
+        });
+        assert_eq!(integration.program(), "kitty");
+    }
+}
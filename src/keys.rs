@@ -0,0 +1,338 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Actions of the main list that the `[keys]` section of config.toml can rebind
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    Up,
+    Down,
+    Confirm, // Select the entry, or create what was typed
+    Back,    // Clear the query, or quit when it is empty (see esc_behavior)
+    Quit,
+    Delete,
+    Edit,
+    Mark,
+    Undo,
+    Pin,
+    GitInit,
+    OpenUrl,
+    Sort,
+    Preview,
+    Diff,
+    Browse,
+    Archive,
+    Archives,
+    Favorite,
+    Notes,
+    Rename,
+    Spawn,    // Open the entry in tmux, Zellij or WezTerm
+    Worktree, // Make a new experiment as a git worktree of the entry
+    Root,     // Switch to the next of the [[roots]]
+    MoveRoot, // Move the entry to another of the [[roots]]
+    Recent,   // List the experiments entered most recently
+    Theme,    // Pick a theme preset, previewed live and saved to config.toml
+    ScrollUp, // Page through the preview
+    ScrollDown,
+    PageUp, // Move the selection a page up the list
+    PageDown,
+    HalfPageUp,
+    HalfPageDown,
+    First, // Jump to the top of the list
+    Last,
+}
+
+// Config name and default chords of every action
+const BINDINGS: &[(Action, &str, &[&str])] = &[
+    (Action::Up, "up", &["up"]),
+    (Action::Down, "down", &["down"]),
+    (Action::Confirm, "confirm", &["enter"]),
+    (Action::Back, "back", &["esc"]),
+    (Action::Quit, "quit", &["ctrl-c"]),
+    (Action::Delete, "delete", &["ctrl-d"]),
+    (Action::Edit, "edit", &["ctrl-e"]),
+    (Action::Mark, "mark", &["ctrl-space"]),
+    (Action::Undo, "undo", &["ctrl-z"]),
+    (Action::Pin, "pin", &["ctrl-p"]),
+    (Action::GitInit, "git_init", &["ctrl-g"]),
+    (Action::OpenUrl, "open_url", &["ctrl-o"]),
+    (Action::Sort, "sort", &["f3"]),
+    (Action::Preview, "preview", &["f4"]),
+    (Action::Diff, "diff", &["f5"]),
+    (Action::Browse, "browse", &["tab"]),
+    (Action::Archive, "archive", &["ctrl-a"]),
+    (Action::Archives, "archives", &["f6"]),
+    (Action::Favorite, "favorite", &["ctrl-f"]),
+    (Action::Notes, "notes", &["ctrl-n"]),
+    (Action::Rename, "rename", &["f2"]),
+    (Action::Spawn, "spawn", &["ctrl-t"]),
+    (Action::Worktree, "worktree", &["ctrl-w"]),
+    (Action::Root, "root", &["f7"]),
+    (Action::MoveRoot, "move_root", &["alt-m"]),
+    (Action::Recent, "recent", &["f8"]),
+    (Action::Theme, "theme", &["f9"]),
+    (Action::ScrollUp, "scroll_up", &["shift-pageup", "alt-up"]),
+    (
+        Action::ScrollDown,
+        "scroll_down",
+        &["shift-pagedown", "alt-down"],
+    ),
+    (Action::PageUp, "page_up", &["pageup"]),
+    (Action::PageDown, "page_down", &["pagedown"]),
+    (Action::HalfPageUp, "half_page_up", &["ctrl-u"]),
+    (Action::HalfPageDown, "half_page_down", &["alt-d"]),
+    (Action::First, "first", &["home"]),
+    (Action::Last, "last", &["end"]),
+];
+
+// A key plus its modifiers, as written in config.toml ("ctrl-d", "alt-x", "f3")
+type Chord = (KeyCode, KeyModifiers);
+
+// `delete = "ctrl-x"` or `delete = ["ctrl-x", "delete"]`
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Chords {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Chords {
+    fn names(&self) -> Vec<&str> {
+        match self {
+            Chords::One(name) => vec![name.as_str()],
+            Chords::Many(names) => names.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct KeyMap {
+    actions: HashMap<Chord, Action>,
+    labels: HashMap<Action, String>, // First chord of each action, for the footer
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut keymap = KeyMap {
+            actions: HashMap::new(),
+            labels: HashMap::new(),
+        };
+        for (action, _, chords) in BINDINGS {
+            for chord in chords.iter().filter_map(|c| parse_chord(c)) {
+                keymap.bind(*action, chord);
+            }
+        }
+        keymap
+    }
+}
+
+impl KeyMap {
+    // The defaults with the `[keys]` overrides applied. An overridden action loses its
+    // default chords; unknown actions and chords are warned about and skipped.
+    pub fn from_config(keys: &HashMap<String, Chords>) -> Self {
+        let mut keymap = KeyMap::default();
+        for (name, chords) in keys {
+            let Some(&(action, _, _)) = BINDINGS.iter().find(|(_, n, _)| n == name) else {
+                eprintln!(
+                    "Warning: unknown action '{}' in [keys] (expected one of: {})",
+                    name,
+                    BINDINGS
+                        .iter()
+                        .map(|(_, n, _)| *n)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                continue;
+            };
+            let parsed: Vec<Chord> = chords
+                .names()
+                .into_iter()
+                .filter_map(|chord| match parse_chord(chord) {
+                    Some(parsed) if is_typed(parsed) => {
+                        eprintln!(
+                            "Warning: '{}' for '{}' in [keys] is typed into the search; use ctrl- or alt-",
+                            chord, name
+                        );
+                        None
+                    }
+                    Some(parsed) => Some(parsed),
+                    None => {
+                        eprintln!("Warning: unknown key '{}' for '{}' in [keys]", chord, name);
+                        None
+                    }
+                })
+                .collect();
+            if parsed.is_empty() {
+                continue;
+            }
+            keymap.actions.retain(|_, a| *a != action);
+            keymap.labels.remove(&action);
+            for chord in parsed {
+                keymap.bind(action, chord);
+            }
+        }
+        keymap
+    }
+
+    fn bind(&mut self, action: Action, chord: Chord) {
+        // A chord taken over from another action no longer labels it
+        if let Some(previous) = self.actions.insert(chord, action)
+            && previous != action
+            && self.labels.get(&previous) == Some(&chord_label(chord))
+        {
+            self.labels.remove(&previous);
+        }
+        self.labels
+            .entry(action)
+            .or_insert_with(|| chord_label(chord));
+    }
+
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.actions
+            .get(&normalize(key.code, key.modifiers))
+            .copied()
+    }
+
+    // How the footer names the key of `action`, e.g. "Ctrl-D"
+    pub fn label(&self, action: Action) -> &str {
+        self.labels.get(&action).map_or("-", String::as_str)
+    }
+}
+
+// Shift is part of the character itself ('D' vs 'd'), so it is dropped for characters
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> Chord {
+    let modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+    match code {
+        KeyCode::Char(c) => (KeyCode::Char(c), modifiers - KeyModifiers::SHIFT),
+        code => (code, modifiers),
+    }
+}
+
+// The search box has the focus in the list, so a character without Ctrl or Alt
+// ("x", "shift-x") belongs to the query and can't be bound
+fn is_typed((code, modifiers): Chord) -> bool {
+    matches!(code, KeyCode::Char(_))
+        && !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+}
+
+// Parses "ctrl-d", "Ctrl+Space", "alt-x", "alt-shift-x", "shift-tab", "f3", "pagedown", ...
+fn parse_chord(chord: &str) -> Option<Chord> {
+    let chord = chord.trim();
+    // A trailing "-" or "+" is the key itself, e.g. "ctrl--"
+    let (prefix, key) = match chord.rfind(['-', '+']) {
+        Some(i) if i + 1 == chord.len() && i > 0 => (&chord[..i - 1], &chord[i..]),
+        Some(i) if i + 1 < chord.len() => (&chord[..i], &chord[i + 1..]),
+        _ => ("", chord),
+    };
+    let mut modifiers = KeyModifiers::NONE;
+    for part in prefix.split(['-', '+']).filter(|p| !p.is_empty()) {
+        modifiers |= match part.to_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" | "meta" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+    }
+    let code = match key.to_lowercase().as_str() {
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" | "ins" => KeyCode::Insert,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" | "pgup" => KeyCode::PageUp,
+        "pagedown" | "pgdn" => KeyCode::PageDown,
+        "space" => KeyCode::Char(' '),
+        name if name.len() > 1 && name.starts_with('f') => KeyCode::F(name[1..].parse().ok()?),
+        _ => {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                // Terminals report Shift with a letter as the uppercase letter
+                (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                    KeyCode::Char(c.to_uppercase().next().unwrap_or(c))
+                }
+                (Some(c), None) => KeyCode::Char(c),
+                _ => return None,
+            }
+        }
+    };
+    Some(normalize(code, modifiers))
+}
+
+fn chord_label((code, modifiers): Chord) -> String {
+    let key = match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Char(c) if modifiers.is_empty() => c.to_string(),
+        KeyCode::Char(c) => c.to_uppercase().to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        KeyCode::BackTab => "Shift-Tab".to_string(),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        code => format!("{:?}", code),
+    };
+    let mut label = String::new();
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        label.push_str("Ctrl-");
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        label.push_str("Alt-");
+    }
+    if modifiers.contains(KeyModifiers::SHIFT) && code != KeyCode::BackTab {
+        label.push_str("Shift-");
+    }
+    label.push_str(&key);
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn typed_characters_are_not_bound() {
+        let keys = HashMap::from([(
+            "delete".to_string(),
+            Chords::Many(vec!["x".to_string(), "shift-d".to_string()]),
+        )]);
+        let keymap = KeyMap::from_config(&keys);
+        for typed in [
+            key(KeyCode::Char('x'), KeyModifiers::NONE),
+            key(KeyCode::Char('D'), KeyModifiers::SHIFT),
+            key(KeyCode::Char('d'), KeyModifiers::NONE),
+        ] {
+            assert_eq!(keymap.action(&typed), None);
+        }
+        // Nothing usable was given, so the default stays
+        let ctrl_d = key(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(&ctrl_d), Some(Action::Delete));
+    }
+
+    #[test]
+    fn shift_with_a_letter_is_the_uppercase_letter() {
+        let keys = HashMap::from([("edit".to_string(), Chords::One("alt-shift-e".to_string()))]);
+        let keymap = KeyMap::from_config(&keys);
+        let alt_shift_e = key(KeyCode::Char('E'), KeyModifiers::ALT | KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(&alt_shift_e), Some(Action::Edit));
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('e'), KeyModifiers::ALT)),
+            None
+        );
+        assert_eq!(keymap.label(Action::Edit), "Alt-E");
+    }
+}
@@ -0,0 +1,68 @@
+//! The logic behind the `try-rs` binary: finding experiments, creating, cloning,
+//! renaming and deleting them, and the TUI itself. Status bars, launchers and
+//! scripts can build on it instead of shelling out to `try-rs`.
+//!
+//! - [`load_configuration`] resolves [`Settings`] from config.toml and the environment
+//! - [`Scanner`] lists the experiments of a tries directory as [`RawEntry`] values,
+//!   which [`TryEntry::from_raw`] interprets
+//! - [`ops`] changes experiments on disk
+
+pub mod config;
+pub mod detect;
+pub mod ops;
+pub mod scan;
+
+// The modules below back the bundled CLI and TUI. They are public for the `try-rs`
+// binary only and may change in any release.
+#[doc(hidden)]
+pub mod archive;
+#[doc(hidden)]
+pub mod backup;
+#[doc(hidden)]
+pub mod clean;
+#[doc(hidden)]
+pub mod favorites;
+#[doc(hidden)]
+pub mod git;
+#[doc(hidden)]
+pub mod history;
+#[doc(hidden)]
+pub mod hooks;
+#[doc(hidden)]
+pub mod integrations;
+#[doc(hidden)]
+pub mod logging;
+#[doc(hidden)]
+pub mod lookup;
+#[doc(hidden)]
+pub mod paths;
+#[doc(hidden)]
+pub mod plain;
+#[doc(hidden)]
+pub mod templates;
+#[doc(hidden)]
+pub mod term;
+#[doc(hidden)]
+pub mod theme;
+#[doc(hidden)]
+pub mod tui;
+#[doc(hidden)]
+pub mod update;
+#[doc(hidden)]
+pub mod utils;
+
+mod git_state;
+mod highlight;
+mod icons;
+mod keys;
+mod meta;
+mod preview;
+mod query;
+mod tmux;
+
+pub use config::{Config, Settings, load_configuration};
+pub use detect::{Detectors, ProjectKind};
+pub use git_state::GitStatus;
+pub use meta::Meta;
+pub use scan::{RawEntry, ScanSource, Scanner};
+pub use tui::TryEntry;
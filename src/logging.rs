@@ -0,0 +1,67 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::sync::Mutex;
+
+// Verbose output (-v, -vv) goes to stderr, which is also where the TUI draws.
+// While the TUI owns the screen, lines are held back and printed once it exits.
+struct StderrLogger {
+    held: Mutex<Option<Vec<String>>>,
+}
+
+static LOGGER: StderrLogger = StderrLogger {
+    held: Mutex::new(None),
+};
+
+// Enables logging for `-v` (debug) or `-vv` (trace); without the flag nothing is logged
+pub fn init(verbosity: u8) {
+    let level = match verbosity {
+        0 => return,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+// Starts holding back log lines, e.g. while the alternate screen is shown
+pub fn hold() {
+    if let Ok(mut held) = LOGGER.held.lock() {
+        held.get_or_insert_with(Vec::new);
+    }
+}
+
+// Prints the lines held back since `hold` and logs directly again
+pub fn release() {
+    let lines = LOGGER.held.lock().ok().and_then(|mut held| held.take());
+    for line in lines.into_iter().flatten() {
+        eprintln!("{}", line);
+    }
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let target = record.target().trim_start_matches("try_rs::");
+        let line = format!(
+            "[{} {}] {}",
+            record.level().as_str().to_lowercase(),
+            target,
+            record.args()
+        );
+        if let Ok(mut held) = self.held.lock()
+            && let Some(lines) = held.as_mut()
+        {
+            lines.push(line);
+            return;
+        }
+        eprintln!("{}", line);
+    }
+
+    fn flush(&self) {}
+}
@@ -0,0 +1,222 @@
+use crate::config::Settings;
+use crate::favorites;
+use crate::query::Query;
+use crate::tui::{TryEntry, match_score, scan_entries};
+use crate::utils::{
+    ARCHIVE_DIR, TEMP_CLONE_PREFIX, TRASH_DIR, date_prefixed, normalize_selection, stays_inside,
+};
+use anyhow::{Result, bail};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Exit codes of `try-rs path` and `try-rs pick`
+pub const FOUND: i32 = 0;
+pub const NOT_FOUND: i32 = 1;
+pub const AMBIGUOUS: i32 = 2;
+
+// Names of the experiment directories, without probing them for project markers
+fn list_names(tries_dir: &Path) -> Vec<String> {
+    let Ok(read_dir) = fs::read_dir(tries_dir) else {
+        return Vec::new();
+    };
+    read_dir
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| {
+            !name.starts_with(TEMP_CLONE_PREFIX) && name != TRASH_DIR && name != ARCHIVE_DIR
+        })
+        .collect()
+}
+
+// The experiment named exactly `name`, without fuzzy matching or creating anything
+pub fn exact_match(tries_dir: &Path, name: &str) -> Option<PathBuf> {
+    let tries_dir = std::path::absolute(tries_dir).unwrap_or_else(|_| tries_dir.to_path_buf());
+    let name = normalize_selection(name);
+    list_names(&tries_dir)
+        .contains(&name)
+        .then(|| tries_dir.join(name))
+}
+
+// Resolves `name` to an experiment path: an exact match first, then a unique fuzzy match.
+// With `create`, a missing exact match is created instead of fuzzy matched
+// (dated with `date_prefix`).
+// Prints only the absolute path on stdout and returns the process exit code.
+pub fn run_path(tries_dir: &Path, name: &str, create: bool, date_prefix: bool) -> i32 {
+    let tries_dir = std::path::absolute(tries_dir).unwrap_or_else(|_| tries_dir.to_path_buf());
+    let name = &normalize_selection(name);
+    if !stays_inside(name) {
+        eprintln!("'{}' is not a name inside the tries directory", name);
+        return NOT_FOUND;
+    }
+    let exact = tries_dir.join(name);
+    if exact.is_dir() {
+        return print_path(exact);
+    }
+
+    if create {
+        let new = if date_prefix {
+            tries_dir.join(date_prefixed(name))
+        } else {
+            exact
+        };
+        return match fs::create_dir_all(&new) {
+            Ok(_) => print_path(new),
+            Err(e) => {
+                eprintln!("Error creating {}: {}", new.display(), e);
+                NOT_FOUND
+            }
+        };
+    }
+
+    let matcher = SkimMatcherV2::default();
+    let mut candidates: Vec<(i64, String)> = list_names(&tries_dir)
+        .into_iter()
+        .filter_map(|n| matcher.fuzzy_match(&n, name).map(|score| (score, n)))
+        .collect();
+
+    match candidates.len() {
+        0 => {
+            eprintln!("No experiment matches '{}'", name);
+            NOT_FOUND
+        }
+        1 => print_path(tries_dir.join(&candidates[0].1)),
+        _ => {
+            candidates.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+            eprintln!("'{}' is ambiguous, candidates:", name);
+            for (_, candidate) in &candidates {
+                eprintln!("  {}", candidate);
+            }
+            AMBIGUOUS
+        }
+    }
+}
+
+fn print_path(path: PathBuf) -> i32 {
+    println!("{}", path.display());
+    FOUND
+}
+
+// `try-rs pick <query>`: the list's search without the TUI. Ranks the experiments
+// like the search box would (filters such as `lang:rust` included), prints the path
+// of the best match and returns the process exit code. When several share the best
+// score, they are numbered on stderr and the choice is read from stdin; without a
+// terminal to ask, that is AMBIGUOUS.
+pub fn run_pick(settings: &Settings, query: &str) -> Result<i32> {
+    let mut entries = scan_entries(
+        &settings.tries_dir,
+        settings.scan_depth,
+        &settings.detectors,
+    );
+    favorites::mark(&mut entries, &favorites::load(&settings.paths));
+    settings.sort_mode.sort(&mut entries);
+
+    let query = Query::parse(query);
+    let unknown = query.unknown_kinds(&settings.detectors);
+    if !unknown.is_empty() {
+        bail!(
+            "Unknown lang: {} (known: {})",
+            unknown.join(", "),
+            settings.detectors.names().join(", ")
+        );
+    }
+    let now = SystemTime::now();
+    let matcher = SkimMatcherV2::default();
+    let mut ranked: Vec<(bool, i64, &TryEntry)> = entries
+        .iter()
+        .filter(|entry| query.matches(entry, now))
+        .filter_map(|entry| {
+            if query.text.is_empty() {
+                return Some((entry.is_favorite, 0, entry));
+            }
+            let score = match_score(
+                &matcher,
+                entry,
+                &query.text,
+                settings.normalize_names,
+                settings.date_prefix,
+            )?;
+            Some((entry.is_favorite, score, entry))
+        })
+        .collect();
+    // Stable, so entries ranked alike keep the sort mode order
+    ranked.sort_by_key(|&(favorite, score, _)| (!favorite, std::cmp::Reverse(score)));
+
+    let Some(&(favorite, score, best)) = ranked.first() else {
+        eprintln!("No experiment matches '{}'", query.text);
+        return Ok(NOT_FOUND);
+    };
+    let tied: Vec<&TryEntry> = ranked
+        .iter()
+        .take_while(|&&(f, s, _)| f == favorite && s == score)
+        .map(|&(_, _, entry)| entry)
+        .collect();
+    // Without a search text the sort mode breaks ties
+    if tied.len() == 1 || query.text.is_empty() {
+        return Ok(print_path(best.path.clone()));
+    }
+
+    let width = tied.len().to_string().len();
+    for (i, entry) in tied.iter().enumerate() {
+        eprintln!("{:>width$}) {}", i + 1, entry.name);
+    }
+    if !io::stdin().is_terminal() {
+        eprintln!("'{}' matches these equally well", query.text);
+        return Ok(AMBIGUOUS);
+    }
+    eprint!("Number (empty to cancel): ");
+    io::stderr().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    match input.trim().parse::<usize>() {
+        Ok(n) if (1..=tied.len()).contains(&n) => Ok(print_path(tied[n - 1].path.clone())),
+        _ => Ok(NOT_FOUND),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_create_stays_inside_the_tries_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let tries = dir.path().join("tries");
+        fs::create_dir(&tries).unwrap();
+        let outside = dir.path().join("outside");
+        for name in [
+            "../outside",
+            "a/../../outside",
+            outside.to_str().unwrap(),
+            ".",
+            ".trash/x",
+        ] {
+            assert_eq!(run_path(&tries, name, true, false), NOT_FOUND, "{}", name);
+        }
+        assert!(!outside.exists() && !tries.join(".trash").exists());
+
+        assert_eq!(run_path(&tries, "clients/acme", true, false), FOUND);
+        assert!(tries.join("clients/acme").is_dir());
+    }
+
+    #[test]
+    fn path_ignores_surrounding_blanks_and_a_trailing_slash() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("parser")).unwrap();
+        for name in ["parser/", "  parser \t", " parser/ "] {
+            assert_eq!(
+                run_path(dir.path(), name, false, false),
+                FOUND,
+                "{:?}",
+                name
+            );
+        }
+        assert_eq!(run_path(dir.path(), " new/ ", true, false), FOUND);
+        assert!(dir.path().join("new").is_dir());
+        assert!(!dir.path().join(" new").exists());
+    }
+}
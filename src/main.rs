@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 
 use clap::Parser;
 use crossterm::{
@@ -6,25 +6,374 @@ use crossterm::{
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::prelude::*;
-use std::process::Stdio;
 use std::{
+    collections::HashSet,
     fs,
-    io::{self, Write},
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
 };
 
 mod cli;
-mod config;
+mod completions;
 mod shell;
-mod tui;
-mod utils;
+mod wizard;
 
-use cli::{Cli, Shell};
-use config::load_configuration;
-use shell::{setup_bash, setup_fish, setup_nushell, setup_powershell, setup_zsh};
-use tui::{App, run_app};
-use utils::{extract_repo_name, is_git_url};
+use cli::{Cli, Command, Shell};
+use shell::{diff_command, enter_command, quote, run_command};
+use try_rs::detect::Detectors;
+use try_rs::git::CloneOptions;
+use try_rs::theme::PRESETS;
+use try_rs::tui::{App, SortMode, run_app, scan_entries};
+use try_rs::utils::{
+    ARCHIVE_DIR, SIZE_LIMITS, TRASH_DIR, clean_stale_clones, create_scratch, date_prefixed,
+    dir_sizes, expand_git_url, extract_repo_name, format_size, is_git_url, is_single_name,
+    normalize_selection, restore_from_trash,
+};
+use try_rs::{
+    Settings, TryEntry, archive, backup, clean, favorites, history, hooks, integrations,
+    load_configuration, logging, lookup, ops, plain, term, update,
+};
+
+// Runs the enter hooks, then prints the command the shell wrapper evals: open the
+// editor, or cd into `path` (or run `on_select` instead).
+// The editor is picked by `Settings::editor_for`.
+// With `osc7` set, every `cd` is also reported to the terminal so tabs update right away;
+// after the TUI the terminal title can be set as well.
+fn print_enter_command(
+    path: &Path,
+    open_editor: bool,
+    settings: &Settings,
+    shell: Option<Shell>,
+    from_tui: bool,
+) {
+    // A picker for another program: nothing is entered, so no hooks run either
+    if settings.print_path {
+        println!("{}", path.display());
+        return;
+    }
+    settings.hooks.entered(path);
+    history::record_visit(&settings.paths, path);
+    let editor = open_editor.then(|| settings.editor_for(path)).flatten();
+    match (&settings.on_select, &editor) {
+        // Nushell's and xonsh's wrappers can't eval, so they keep the plain `cd`
+        (Some(template), None) if shell.is_none_or(Shell::evals) => {
+            println!("{}", hooks::expand(template, path, |s| quote(s, shell)));
+        }
+        _ => println!("{}", enter_command(shell, editor.as_deref(), path)),
+    }
+    if editor.is_none() && settings.osc7 {
+        term::report_cwd(path);
+    }
+    if from_tui
+        && settings.set_title
+        && let Some(name) = path.file_name()
+    {
+        term::set_title(&name.to_string_lossy());
+    }
+}
+
+// `try-rs new <name>`: creates the experiment (see `ops::create`) and enters it
+fn run_new(
+    settings: &Settings,
+    name: &str,
+    template: Option<&str>,
+    shell: Option<Shell>,
+) -> Result<()> {
+    let path = ops::create(settings, name, template)?;
+    print_enter_command(&path, false, settings, shell, false);
+    Ok(())
+}
+
+// `try-rs worktree <repo> <branch>`: creates the experiment as a worktree of `repo`
+// (see `ops::worktree`) and enters it
+fn run_worktree(
+    settings: &Settings,
+    repo: &Path,
+    branch: &str,
+    name: Option<&str>,
+    shell: Option<Shell>,
+) -> Result<()> {
+    let mut name = name.map_or_else(|| ops::worktree_name(repo, branch), str::to_string);
+    if settings.date_prefix {
+        name = date_prefixed(&name);
+    }
+    let path = ops::worktree(&settings.tries_dir, repo, branch, &name)?;
+    settings.hooks.created(&path);
+    print_enter_command(&path, false, settings, shell, false);
+    Ok(())
+}
+
+// `try-rs recent [N]`: lists the last `limit` experiments entered, or enters the
+// `number`th of them again
+fn run_recent(
+    settings: &Settings,
+    number: Option<usize>,
+    limit: usize,
+    shell: Option<Shell>,
+) -> Result<()> {
+    let mut records = history::load_visits(&settings.paths);
+    let Some(number) = number else {
+        records.truncate(limit);
+        history::print_visits(&records, &settings.tries_dir);
+        return Ok(());
+    };
+    let Some(record) = number.checked_sub(1).and_then(|i| records.get(i)) else {
+        bail!("No experiment #{} in the visit history", number);
+    };
+    if !record.path.is_dir() {
+        bail!("{} no longer exists", record.path.display());
+    }
+    print_enter_command(&record.path, false, settings, shell, false);
+    Ok(())
+}
+
+// `try-rs clones [NAME]`: lists the clone history, or enters a past clone,
+// cloning it again first if its directory is gone.
+fn run_clones(
+    settings: &Settings,
+    name: Option<&str>,
+    options: &CloneOptions,
+    shell: Option<Shell>,
+) -> Result<()> {
+    let records = history::load_clones(&settings.paths);
+    let Some(name) = name else {
+        history::print_clones(&records, &settings.tries_dir);
+        return Ok(());
+    };
+    let name = normalize_selection(name);
+    let Some(record) = records.iter().find(|r| r.name == name || r.url == name) else {
+        bail!("No clone named '{}' in the history", name);
+    };
+
+    let mut path = settings.tries_dir.join(&record.name);
+    if !path.exists() {
+        eprintln!("Cloning {} into {}...", record.url, record.name);
+        let name = ops::clone(&record.url, &settings.tries_dir, &record.name, options)?;
+        history::record_clone(&settings.paths, &record.url, &name);
+        path = settings.tries_dir.join(name);
+    }
+    print_enter_command(&path, false, settings, shell, false);
+    Ok(())
+}
+
+// `try-rs unarchive [name]`: lists the archives, or unpacks `name` back among the experiments
+fn run_unarchive(tries_dir: &Path, name: Option<&str>) -> Result<()> {
+    let Some(name) = name else {
+        let archives = archive::list(tries_dir);
+        if archives.is_empty() {
+            eprintln!("There are no archives.");
+        }
+        for archive in archives {
+            eprintln!("{:<50} {:>10}", archive.name, format_size(archive.bytes));
+        }
+        return Ok(());
+    };
+    let Some(found) = archive::find(tries_dir, &normalize_selection(name)) else {
+        bail!(
+            "No archive named '{}' in {}",
+            name,
+            tries_dir.join(ARCHIVE_DIR).display()
+        );
+    };
+    let restored = archive::restore(&found.path, tries_dir)?;
+    eprintln!("Restored {}", restored.display());
+    Ok(())
+}
+
+// `try-rs restore [name]`: lists the trash, or moves `name` back among the experiments.
+// With `empty`, deletes everything in the trash for good instead, after asking
+// unless `yes`.
+fn run_restore(tries_dir: &Path, name: Option<&str>, empty: bool, yes: bool) -> Result<()> {
+    let trash = tries_dir.join(TRASH_DIR);
+    if empty {
+        return empty_trash(&trash, yes);
+    }
+    let Some(name) = name else {
+        let mut names: Vec<String> = fs::read_dir(&trash)
+            .map(|read_dir| {
+                read_dir
+                    .flatten()
+                    .map(|e| e.file_name().to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        if names.is_empty() {
+            eprintln!("The trash is empty.");
+        }
+        for name in names {
+            eprintln!("{}", name);
+        }
+        return Ok(());
+    };
+    let name = normalize_selection(name);
+    let trashed = trash.join(&name);
+    if !is_single_name(&name) || !trashed.is_dir() {
+        bail!("'{}' is not in the trash ({})", name, trash.display());
+    }
+    let restored = restore_from_trash(&trashed, tries_dir, &name)?;
+    eprintln!("Restored {}", restored.display());
+    Ok(())
+}
+
+// `try-rs restore --empty`: deletes the trashed experiments for good
+fn empty_trash(trash: &Path, yes: bool) -> Result<()> {
+    let trashed: Vec<PathBuf> = fs::read_dir(trash)
+        .map(|read_dir| read_dir.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    if trashed.is_empty() {
+        eprintln!("The trash is empty.");
+        return Ok(());
+    }
+    if !yes {
+        if !io::stdin().is_terminal() {
+            bail!("Refusing to empty the trash without confirmation: pass --yes");
+        }
+        eprint!(
+            "Delete the {} experiment(s) in the trash for good? [y/N] ",
+            trashed.len()
+        );
+        io::stderr().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("y") {
+            return Ok(());
+        }
+    }
+    for path in trashed {
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        if let Err(e) = removed {
+            eprintln!("Error deleting {}: {}", path.display(), e);
+        }
+    }
+    eprintln!("Emptied the trash.");
+    Ok(())
+}
+
+// `try-rs rename <old> <new>`: the shell is moved along when it sits inside the
+// renamed experiment, since its working directory no longer exists under that path
+fn run_rename(
+    settings: &Settings,
+    tries_dir: &Path,
+    old: &str,
+    new: &str,
+    shell: Option<Shell>,
+) -> Result<()> {
+    let Some(path) = lookup::exact_match(tries_dir, old) else {
+        bail!("No experiment named '{}'", old);
+    };
+    // Read before renaming: afterwards the kernel already reports the new path
+    let cwd = std::env::current_dir().ok();
+    let renamed = ops::rename(&settings.paths, &path, new, false)?;
+    eprintln!("Renamed {} to {}", old, renamed.display());
+    if let Some(cwd) = cwd
+        && let Ok(inside) = cwd.strip_prefix(&path)
+    {
+        print_enter_command(&renamed.join(inside), false, settings, shell, false);
+    }
+    Ok(())
+}
+
+// `try-rs list` / `--list`: experiment names on stdout, or a JSON array with `json`
+fn print_list(
+    tries_dir: &Path,
+    scan_depth: usize,
+    detectors: &Detectors,
+    favorites: &HashSet<PathBuf>,
+    sort: SortMode,
+    json: bool,
+) {
+    let mut entries = scan_entries(tries_dir, scan_depth, detectors);
+    favorites::mark(&mut entries, favorites);
+    if sort == SortMode::Size {
+        let paths: Vec<_> = entries.iter().map(|e| e.path.clone()).collect();
+        for (entry, size) in entries.iter_mut().zip(dir_sizes(&paths, SIZE_LIMITS)) {
+            entry.size = Some(size);
+        }
+    }
+    sort.sort(&mut entries);
+    if !json {
+        for entry in entries {
+            println!("{}", entry.name);
+        }
+        return;
+    }
+    println!("{}", list_json(&entries));
+}
+
+// --sparse picks directories of a repository being cloned, so anything but a URL
+// (or shorthand) is a mistake rather than a name to enter
+fn check_sparse(
+    sparse: &[String],
+    name: &str,
+    tries_dir: &Path,
+    clone_host: Option<&str>,
+) -> Result<()> {
+    if !sparse.is_empty() && !is_git_url(name, tries_dir, clone_host) {
+        bail!(
+            "--sparse only applies when cloning a repository, and '{}' isn't a URL",
+            name
+        );
+    }
+    Ok(())
+}
+
+// The `--json` listing: one object per experiment, times in RFC 3339
+fn list_json(entries: &[TryEntry]) -> serde_json::Value {
+    let rfc3339 =
+        |time: std::time::SystemTime| chrono::DateTime::<chrono::Local>::from(time).to_rfc3339();
+    entries
+        .iter()
+        .map(|entry| {
+            serde_json::json!({
+                "name": entry.name,
+                "path": entry.path.to_string_lossy(),
+                "created": rfc3339(entry.created),
+                "modified": rfc3339(entry.modified),
+                "types": entry.kind_labels(),
+            })
+        })
+        .collect()
+}
+
+// Shows where try-rs looks for everything, to debug setups
+fn print_doctor(settings: &Settings) {
+    let show = |p: &Path| {
+        let status = if p.exists() { "" } else { " (missing)" };
+        format!("{}{}", p.display(), status)
+    };
+    eprintln!("try-rs {}", env!("CARGO_PKG_VERSION"));
+    eprintln!(
+        "config file: {}",
+        settings
+            .config_file
+            .as_deref()
+            .map(show)
+            .unwrap_or_else(|| "(none, using defaults)".to_string())
+    );
+    eprintln!("config dir:  {}", show(&settings.paths.config_dir));
+    eprintln!("state dir:   {}", show(&settings.paths.state_dir));
+    eprintln!("cache dir:   {}", show(&settings.paths.cache_dir));
+    eprintln!("tries path:  {}", show(&settings.tries_dir));
+    for root in &settings.roots {
+        eprintln!("root:        {} = {}", root.name, show(&root.path));
+    }
+    eprintln!(
+        "editor:      {}",
+        settings.editor_cmd.as_deref().unwrap_or("(none)")
+    );
+    eprintln!(
+        "theme:       {} ({:?})",
+        settings.theme_name, settings.color_depth
+    );
+}
 
 fn main() -> Result<()> {
+    completions::handle_request();
     let cli = match Cli::try_parse() {
         Ok(cli) => cli,
         Err(err) => {
@@ -33,88 +382,234 @@ fn main() -> Result<()> {
             std::process::exit(if err.use_stderr() { 1 } else { 0 });
         }
     };
-    let (tries_dir, theme, editor_cmd, is_first_run) = load_configuration();
+    logging::init(cli.verbose);
+    // Completion runs on every Tab: no first-run config, no wizard
+    if let Some(Command::CompleteNames) = &cli.command {
+        completions::print_names(cli.tries_path.as_deref(), cli.root.as_deref());
+        return Ok(());
+    }
+    let mut settings = load_configuration(cli.tries_path.as_deref())?;
+    // First run of an interactive command: the wizard rewrites the config just created.
+    // A wrapper passing --shell is already installed, so it isn't offered again.
+    let interactive =
+        cli.command.is_none() && cli.setup.is_none() && !(cli.list || cli.build_info || cli.doctor);
+    if settings.is_first_run
+        && interactive
+        && io::stdin().is_terminal()
+        && io::stderr().is_terminal()
+        && let Some(config_file) = settings.config_file.clone()
+        && wizard::run(&config_file, &settings, cli.shell.is_some())?
+    {
+        settings = load_configuration(cli.tries_path.as_deref())?;
+    }
+    if let Some(editor) = cli.editor.clone().filter(|e| !e.is_empty()) {
+        log::debug!("editor: {} (from --editor)", editor);
+        settings.editor_cmd = Some(editor);
+        settings.editor_forced = true;
+    }
+    settings.print_path = cli.print_path;
+    // Clone flags beat `clone_depth`
+    if cli.shallow_clone {
+        settings.clone_options.depth = Some(1);
+    }
+    if let Some(branch) = cli.branch.clone() {
+        settings.clone_options.branch = Some(branch);
+    }
+    if let Some(filter) = cli.filter.clone() {
+        settings.clone_options.filter = Some(filter);
+    }
+    settings.clone_options.sparse = cli.sparse.clone();
+    if let Some(root) = &cli.root {
+        settings.select_root(root)?;
+    }
+    if let Some(theme) = &cli.theme {
+        settings.select_theme(theme)?;
+    }
+    let mut tries_dir = settings.tries_dir.clone();
 
-    // Ensure the directory exists (either from config or default)
-    if !tries_dir.exists() {
-        fs::create_dir_all(&tries_dir)?;
+    if cli.build_info {
+        update::print_build_info();
+        return Ok(());
     }
 
-    // Handle Shell Setup
-    if let Some(shell) = cli.setup {
-        match shell {
-            Shell::Fish => setup_fish()?,
-            Shell::Zsh => setup_zsh()?,
-            Shell::Bash => setup_bash()?,
-            Shell::PowerShell => setup_powershell()?,
-            Shell::NuShell => setup_nushell()?,
-        }
+    if cli.doctor {
+        print_doctor(&settings);
         return Ok(());
     }
 
-    // Handle First Run / Interactive Setup
-    if is_first_run && cli.setup.is_none() {
-        let shell_type = if cfg!(windows) {
-            // On Windows, PowerShell is the most likely modern shell.
-            Some(Shell::PowerShell)
-        } else {
-            // Check for Nushell first
-            if std::env::var("NU_VERSION").is_ok() {
-                Some(Shell::NuShell)
-            } else {
-                let shell = std::env::var("SHELL").unwrap_or_default();
-                if shell.contains("fish") {
-                    Some(Shell::Fish)
-                } else if shell.contains("zsh") {
-                    Some(Shell::Zsh)
-                } else if shell.contains("bash") {
-                    Some(Shell::Bash)
-                } else {
-                    None
+    // Ensure the directory exists (either from config or default)
+    if !tries_dir.exists() {
+        fs::create_dir_all(&tries_dir)
+            .with_context(|| format!("creating {}", tries_dir.display()))?;
+    } else if !tries_dir.is_dir() {
+        bail!("{} is not a directory", tries_dir.display());
+    }
+    clean_stale_clones(&tries_dir);
+
+    // Handle Subcommands
+    if let Some(command) = &cli.command {
+        match command {
+            Command::Clean {
+                older_than,
+                dry_run,
+                sizes,
+                yes,
+            } => clean::run_clean(&settings, older_than.as_deref(), *dry_run, *sizes, *yes)?,
+            Command::Path { name, create } => {
+                std::process::exit(lookup::run_path(
+                    &tries_dir,
+                    name,
+                    *create,
+                    settings.date_prefix,
+                ));
+            }
+            Command::Pick { query } => {
+                std::process::exit(lookup::run_pick(&settings, &query.join(" "))?);
+            }
+            Command::Restore { name, empty, yes } => {
+                run_restore(&tries_dir, name.as_deref(), *empty, *yes)?
+            }
+            Command::Archive { name, format } => {
+                let Some(path) = lookup::exact_match(&tries_dir, name) else {
+                    bail!("No experiment named '{}'", name);
+                };
+                let written =
+                    archive::archive(&path, &tries_dir, format.unwrap_or(settings.archive_format))?;
+                eprintln!("Archived {} to {}", name, written.display());
+            }
+            Command::Rename { old, new } => run_rename(&settings, &tries_dir, old, new, cli.shell)?,
+            Command::Unarchive { name } => run_unarchive(&tries_dir, name.as_deref())?,
+            Command::List { json, sort } => {
+                print_list(
+                    &tries_dir,
+                    settings.scan_depth,
+                    &settings.detectors,
+                    &favorites::load(&settings.paths),
+                    sort.unwrap_or(settings.sort_mode),
+                    *json,
+                );
+            }
+            Command::New { name, template } => {
+                run_new(&settings, name, template.as_deref(), cli.shell)?;
+            }
+            Command::Worktree { repo, branch, name } => {
+                run_worktree(&settings, repo, branch, name.as_deref(), cli.shell)?;
+            }
+            Command::Jump { name } => {
+                let Some(path) = lookup::exact_match(&tries_dir, name) else {
+                    bail!("No experiment named '{}'", name);
+                };
+                print_enter_command(&path, false, &settings, cli.shell, false);
+            }
+            Command::Export { file } => backup::run_export(&settings, file)?,
+            Command::Import { file } => backup::run_import(&settings, file)?,
+            Command::Clones {
+                name,
+                shallow_clone,
+            } => {
+                let mut options = settings.clone_options.clone();
+                if *shallow_clone {
+                    options.depth = Some(1);
                 }
+                run_clones(&settings, name.as_deref(), &options, cli.shell)?
             }
-        };
-
-        if let Some(s) = shell_type {
-            eprintln!("Detected shell: {:?}", s);
-            eprint!(
-                "Shell integration not configured. Do you want to set it up for {:?}? [Y/n] ",
-                s
-            );
-            io::stderr().flush()?;
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
-            if input.trim().is_empty() || input.trim().eq_ignore_ascii_case("y") {
-                match s {
-                    Shell::Fish => setup_fish()?,
-                    Shell::Zsh => setup_zsh()?,
-                    Shell::Bash => setup_bash()?,
-                    Shell::PowerShell => setup_powershell()?,
-                    Shell::NuShell => setup_nushell()?,
+            Command::Recent { number, limit } => run_recent(&settings, *number, *limit, cli.shell)?,
+            Command::Completions { shell } => completions::print_script(*shell)?,
+            Command::Themes => {
+                for (name, _) in PRESETS {
+                    println!("{}", name);
                 }
             }
+            Command::Init { shell } => print!("{}", shell::wrapper_script(*shell)),
+            // Answered before the configuration was loaded
+            Command::CompleteNames => {}
         }
+        return Ok(());
+    }
+
+    // Handle non-interactive listing, using the same ordering as the TUI
+    if cli.list {
+        print_list(
+            &tries_dir,
+            settings.scan_depth,
+            &settings.detectors,
+            &favorites::load(&settings.paths),
+            cli.sort.unwrap_or(settings.sort_mode),
+            false,
+        );
+        return Ok(());
+    }
+
+    // Handle Shell Setup
+    if let Some(shell) = cli.setup {
+        shell::setup(shell)?;
+        return Ok(());
     }
 
     // The 'selection' variable will hold the chosen name or URL.
     // It can come from arguments (CLI) or the interface (TUI).
     let selection_result: Option<String>;
-    let mut open_editor = false;
+    let open_editor: bool;
+    let scratch: bool;
+    let mut template: Option<String> = None;
+    let from_tui = cli.name_or_url.is_none() && !cli.scratch;
 
-    if let Some(name) = cli.name_or_url {
+    if cli.scratch {
+        selection_result = None;
+        open_editor = false;
+        scratch = true;
+    } else if let Some(name) = cli.name_or_url {
         // CLI MODE: The user passed an argument (e.g., try-rs https://...)
         // We skip the graphical interface entirely.
+        check_sparse(
+            &cli.sparse,
+            &name,
+            &tries_dir,
+            settings.clone_host.as_deref(),
+        )?;
         selection_result = Some(name);
+        open_editor = cli.open;
+        scratch = false;
+    } else if cli.plain || std::env::var("TERM").is_ok_and(|t| t == "dumb") {
+        // PLAIN MODE: a numbered list and a line of input, for terminals the TUI can't drive
+        let outcome = plain::pick(
+            &tries_dir,
+            settings.scan_depth,
+            &settings.detectors,
+            &favorites::load(&settings.paths),
+            settings.sort_mode,
+        )?;
+        template = outcome.template;
+        selection_result = outcome.selection;
+        open_editor = outcome.open_editor;
+        scratch = outcome.scratch;
     } else {
         // TUI MODE: No arguments, open the visual interface.
 
+        // The TUI draws on stderr; refuse to put a pipe or file into raw mode
+        if !io::stderr().is_terminal() {
+            bail!(
+                "The interactive picker needs a terminal on stderr. Pass a name (e.g. `try-rs <name>`) or use `--list` when piping."
+            );
+        }
+
+        clean::prompt_expired(
+            &tries_dir,
+            settings.scan_depth,
+            &settings.detectors,
+            &settings.retention,
+            &favorites::load(&settings.paths),
+            &settings.hooks,
+        )?;
+
         enable_raw_mode()?;
         let mut stderr = io::stderr();
+        logging::hold();
         execute!(stderr, EnterAlternateScreen)?;
         let backend = CrosstermBackend::new(stderr);
         let mut terminal = Terminal::new(backend)?;
 
-        let app = App::new(tries_dir.clone(), theme, editor_cmd.clone());
+        let app = App::new(&settings);
         // Run the app and capture the result
         let res = run_app(&mut terminal, app);
 
@@ -122,57 +617,134 @@ fn main() -> Result<()> {
         disable_raw_mode()?;
         execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
         terminal.show_cursor()?;
+        logging::release();
 
-        (selection_result, open_editor) = res?;
+        let outcome = res?;
+        // Clones made in the TUI get their create hooks once the terminal is back
+        if let Some((url, folder)) = &outcome.cloned {
+            history::record_clone(&settings.paths, url, folder);
+            let root = outcome.tries_dir.as_ref().unwrap_or(&tries_dir);
+            settings.hooks.created(&root.join(folder));
+        }
+        if let Some((left, right)) = &outcome.diff {
+            println!(
+                "{}",
+                diff_command(cli.shell, &settings.diff_tool, left, right)
+            );
+            return Ok(());
+        }
+        if let Some(dir) = &outcome.spawn {
+            if let Some(command) = integrations::open(&settings, dir)? {
+                println!("{}", run_command(cli.shell, &command));
+            }
+            return Ok(());
+        }
+        // A file picked in the preview browser opens in the experiment's editor
+        if let Some((experiment, file)) = &outcome.edit_file {
+            let editor = settings.editor_for(experiment);
+            println!("{}", enter_command(cli.shell, editor.as_deref(), file));
+            return Ok(());
+        }
+        // New names go to the root the TUI was switched to
+        if let Some(root) = outcome.tries_dir {
+            settings.tries_dir = root.clone();
+            tries_dir = root;
+        }
+        template = outcome.template;
+        selection_result = outcome.selection;
+        open_editor = outcome.open_editor;
+        scratch = outcome.scratch;
     }
 
+    // Scratch experiments get a generated name and then behave like a named one
+    let selection_result = if scratch {
+        let name = create_scratch(&tries_dir, &settings.auto_name)?;
+        eprintln!("Created scratch experiment: {}", name);
+        settings.hooks.created(&tries_dir.join(&name));
+        Some(name)
+    } else {
+        selection_result
+    };
+
     // 3. Process the result (Common for both modes)
-    if let Some(selection) = selection_result {
-        let target_path = tries_dir.join(&selection);
+    if let Some(selection) = selection_result
+        .map(|s| normalize_selection(&s))
+        .filter(|s| !s.is_empty())
+    {
+        let mut target_path = tries_dir.join(&selection);
+        // With `date_prefix`, a name that doesn't exist yet gets today's date
+        // (and enters the experiment if one was already made today)
+        let selection = if settings.date_prefix
+            && !target_path.exists()
+            && !is_git_url(&selection, &tries_dir, settings.clone_host.as_deref())
+        {
+            let dated = date_prefixed(&selection);
+            target_path = tries_dir.join(&dated);
+            dated
+        } else {
+            selection
+        };
 
         // CASE 1: Does the folder already exist? Enter it.
         if target_path.exists() {
-            if open_editor && let Some(cmd) = editor_cmd {
-                println!("{} '{}'", cmd, target_path.to_string_lossy());
-            } else {
-                println!("cd '{}'", target_path.to_string_lossy());
+            if cli.open && settings.editor_for(&target_path).is_none() {
+                bail!(
+                    "No editor configured (pass --editor, set $TRY_EDITOR or `editor` in config.toml)"
+                );
             }
+            print_enter_command(&target_path, open_editor, &settings, cli.shell, from_tui);
+        } else if cli.open && !is_git_url(&selection, &tries_dir, settings.clone_host.as_deref()) {
+            // --open only jumps into existing experiments, or ones it clones
+            bail!("Experiment '{}' does not exist", target_path.display());
         } else {
             // CASE 2: Is it a Git URL? Clone it!
-            if is_git_url(&selection) {
-                let repo_name = extract_repo_name(&selection);
-
-                let folder_name = repo_name;
-                let new_path = tries_dir.join(&folder_name);
-
-                eprintln!("Cloning {} into {}...", selection, folder_name);
+            if is_git_url(&selection, &tries_dir, settings.clone_host.as_deref()) {
+                // `<url>#path/to/subdir` clones the repo but enters the subdirectory,
+                // as does --sparse with the first directory checked out
+                let (url, subpath) = match selection.split_once('#') {
+                    Some((url, subpath)) => (url, Some(subpath.trim_matches('/'))),
+                    None => (
+                        selection.as_str(),
+                        settings
+                            .clone_options
+                            .sparse
+                            .first()
+                            .map(|s| s.trim_matches('/')),
+                    ),
+                };
+                let url = &expand_git_url(url, settings.clone_host.as_deref());
+                let repo_name = extract_repo_name(url);
 
-                let mut cmd = std::process::Command::new("git");
-                cmd.arg("clone");
+                let folder_name = if settings.date_prefix {
+                    date_prefixed(&repo_name)
+                } else {
+                    repo_name
+                };
 
-                if cli.shallow_clone {
-                    cmd.arg("--depth").arg("1");
-                }
+                eprintln!("Cloning {} into {}...", url, folder_name);
 
-                let status = cmd
-                    .arg(&selection)
-                    .arg(&new_path)
-                    .arg("--recurse-submodules")
-                    .arg("--no-single-branch")
-                    .stdout(Stdio::null())
-                    .stderr(Stdio::inherit())
-                    .status();
-
-                match status {
-                    Ok(s) if s.success() => {
-                        if open_editor && let Some(cmd) = editor_cmd {
-                            println!("{} '{}'", cmd, new_path.to_string_lossy());
-                        } else {
-                            println!("cd '{}'", new_path.to_string_lossy());
+                match ops::clone(url, &tries_dir, &folder_name, &settings.clone_options) {
+                    Ok(folder_name) => {
+                        history::record_clone(&settings.paths, url, &folder_name);
+                        let mut new_path = tries_dir.join(&folder_name);
+                        if let Some(subpath) = subpath.filter(|s| !s.is_empty()) {
+                            let subdir = new_path.join(subpath);
+                            if subdir.is_dir() {
+                                new_path = subdir;
+                            } else {
+                                eprintln!(
+                                    "Warning: '{}' does not exist in {}, entering the repository root",
+                                    subpath, folder_name
+                                );
+                            }
+                        }
+                        if open_editor && settings.editor_for(&new_path).is_none() {
+                            eprintln!("No editor configured, changing into the clone instead");
                         }
+                        print_enter_command(&new_path, open_editor, &settings, cli.shell, from_tui);
                     }
-                    _ => {
-                        eprintln!("Error: Failed to clone the repository.");
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
                     }
                 }
             } else {
@@ -180,15 +752,73 @@ fn main() -> Result<()> {
                 let new_name = selection;
 
                 let new_path = tries_dir.join(&new_name);
-                fs::create_dir_all(&new_path)?;
-                if open_editor && let Some(cmd) = editor_cmd {
-                    println!("{} '{}'", cmd, new_path.to_string_lossy());
-                } else {
-                    println!("cd '{}'", new_path.to_string_lossy());
+                if let Some(parent) = new_path.parent() {
+                    fs::create_dir_all(parent)?;
                 }
+                // Only the leaf is created atomically: if another instance just created
+                // it, jump into it instead
+                match fs::create_dir(&new_path) {
+                    Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e.into()),
+                    // Only a directory made just now gets the template picked in the TUI
+                    Ok(()) => {
+                        if let Some(template) = &template
+                            && let Err(e) = settings.templates.apply(template, &new_path)
+                        {
+                            eprintln!("Warning: {:#}", e);
+                        }
+                        settings.hooks.created(&new_path);
+                    }
+                    _ => {}
+                }
+                print_enter_command(&new_path, open_editor, &settings, cli.shell, from_tui);
             }
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use try_rs::{Meta, RawEntry};
+
+    #[test]
+    fn sparse_needs_a_repository_to_clone() {
+        use clap::Parser;
+        assert!(cli::Cli::try_parse_from(["try-rs", "--sparse", "src"]).is_err());
+        assert!(cli::Cli::try_parse_from(["try-rs", "--sparse", "src", "gh:a/b"]).is_ok());
+
+        let dir = tempfile::tempdir().unwrap();
+        let sparse = ["src".to_string()];
+        let check = |sparse: &[String], name, host| check_sparse(sparse, name, dir.path(), host);
+        assert!(check(&sparse, "https://example.com/a/b.git", None).is_ok());
+        assert!(check(&sparse, "gh:a/b", None).is_ok());
+        assert!(check(&sparse, "a/b", None).is_err());
+        assert!(check(&sparse, "a/b", Some("github.com")).is_ok());
+        assert!(check(&sparse, "my-experiment", None).is_err());
+        assert!(check(&[], "my-experiment", None).is_ok());
+    }
+
+    #[test]
+    fn list_json_escapes_names_and_keeps_the_field_names() {
+        let entry = TryEntry::from_raw(RawEntry {
+            path: PathBuf::from("/tries/say \"hi\"\n"),
+            name: "say \"hi\"\n".to_string(),
+            modified: std::time::SystemTime::UNIX_EPOCH,
+            created: std::time::SystemTime::UNIX_EPOCH,
+            kinds: Vec::new(),
+            git: false,
+            meta: Meta::default(),
+        });
+        let text = list_json(&[entry]).to_string();
+        let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let item = &parsed[0];
+        assert_eq!(item["name"], "say \"hi\"\n");
+        assert_eq!(item["path"], "/tries/say \"hi\"\n");
+        assert_eq!(item["types"], serde_json::json!([]));
+        let created = item["created"].as_str().unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(created).is_ok());
+        assert!(item["modified"].is_string());
+    }
+}
@@ -0,0 +1,126 @@
+use crate::git;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Kept inside the experiment, so the notes travel with it (renames, archives, trash).
+/// Repositories list it in their local excludes, so it never shows as untracked.
+pub const META_FILE: &str = ".try-meta.toml";
+
+/// A one-line description and free-form notes attached to an experiment
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Meta {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+impl Meta {
+    pub fn is_empty(&self) -> bool {
+        self.description.trim().is_empty() && self.notes.trim().is_empty()
+    }
+}
+
+/// The metadata of the experiment in `dir`; a missing or unreadable file means none
+pub fn load(dir: &Path) -> Meta {
+    let path = dir.join(META_FILE);
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return Meta::default();
+    };
+    toml::from_str(&contents).unwrap_or_else(|e| {
+        log::debug!("ignoring {}: {}", path.display(), e);
+        Meta::default()
+    })
+}
+
+/// Writes the metadata of `dir`, removing the file once both fields are empty.
+/// Leaves the modification time of `dir` alone, so taking notes doesn't make an
+/// experiment look recently worked on.
+pub fn save(dir: &Path, meta: &Meta) -> io::Result<()> {
+    let modified = fs::metadata(dir).and_then(|m| m.modified());
+    write(dir, meta)?;
+    if let Ok(modified) = modified {
+        fs::File::open(dir)?.set_modified(modified)?;
+    }
+    Ok(())
+}
+
+fn write(dir: &Path, meta: &Meta) -> io::Result<()> {
+    let path = dir.join(META_FILE);
+    if meta.is_empty() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    if dir.join(".git").exists()
+        && let Err(e) = git::exclude(dir, META_FILE)
+    {
+        log::warn!("could not exclude {} from git: {:#}", META_FILE, e);
+    }
+    let contents = toml::to_string(meta).map_err(io::Error::other)?;
+    fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn notes_keep_their_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = Meta {
+            description: "parser spike".into(),
+            notes: "first line\n\n  indented \"quoted\"".into(),
+        };
+        save(dir.path(), &meta).unwrap();
+        assert_eq!(load(dir.path()), meta);
+        save(dir.path(), &Meta::default()).unwrap();
+        assert!(!dir.path().join(META_FILE).exists());
+    }
+
+    #[test]
+    fn saving_keeps_the_modification_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = SystemTime::now() - Duration::from_secs(86400 * 30);
+        fs::File::open(dir.path())
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let meta = Meta {
+            notes: "x".into(),
+            ..Meta::default()
+        };
+        save(dir.path(), &meta).unwrap();
+        let modified = fs::metadata(dir.path()).unwrap().modified().unwrap();
+        assert_eq!(modified, old);
+    }
+
+    #[test]
+    fn repositories_ignore_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(dir.path())
+                .args(args)
+                .output()
+                .unwrap()
+        };
+        assert!(git(&["init", "-q"]).status.success());
+        let meta = Meta {
+            description: "x".into(),
+            ..Meta::default()
+        };
+        save(dir.path(), &meta).unwrap();
+        save(dir.path(), &meta).unwrap();
+        assert!(git(&["status", "--porcelain"]).stdout.is_empty());
+        let exclude = fs::read_to_string(dir.path().join(".git/info/exclude")).unwrap();
+        assert_eq!(exclude.matches(META_FILE).count(), 1);
+    }
+}
@@ -0,0 +1,349 @@
+use crate::config::Settings;
+use crate::favorites;
+use crate::git::{self, CloneOptions, CloneProgress};
+use crate::hooks::{Hook, Hooks};
+use crate::paths::Paths;
+use crate::utils::{
+    date_prefixed, move_experiment, move_to_trash, normalize_selection, rename_experiment,
+    rename_into_place, rename_target, stays_inside, temp_clone_dir,
+};
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+// Changes to experiments on disk, shared by the CLI and anything embedding try-rs.
+// Nothing here prints to stdout, which belongs to the shell wrapper.
+
+// How often a clone with progress looks at its cancel flag
+const CANCEL_POLL: Duration = Duration::from_millis(100);
+
+/// Creates the experiment `name` (dated with `date_prefix`), which must not exist
+/// yet, fills it from `template` and runs `on_create`. An experiment whose template
+/// failed is removed again. Returns the new directory.
+pub fn create(settings: &Settings, name: &str, template: Option<&str>) -> Result<PathBuf> {
+    let mut name = normalize_selection(name);
+    if name.is_empty() {
+        bail!("The experiment name is empty");
+    }
+    if !stays_inside(&name) {
+        bail!("'{}' is not a name inside the tries directory", name);
+    }
+    if settings.date_prefix {
+        name = date_prefixed(&name);
+    }
+    let path = settings.tries_dir.join(&name);
+    if path.exists() {
+        bail!("Experiment '{}' already exists", name);
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::create_dir(&path).with_context(|| format!("creating {}", path.display()))?;
+    if let Some(template) = template
+        && let Err(e) = settings.templates.apply(template, &path)
+    {
+        let _ = fs::remove_dir_all(&path);
+        return Err(e);
+    }
+    settings.hooks.created(&path);
+    Ok(path)
+}
+
+/// Clones `url` as `name` under `tries_dir`, showing git's progress on stderr.
+/// The clone goes to a temporary directory first and is renamed into place when done;
+/// returns the directory name used, suffixed if `name` was taken in the meantime.
+pub fn clone(url: &str, tries_dir: &Path, name: &str, options: &CloneOptions) -> Result<String> {
+    let temp = temp_clone_dir(tries_dir, name);
+    let status = git::clone_command(url, &temp, options)
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .status();
+
+    if !matches!(status, Ok(s) if s.success()) {
+        let _ = fs::remove_dir_all(&temp);
+        bail!("Failed to clone the repository.");
+    }
+    place_clone(&temp, tries_dir, name, options)
+}
+
+/// Like `clone`, but hands git's progress to `progress` instead of showing it, for
+/// callers that own the terminal. Once `cancel` is set, git is killed within
+/// CANCEL_POLL, even when stalled without output, and nothing is left behind.
+pub fn clone_with_progress(
+    url: &str,
+    tries_dir: &Path,
+    name: &str,
+    options: &CloneOptions,
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(CloneProgress),
+) -> Result<String> {
+    let temp = temp_clone_dir(tries_dir, name);
+    let mut child = git::clone_command(url, &temp, options)
+        .arg("--progress")
+        // A credential prompt would hang behind the TUI; fail instead
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("running git")?;
+
+    // git's output is read on a thread of its own, so a stalled network (no output
+    // at all) can't keep the cancel flag from being looked at
+    let (tx, rx) = mpsc::channel();
+    if let Some(stderr) = child.stderr.take() {
+        std::thread::spawn(move || {
+            // Progress lines end in \r while they update, and in \n once a stage is done
+            for chunk in BufReader::new(stderr).split(b'\r').map_while(Result::ok) {
+                for line in String::from_utf8_lossy(&chunk).lines() {
+                    let line = if line.starts_with("fatal:") || line.starts_with("error:") {
+                        Err(line.trim().to_string())
+                    } else if let Some(update) = CloneProgress::parse(line) {
+                        Ok(update)
+                    } else {
+                        continue;
+                    };
+                    if tx.send(line).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    let mut last_error = None;
+    let status = loop {
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            let _ = fs::remove_dir_all(&temp);
+            bail!("Clone cancelled");
+        }
+        match rx.recv_timeout(CANCEL_POLL) {
+            Ok(Ok(update)) => progress(update),
+            Ok(Err(error)) => last_error = Some(error),
+            Err(RecvTimeoutError::Timeout) => {}
+            // git closed its stderr: it is about to exit
+            Err(RecvTimeoutError::Disconnected) => match child.try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) => std::thread::sleep(CANCEL_POLL),
+                Err(e) => break Err(e),
+            },
+        }
+    };
+
+    if !matches!(status, Ok(s) if s.success()) {
+        let _ = fs::remove_dir_all(&temp);
+        match last_error {
+            Some(error) => bail!("Failed to clone the repository: {}", error),
+            None => bail!("Failed to clone the repository."),
+        }
+    }
+    place_clone(&temp, tries_dir, name, options)
+}
+
+// Finishes the clone at `temp` (see `git::finish_clone`) and renames it into place
+fn place_clone(
+    temp: &Path,
+    tries_dir: &Path,
+    name: &str,
+    options: &CloneOptions,
+) -> Result<String> {
+    let result = git::finish_clone(temp, options)
+        .and_then(|_| Ok(rename_into_place(temp, tries_dir, name)?));
+    if result.is_err() {
+        let _ = fs::remove_dir_all(temp);
+    }
+    result
+}
+
+/// Name of a worktree experiment: the repository's name and the branch, e.g. "try-rs-fix-scan"
+pub fn worktree_name(repo: &Path, branch: &str) -> String {
+    let repo_name = repo
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "worktree".to_string());
+    format!("{}-{}", repo_name, branch.replace('/', "-"))
+}
+
+/// Creates the experiment `name` under `tries_dir` as a git worktree of `repo` with
+/// `branch` checked out, so it shares the repository's objects and branches.
+/// Returns the new directory.
+pub fn worktree(tries_dir: &Path, repo: &Path, branch: &str, name: &str) -> Result<PathBuf> {
+    let name = normalize_selection(name);
+    if name.is_empty() {
+        bail!("The experiment name is empty");
+    }
+    if !stays_inside(&name) {
+        bail!("'{}' is not a name inside the tries directory", name);
+    }
+    let path = tries_dir.join(&name);
+    if path.exists() {
+        bail!("Experiment '{}' already exists", name);
+    }
+    git::worktree_add(repo, &path, branch)?;
+    Ok(path)
+}
+
+/// Runs the delete hooks (quietly inside the TUI), then moves the experiment at `path`
+/// into the trash of `tries_dir` (see `try-rs restore`). A failing hook keeps the
+/// experiment. Returns where it ended up; worktrees are removed through git for good
+/// instead and return None. Git refuses to remove a worktree with uncommitted changes
+/// unless `force`, which callers only pass once the user confirmed losing them.
+pub fn delete(
+    hooks: &Hooks,
+    tries_dir: &Path,
+    path: &Path,
+    quiet: bool,
+    force: bool,
+) -> Result<Option<PathBuf>> {
+    hooks.run(Hook::Delete, path, quiet)?;
+    if git::is_worktree(path) {
+        git::worktree_remove(path, force)?;
+        return Ok(None);
+    }
+    Ok(Some(move_to_trash(path, tries_dir)?))
+}
+
+/// Moves the experiment at `path` into the tries root `root`, keeping its name.
+/// Worktrees are moved through git, which would otherwise lose track of them.
+/// Returns the new path; favorites are left to the caller.
+pub fn move_to_root(path: &Path, root: &Path) -> Result<PathBuf> {
+    if git::is_worktree(path) {
+        // git refuses a destination that exists
+        let target = root.join(path.file_name().unwrap_or_default());
+        git::worktree_move(path, &target)?;
+        return Ok(target);
+    }
+    move_experiment(path, root).with_context(|| format!("moving {}", path.display()))
+}
+
+/// Renames the experiment at `path` to `new_name` and carries its favorite star
+/// over, warning (only in the log when `quiet`, inside the TUI) if that fails.
+/// Worktrees are moved through git, which would otherwise lose track of them.
+/// Returns the new path.
+pub fn rename(paths: &Paths, path: &Path, new_name: &str, quiet: bool) -> Result<PathBuf> {
+    let renamed = if git::is_worktree(path) {
+        let target = rename_target(path, new_name)?;
+        git::worktree_move(path, &target)?;
+        target
+    } else {
+        rename_experiment(path, new_name).with_context(|| format!("renaming {}", path.display()))?
+    };
+    if let Err(e) = favorites::rename(paths, path, &renamed) {
+        if quiet {
+            log::warn!("could not update the favorites: {}", e);
+        } else {
+            eprintln!("Warning: could not update the favorites: {}", e);
+        }
+    }
+    Ok(renamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use std::time::Instant;
+
+    // A repository with one commit, to clone from
+    fn repo(root: &Path) -> PathBuf {
+        let repo = root.join("origin");
+        fs::create_dir(&repo).unwrap();
+        let git = |args: &[&str]| {
+            let ok = Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status
+                .success();
+            assert!(ok, "git {:?}", args);
+        };
+        git(&["init", "-q"]);
+        fs::write(repo.join("README"), "hi").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-qm", "init"]);
+        repo
+    }
+
+    #[test]
+    fn create_refuses_names_outside_the_tries_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let tries = dir.path().join("tries");
+        fs::create_dir(&tries).unwrap();
+        let settings = Settings::for_tests(&tries);
+        let outside = dir.path().join("outside");
+        for name in ["../outside", outside.to_str().unwrap(), ".trash/x"] {
+            assert!(create(&settings, name, None).is_err(), "{}", name);
+        }
+        assert!(!outside.exists() && !tries.join(".trash").exists());
+
+        let path = create(&settings, "clients/acme", None).unwrap();
+        assert_eq!(path, tries.join("clients/acme"));
+        assert!(path.is_dir());
+    }
+
+    #[test]
+    fn worktree_refuses_names_outside_the_tries_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let origin = repo(dir.path());
+        let tries = dir.path().join("tries");
+        fs::create_dir(&tries).unwrap();
+        for name in ["../outside", ".trash/x"] {
+            assert!(worktree(&tries, &origin, "wt", name).is_err(), "{}", name);
+        }
+        assert!(!dir.path().join("outside").exists() && !tries.join(".trash").exists());
+    }
+
+    #[test]
+    fn clone_with_progress_places_the_clone() {
+        let dir = tempfile::tempdir().unwrap();
+        let origin = repo(dir.path());
+        let tries = dir.path().join("tries");
+        fs::create_dir(&tries).unwrap();
+        let url = origin.to_string_lossy();
+        let cancel = AtomicBool::new(false);
+        let name = clone_with_progress(
+            &url,
+            &tries,
+            "copy",
+            &CloneOptions::default(),
+            &cancel,
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(name, "copy");
+        assert!(tries.join("copy/README").is_file());
+    }
+
+    #[test]
+    fn cancelled_clone_leaves_nothing_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let origin = repo(dir.path());
+        let tries = dir.path().join("tries");
+        fs::create_dir(&tries).unwrap();
+        let url = origin.to_string_lossy();
+        let cancel = AtomicBool::new(true);
+        let started = Instant::now();
+        let result = clone_with_progress(
+            &url,
+            &tries,
+            "copy",
+            &CloneOptions::default(),
+            &cancel,
+            |_| {},
+        );
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(fs::read_dir(&tries).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::utils::expand_path;
+use std::path::PathBuf;
+
+// Every file try-rs reads or writes is located through this module:
+// - configuration lives in the config dir (XDG_CONFIG_HOME)
+// - history and other state in the state dir (XDG_STATE_HOME, data dir on macOS/Windows)
+// - anything that can be regenerated in the cache dir
+
+// Clone history, one line per successful clone
+pub const CLONES_FILE: &str = "clones.tsv";
+
+// Favorite experiments, one path per line
+pub const FAVORITES_FILE: &str = "favorites.txt";
+
+// Experiments entered, one line per visit
+pub const VISITS_FILE: &str = "visits.tsv";
+
+// State files carried over by `try-rs export` / `import`. Machine-local state
+// (caches, update check timestamps) is deliberately left out.
+pub const EXPORTED_STATE_FILES: &[&str] = &[CLONES_FILE, FAVORITES_FILE, VISITS_FILE];
+
+// Base config directory shared with other tools (e.g., ~/.config), if there is a home
+pub fn user_config_dir() -> Option<PathBuf> {
+    dirs::config_dir().or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+}
+
+// Like `user_config_dir`, but without a home directory the temp dir stands in, so
+// try-rs still runs on defaults and env vars
+pub fn config_home() -> PathBuf {
+    user_config_dir().unwrap_or_else(std::env::temp_dir)
+}
+
+// Directory holding try-rs' own configuration (TRY_CONFIG_DIR overrides it)
+pub fn app_config_dir() -> PathBuf {
+    std::env::var_os("TRY_CONFIG_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config_home().join("try-rs"))
+}
+
+// Places searched for the config file, in order of precedence
+pub fn config_candidates(file_name: &str) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Some(env_dir) = std::env::var_os("TRY_CONFIG_DIR") {
+        candidates.push(PathBuf::from(env_dir).join(file_name));
+    }
+    candidates.push(config_home().join("try-rs").join(file_name));
+    if let Some(home) = dirs::home_dir() {
+        // ~/.config/try-rs is also honored on macOS/Windows
+        candidates.push(home.join(".config").join("try-rs").join(file_name));
+    }
+    candidates
+}
+
+#[derive(Clone)]
+pub struct Paths {
+    pub config_dir: PathBuf,
+    pub state_dir: PathBuf,
+    pub cache_dir: PathBuf,
+}
+
+impl Paths {
+    // Resolves the state and cache locations. Precedence: TRY_STATE_DIR > config > platform default
+    pub fn resolve(state_dir: Option<&str>, cache_dir: Option<&str>) -> Self {
+        let state_dir = std::env::var_os("TRY_STATE_DIR")
+            .map(PathBuf::from)
+            .or_else(|| state_dir.map(expand_path))
+            .unwrap_or_else(|| {
+                dirs::state_dir()
+                    .or_else(dirs::data_dir)
+                    .unwrap_or_else(std::env::temp_dir)
+                    .join("try-rs")
+            });
+        let cache_dir = cache_dir.map(expand_path).unwrap_or_else(|| {
+            dirs::cache_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("try-rs")
+        });
+
+        log::debug!("state dir: {}", state_dir.display());
+        log::debug!("cache dir: {}", cache_dir.display());
+        Self {
+            config_dir: app_config_dir(),
+            state_dir,
+            cache_dir,
+        }
+    }
+
+    pub fn state_file(&self, name: &str) -> PathBuf {
+        self.state_dir.join(name)
+    }
+}
@@ -0,0 +1,104 @@
+use crate::detect::Detectors;
+use crate::favorites;
+use crate::preview::format_age;
+use crate::tui::{Outcome, SortMode, TryEntry, scan_entries};
+use crate::utils::normalize_selection;
+use anyhow::{Result, bail};
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Line-based picker for terminals the TUI can't drive (`--plain`, or TERM=dumb).
+// The list goes to stderr and the answer is read from stdin; no raw mode involved.
+pub fn pick(
+    tries_dir: &Path,
+    scan_depth: usize,
+    detectors: &Detectors,
+    favorites: &HashSet<PathBuf>,
+    sort: SortMode,
+) -> Result<Outcome> {
+    let mut entries = scan_entries(tries_dir, scan_depth, detectors);
+    favorites::mark(&mut entries, favorites);
+    sort.sort(&mut entries);
+
+    let now = SystemTime::now();
+    let width = entries.len().to_string().len();
+    for (i, entry) in entries.iter().enumerate() {
+        let age = now.duration_since(entry.modified).unwrap_or_default();
+        eprintln!(
+            "{:>width$}) {}  ({} ago)",
+            i + 1,
+            entry.name,
+            format_age(age)
+        );
+    }
+    eprint!(
+        "Number, or a name/URL to create (prefix `e ` to open in the editor, empty to cancel): "
+    );
+    io::stderr().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let (open_editor, answer) = match input.trim().strip_prefix("e ") {
+        Some(rest) => (true, rest.trim()),
+        None => (false, input.trim()),
+    };
+
+    Ok(Outcome {
+        selection: selection(&entries, answer)?,
+        open_editor,
+        scratch: false,
+        diff: None,
+        template: None,
+        edit_file: None,
+        spawn: None,
+        cloned: None,
+        tries_dir: None,
+    })
+}
+
+// What `answer` picks: a number picks from the list, and anything else is handled
+// like a typed query. A number past the end is an error rather than a new name.
+fn selection(entries: &[TryEntry], answer: &str) -> Result<Option<String>> {
+    if !answer.is_empty() && answer.bytes().all(|b| b.is_ascii_digit()) {
+        return match answer.parse::<usize>() {
+            Ok(n) if (1..=entries.len()).contains(&n) => Ok(Some(entries[n - 1].selection())),
+            _ => bail!("No entry {} (the list has {})", answer, entries.len()),
+        };
+    }
+    Ok(Some(normalize_selection(answer)).filter(|s| !s.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meta::Meta;
+    use crate::scan::RawEntry;
+
+    fn entry(name: &str) -> TryEntry {
+        TryEntry::from_raw(RawEntry {
+            path: PathBuf::from("/tries").join(name),
+            name: name.to_string(),
+            modified: SystemTime::UNIX_EPOCH,
+            created: SystemTime::UNIX_EPOCH,
+            kinds: Vec::new(),
+            git: false,
+            meta: Meta::default(),
+        })
+    }
+
+    #[test]
+    fn numbers_pick_from_the_list_and_names_create() {
+        let entries = [entry("a"), entry("b")];
+        assert_eq!(
+            selection(&entries, "2").unwrap().as_deref(),
+            Some("/tries/b")
+        );
+        assert_eq!(selection(&entries, "new/").unwrap().as_deref(), Some("new"));
+        assert_eq!(selection(&entries, "").unwrap(), None);
+        for answer in ["0", "3", "7", "99999999999999999999999"] {
+            assert!(selection(&entries, answer).is_err(), "{}", answer);
+        }
+    }
+}
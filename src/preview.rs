@@ -0,0 +1,287 @@
+use crate::utils::{PREVIEW_SKIP_DIRS, WalkLimits, format_size};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant, SystemTime};
+
+// How many files the recent-changes preview shows
+const RECENT_FILES: usize = 10;
+
+// How many commits the summary preview lists
+pub const GIT_LOG_COMMITS: usize = 8;
+
+// How much of a file the preview reads at most
+const FILE_PREVIEW_BYTES: u64 = 64 * 1024;
+
+// Caps for the recent-changes walk, so huge experiments can't stall the worker
+const RECENT_LIMITS: WalkLimits = WalkLimits {
+    max_depth: 32,
+    max_entries: 20_000,
+    budget: Duration::from_secs(2),
+};
+
+// What the preview pane shows for the selected entry (cycled with F4)
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum PreviewMode {
+    Summary,
+    #[default]
+    Readme, // Falls back to the file list when there is no README
+    Files,
+    Recent,
+}
+
+impl PreviewMode {
+    pub fn next(self) -> Self {
+        match self {
+            PreviewMode::Summary => PreviewMode::Readme,
+            PreviewMode::Readme => PreviewMode::Files,
+            PreviewMode::Files => PreviewMode::Recent,
+            PreviewMode::Recent => PreviewMode::Summary,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PreviewMode::Summary => "Summary",
+            PreviewMode::Readme => "README",
+            PreviewMode::Files => "Files",
+            PreviewMode::Recent => "Recent",
+        }
+    }
+}
+
+// Output of the recent-changes preview, ready to be rendered line by line
+#[derive(Clone, Default)]
+pub struct RecentChanges {
+    pub lines: Vec<String>,
+    pub truncated: bool, // The walk hit its limits, older files may be missing
+}
+
+// Lists what changed last in `dir`: uncommitted git changes when there are any,
+// otherwise the most recently modified files.
+pub fn recent_changes(dir: &Path, is_git: bool) -> RecentChanges {
+    if is_git && let Some(lines) = git_changes(dir) {
+        return RecentChanges {
+            lines,
+            truncated: false,
+        };
+    }
+    recent_files(dir)
+}
+
+// `git status --porcelain` followed by `git diff --stat`, or None if the tree is clean
+fn git_changes(dir: &Path) -> Option<Vec<String>> {
+    let run = |args: &[&str]| -> Vec<String> {
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| {
+                String::from_utf8_lossy(&o.stdout)
+                    .lines()
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let status = run(&["status", "--porcelain"]);
+    if status.is_empty() {
+        return None;
+    }
+    let mut lines = status;
+    let diff = run(&["diff", "--stat"]);
+    if !diff.is_empty() {
+        lines.push(String::new());
+        lines.extend(diff);
+    }
+    Some(lines)
+}
+
+// The last `count` commits of the repository at `dir`, newest first, as
+// (commit time, subject). Empty when git fails or there are no commits yet.
+pub fn git_log(dir: &Path, count: usize) -> Vec<(SystemTime, String)> {
+    let Ok(output) = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["log", "--no-color", "--format=%ct%x09%s"])
+        .arg(format!("-{}", count))
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (time, subject) = line.split_once('\t')?;
+            let secs = time.parse().ok()?;
+            Some((
+                SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+                subject.to_string(),
+            ))
+        })
+        .collect()
+}
+
+// The most recently modified files below `dir`, newest first, skipping build/VCS dirs
+fn recent_files(dir: &Path) -> RecentChanges {
+    let started = Instant::now();
+    let mut files: Vec<(SystemTime, String)> = Vec::new();
+    let mut truncated = false;
+    let mut visited = 0;
+    let mut pending = vec![(dir.to_path_buf(), 0)];
+
+    'walk: while let Some((current, depth)) = pending.pop() {
+        if started.elapsed() > RECENT_LIMITS.budget {
+            truncated = true;
+            break;
+        }
+        let Ok(read_dir) = fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            visited += 1;
+            if visited > RECENT_LIMITS.max_entries {
+                truncated = true;
+                break 'walk;
+            }
+            let Ok(metadata) = fs::symlink_metadata(entry.path()) else {
+                continue;
+            };
+            if metadata.is_dir() {
+                let name = entry.file_name();
+                let skipped = PREVIEW_SKIP_DIRS.contains(&name.to_string_lossy().as_ref());
+                if !skipped && depth < RECENT_LIMITS.max_depth {
+                    pending.push((entry.path(), depth + 1));
+                }
+            } else if let Ok(modified) = metadata.modified() {
+                let relative = entry.path();
+                let relative = relative.strip_prefix(dir).unwrap_or(&relative);
+                files.push((modified, relative.to_string_lossy().to_string()));
+            }
+        }
+    }
+
+    files.sort_by_key(|f| std::cmp::Reverse(f.0));
+    let now = SystemTime::now();
+    let lines = files
+        .into_iter()
+        .take(RECENT_FILES)
+        .map(|(modified, name)| {
+            let age = now.duration_since(modified).unwrap_or_default();
+            format!("{:>4}  {}", format_age(age), name)
+        })
+        .collect();
+
+    RecentChanges { lines, truncated }
+}
+
+// The README at the top of `dir`, preferring Markdown when there are several
+pub fn find_readme(dir: &Path) -> Option<PathBuf> {
+    let mut candidates: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().to_lowercase().starts_with("readme"))
+        })
+        .collect();
+    candidates.sort_by_key(|path| {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase();
+        (name != "readme.md", !name.ends_with(".md"), name)
+    });
+    candidates.into_iter().next()
+}
+
+// Contents of a file opened from the preview browser
+#[derive(Clone)]
+pub enum FilePreview {
+    Text {
+        lines: Vec<String>,
+        truncated: bool, // Only the first FILE_PREVIEW_BYTES were read
+    },
+    Binary(u64),
+    Unreadable(String),
+}
+
+impl FilePreview {
+    // Lines to render, with a placeholder for content that can't be shown as text
+    pub fn lines(&self) -> Vec<String> {
+        match self {
+            FilePreview::Text { lines, .. } => lines.clone(),
+            FilePreview::Binary(size) => vec![format!("(binary file, {})", format_size(*size))],
+            FilePreview::Unreadable(err) => vec![format!("(unreadable: {})", err)],
+        }
+    }
+}
+
+// Reads the start of `path` for the preview: a bounded read, binary detection
+// and lossy UTF-8 decoding so arbitrary files never produce garbage.
+pub fn read_file_preview(path: &Path) -> FilePreview {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) => return FilePreview::Unreadable(e.to_string()),
+    };
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut buf = Vec::new();
+    if let Err(e) = file.take(FILE_PREVIEW_BYTES).read_to_end(&mut buf) {
+        return FilePreview::Unreadable(e.to_string());
+    }
+    if looks_binary(&buf) {
+        return FilePreview::Binary(size);
+    }
+
+    let truncated = size > buf.len() as u64;
+    let text = String::from_utf8_lossy(&buf);
+    let mut lines: Vec<String> = text
+        .lines()
+        .map(|line| line.replace('\t', "    ").replace(char::is_control, ""))
+        .collect();
+    if truncated && !text.ends_with('\n') {
+        // The last line was cut by the bound (possibly mid-character)
+        lines.pop();
+    }
+    FilePreview::Text { lines, truncated }
+}
+
+// A null byte, or mostly non-text control bytes, in the first KB means binary
+fn looks_binary(buf: &[u8]) -> bool {
+    let head = &buf[..buf.len().min(1024)];
+    if head.contains(&0) {
+        return true;
+    }
+    let control = head
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x0c | 0x1b))
+        .count();
+    control * 10 > head.len()
+}
+
+// Compact relative time (e.g., "5m", "3h", "12d")
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
@@ -0,0 +1,230 @@
+use crate::detect::Detectors;
+use crate::tui::TryEntry;
+use crate::utils::parse_age;
+use std::time::{Duration, SystemTime};
+
+// Names of the filters, for the help text
+pub const OPERATORS: &str = "lang:, git:, older:, newer:, has:";
+
+// A search query split into its fuzzy text and `key:value` filters. Words that
+// aren't a known filter (URLs, `foo:bar`) stay part of the text; a leading `-`
+// negates a filter, e.g. `-lang:rust`.
+pub struct Query {
+    pub text: String,
+    filters: Vec<(bool, Filter)>, // (negated, filter)
+}
+
+#[derive(PartialEq, Debug)]
+enum Filter {
+    Kind(String),    // lang:rust
+    Git(GitFilter),  // git:dirty
+    Older(Duration), // older:30d, by modification time
+    Newer(Duration), // newer:2w
+    Has(String),     // has:Dockerfile
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum GitFilter {
+    Dirty,
+    Clean,
+    Ahead,
+    Behind,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        let mut text = Vec::new();
+        let mut filters = Vec::new();
+        for word in input.split_whitespace() {
+            let (negated, term) = match word.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, word),
+            };
+            match term.split_once(':').and_then(|(k, v)| parse_filter(k, v)) {
+                Some(filter) => filters.push((negated, filter)),
+                None => text.push(word),
+            }
+        }
+        Query {
+            text: text.join(" "),
+            filters,
+        }
+    }
+
+    pub fn has_filters(&self) -> bool {
+        !self.filters.is_empty()
+    }
+
+    // The `lang:` values no detector answers to; `git` always exists
+    pub fn unknown_kinds(&self, detectors: &Detectors) -> Vec<&str> {
+        self.filters
+            .iter()
+            .filter_map(|(_, filter)| match filter {
+                Filter::Kind(name)
+                    if !name.eq_ignore_ascii_case("git") && !detectors.knows(name) =>
+                {
+                    Some(name.as_str())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    // git: filters need the status of every repository
+    pub fn needs_git_status(&self) -> bool {
+        self.filters
+            .iter()
+            .any(|(_, f)| matches!(f, Filter::Git(_)))
+    }
+
+    // Whether `entry` passes every filter. A repository whose git status isn't
+    // known yet fails git: filters either way, until the status comes in.
+    pub fn matches(&self, entry: &TryEntry, now: SystemTime) -> bool {
+        self.filters.iter().all(|(negated, filter)| {
+            let age = now.duration_since(entry.modified).unwrap_or_default();
+            let hit = match filter {
+                Filter::Kind(name) if name.eq_ignore_ascii_case("git") => Some(entry.is_git()),
+                Filter::Kind(name) => Some(entry.has_kind(name)),
+                Filter::Git(want) => entry.git_status.map(|s| match want {
+                    GitFilter::Dirty => s.dirty,
+                    GitFilter::Clean => !s.dirty && s.ahead == 0 && s.behind == 0,
+                    GitFilter::Ahead => s.ahead > 0,
+                    GitFilter::Behind => s.behind > 0,
+                }),
+                Filter::Older(limit) => Some(age > *limit),
+                Filter::Newer(limit) => Some(age <= *limit),
+                Filter::Has(name) => Some(entry.path.join(name).exists()),
+            };
+            hit.is_some_and(|hit| hit != *negated)
+        })
+    }
+}
+
+fn parse_filter(key: &str, value: &str) -> Option<Filter> {
+    match key.to_ascii_lowercase().as_str() {
+        "lang" | "type" if !value.is_empty() => Some(Filter::Kind(value.to_string())),
+        "git" => parse_git(value).map(Filter::Git),
+        "older" => parse_age(value).map(Filter::Older),
+        "newer" => parse_age(value).map(Filter::Newer),
+        // A path of its own would escape the experiment
+        "has" if !value.is_empty() && !value.starts_with('/') && !value.contains("..") => {
+            Some(Filter::Has(value.to_string()))
+        }
+        _ => None,
+    }
+}
+
+fn parse_git(value: &str) -> Option<GitFilter> {
+    match value.to_ascii_lowercase().as_str() {
+        "dirty" => Some(GitFilter::Dirty),
+        "clean" => Some(GitFilter::Clean),
+        "ahead" => Some(GitFilter::Ahead),
+        "behind" => Some(GitFilter::Behind),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_state::GitStatus;
+    use crate::scan;
+    use std::fs;
+
+    #[test]
+    fn text_and_filters_are_split() {
+        let query = Query::parse("api lang:rust -git:dirty older:30d notes");
+        assert_eq!(query.text, "api notes");
+        assert_eq!(
+            query.filters,
+            [
+                (false, Filter::Kind("rust".to_string())),
+                (true, Filter::Git(GitFilter::Dirty)),
+                (false, Filter::Older(Duration::from_secs(30 * 86400))),
+            ]
+        );
+        assert!(query.has_filters());
+        assert!(query.needs_git_status());
+    }
+
+    #[test]
+    fn unknown_or_invalid_filters_stay_text() {
+        let query = Query::parse("https://x.io/a foo:bar git:maybe older:soon lang: has:../x");
+        assert_eq!(
+            query.text,
+            "https://x.io/a foo:bar git:maybe older:soon lang: has:../x"
+        );
+        assert!(!query.has_filters());
+    }
+
+    #[test]
+    fn ages_accept_every_unit_and_bare_days() {
+        let age = |input: &str| Query::parse(input).filters.pop().map(|(_, f)| f);
+        assert_eq!(
+            age("newer:12h"),
+            Some(Filter::Newer(Duration::from_secs(12 * 3600)))
+        );
+        assert_eq!(
+            age("older:2w"),
+            Some(Filter::Older(Duration::from_secs(14 * 86400)))
+        );
+        assert_eq!(
+            age("older:7"),
+            Some(Filter::Older(Duration::from_secs(7 * 86400)))
+        );
+        assert_eq!(age("older:99999999999999999999y"), None);
+    }
+
+    #[test]
+    fn filters_match_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("Cargo.toml"), "").unwrap();
+        let raw = scan::probe(path, &Detectors::default()).unwrap();
+        let mut entry = TryEntry::from_raw(raw);
+        let now = SystemTime::now();
+        let matches = |entry: &TryEntry, input: &str| Query::parse(input).matches(entry, now);
+
+        assert!(matches(&entry, "lang:rust"));
+        assert!(matches(&entry, "lang:cargo has:Cargo.toml"));
+        assert!(!matches(&entry, "lang:go"));
+        assert!(matches(&entry, "-lang:go"));
+        assert!(matches(&entry, "newer:1d"));
+        assert!(!matches(&entry, "older:1d"));
+
+        // Unknown status fails git: filters either way
+        assert!(!matches(&entry, "git:clean"));
+        assert!(!matches(&entry, "-git:clean"));
+        entry.git_status = Some(GitStatus {
+            dirty: true,
+            ..GitStatus::default()
+        });
+        assert!(matches(&entry, "git:dirty -git:clean"));
+    }
+
+    #[test]
+    fn unknown_kinds_are_reported() {
+        let detectors = Detectors::default();
+        let query = Query::parse("lang:rust -lang:cobol lang:GIT type:py");
+        assert_eq!(query.unknown_kinds(&detectors), ["cobol"]);
+    }
+
+    #[test]
+    fn git_filter_does_not_depend_on_the_git_detector() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("repo");
+        fs::create_dir_all(path.join(".git")).unwrap();
+        let detectors = Detectors::with_config(vec![crate::detect::DetectorConfig {
+            name: "git".to_string(),
+            file: crate::detect::Files::One("nothing-here".to_string()),
+            icon: None,
+            color: None,
+            label: None,
+        }]);
+        let entry = TryEntry::from_raw(scan::probe(path, &detectors).unwrap());
+        assert!(!entry.has_kind("git"));
+        assert!(entry.is_git());
+        assert!(Query::parse("lang:git").matches(&entry, SystemTime::now()));
+    }
+}
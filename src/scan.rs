@@ -0,0 +1,219 @@
+use crate::detect::{Detectors, ProjectKind};
+use crate::meta::{self, Meta};
+use crate::utils::{ARCHIVE_DIR, TEMP_CLONE_PREFIX, TRASH_DIR};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// One experiment directory as found by a scan, before any interpretation
+pub struct RawEntry {
+    /// Absolute path
+    pub path: PathBuf,
+    /// Relative to the tries root, '/'-separated: "clients/acme/api"
+    pub name: String,
+    pub modified: SystemTime,
+    pub created: SystemTime,
+    /// Found by their marker files
+    pub kinds: Vec<Arc<ProjectKind>>,
+    /// Has a `.git` (repository, worktree or submodule), whatever the detectors say
+    pub git: bool,
+    /// Description and notes from META_FILE
+    pub meta: Meta,
+}
+
+/// Where the experiment list comes from. The TUI, `--list` and `clean` only see
+/// RawEntry values, so anything able to produce them can stand in for the disk.
+pub trait ScanSource {
+    /// Every experiment of the source, in no particular order
+    fn scan(&self) -> Vec<RawEntry>;
+}
+
+/// The real source: the directories below a tries root. Folders that only hold other
+/// folders (`2024/`, `clients/acme/`) are looked into, down to `depth` levels.
+pub struct Scanner {
+    root: PathBuf,
+    depth: usize,
+    detectors: Detectors,
+}
+
+impl Scanner {
+    /// Scans the direct children of the tries root `root` for the built-in kinds
+    pub fn new(root: &Path) -> Self {
+        // Entries are keyed by absolute path, so resolve a relative TRY_PATH up front
+        let root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+        Self {
+            root,
+            depth: 1,
+            detectors: Detectors::default(),
+        }
+    }
+
+    /// Also finds experiments in subfolders, `depth` levels below the root at most
+    /// (see `scan_depth`); 1 only lists the direct children
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth.max(1);
+        self
+    }
+
+    /// Project kinds to look for besides the built-in ones (see `[[detectors]]`)
+    pub fn with_detectors(mut self, detectors: &Detectors) -> Self {
+        self.detectors = detectors.clone();
+        self
+    }
+
+    /// Hands each entry to `found` as soon as it has been probed, so a slow disk
+    /// (NFS, ...) can fill the list progressively
+    pub fn scan_each(&self, mut found: impl FnMut(RawEntry)) {
+        let mut count = 0;
+        self.walk(&self.root, 1, &mut |path, name| {
+            if let Some(mut entry) = probe(path, &self.detectors) {
+                entry.name = name;
+                log::trace!("{} {:?}", entry.path.display(), kind_names(&entry.kinds));
+                count += 1;
+                found(entry);
+            }
+        });
+        log::debug!("{} entries in {}", count, self.root.display());
+    }
+
+    /// Names of the experiments without probing them, for shell completion
+    pub fn names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        self.walk(&self.root, 1, &mut |_, name| names.push(name));
+        names
+    }
+
+    // Hands every experiment directory below `dir` to `visit`, with its name
+    fn walk(&self, dir: &Path, level: usize, visit: &mut impl FnMut(PathBuf, String)) {
+        let read_dir = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) => {
+                log::debug!("cannot read {}: {}", dir.display(), e);
+                return;
+            }
+        };
+        for entry in read_dir.flatten() {
+            // Skip clones still in progress, the trash and the archives
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(TEMP_CLONE_PREFIX) || name == TRASH_DIR || name == ARCHIVE_DIR {
+                continue;
+            }
+            // Symlinks aren't followed, and aren't experiments either
+            if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+                continue;
+            }
+            let path = entry.path();
+            if level < self.depth && is_folder_of_experiments(&path) {
+                self.walk(&path, level + 1, visit);
+                continue;
+            }
+            let name = match path.strip_prefix(&self.root) {
+                Ok(relative) if level > 1 => {
+                    let parts: Vec<_> = relative.iter().map(|c| c.to_string_lossy()).collect();
+                    parts.join("/")
+                }
+                _ => name,
+            };
+            visit(path, name);
+        }
+    }
+}
+
+// A directory holding nothing but visible subdirectories groups experiments rather
+// than being one. Any file, marker or dotfolder makes it an experiment, except the
+// trash and archives older versions left next to nested experiments.
+fn is_folder_of_experiments(path: &Path) -> bool {
+    let Ok(read_dir) = fs::read_dir(path) else {
+        return false;
+    };
+    let mut any = false;
+    for entry in read_dir.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == TRASH_DIR || name == ARCHIVE_DIR {
+            continue;
+        }
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        if !is_dir || name.starts_with('.') {
+            return false;
+        }
+        any = true;
+    }
+    any
+}
+
+/// Reads one experiment directory: its dates, the project kinds it holds and its notes
+pub fn probe(path: PathBuf, detectors: &Detectors) -> Option<RawEntry> {
+    let metadata = fs::symlink_metadata(&path).ok().filter(|m| m.is_dir())?;
+    Some(RawEntry {
+        name: path.file_name()?.to_string_lossy().into_owned(),
+        modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        created: metadata.created().unwrap_or(SystemTime::UNIX_EPOCH),
+        kinds: detectors.detect(&path),
+        git: path.join(".git").exists(),
+        meta: meta::load(&path),
+        path,
+    })
+}
+
+fn kind_names(kinds: &[Arc<ProjectKind>]) -> Vec<&str> {
+    kinds.iter().map(|k| k.name.as_str()).collect()
+}
+
+impl ScanSource for Scanner {
+    fn scan(&self) -> Vec<RawEntry> {
+        let mut entries = Vec::new();
+        self.scan_each(|entry| entries.push(entry));
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::move_to_trash;
+
+    fn names(root: &Path, depth: usize) -> Vec<String> {
+        let mut names = Scanner::new(root).with_depth(depth).names();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn nested_folders_are_walked_into() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("clients/acme/api")).unwrap();
+        fs::create_dir_all(root.join("clients/acme/web")).unwrap();
+        fs::create_dir_all(root.join("solo")).unwrap();
+        fs::write(root.join("solo/README"), "").unwrap();
+        assert_eq!(
+            names(root, 3),
+            ["clients/acme/api", "clients/acme/web", "solo"]
+        );
+        assert_eq!(names(root, 1), ["clients", "solo"]);
+    }
+
+    #[test]
+    fn deleting_a_nested_experiment_keeps_its_folder_a_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("clients/acme/api")).unwrap();
+        fs::create_dir_all(root.join("clients/acme/web")).unwrap();
+
+        let trashed = move_to_trash(&root.join("clients/acme/api"), root).unwrap();
+        assert_eq!(trashed, root.join(TRASH_DIR).join("api"));
+        assert!(!root.join("clients/acme").join(TRASH_DIR).exists());
+        assert_eq!(names(root, 3), ["clients/acme/web"]);
+    }
+
+    #[test]
+    fn trash_and_archives_inside_a_folder_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("clients/acme/web")).unwrap();
+        fs::create_dir_all(root.join("clients/acme").join(TRASH_DIR).join("api")).unwrap();
+        fs::create_dir_all(root.join("clients/acme").join(ARCHIVE_DIR)).unwrap();
+        assert_eq!(names(root, 3), ["clients/acme/web"]);
+    }
+}
@@ -1,113 +1,288 @@
-use anyhow::Result;
+use crate::cli::Shell;
+use crate::completions::NUSHELL_COMPLETER;
+use anyhow::{Context, Result};
 use std::fs;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use try_rs::paths::{app_config_dir, user_config_dir};
 
-pub fn setup_fish() -> Result<()> {
-    let config_dir = dirs::config_dir().unwrap_or_else(|| {
-        dirs::home_dir()
-            .expect("Could not find home directory")
-            .join(".config")
-    });
+// Setup edits files under the user's home, so unlike the config lookup it has no fallback
+fn home_dir() -> Result<PathBuf> {
+    dirs::home_dir().context("Could not find the home directory")
+}
 
-    let fish_functions_dir = config_dir.join("fish").join("functions");
+fn config_home() -> Result<PathBuf> {
+    user_config_dir().context("Could not find the config directory")
+}
 
-    if !fish_functions_dir.exists() {
-        fs::create_dir_all(&fish_functions_dir)?;
+// Single-quotes `s` for the shell wrapper's eval, so names containing quotes,
+// spaces or `$` can't break out of the command. POSIX shells, fish and tcsh need
+// ' written as '\''; PowerShell and Elvish double it instead. PowerShell also ends
+// a quoted string at the typographic quotes U+2018-U+201B, so those are doubled too,
+// and tcsh expands `!` even between single quotes. Without --shell, Windows is
+// assumed to be PowerShell.
+pub fn quote(s: &str, shell: Option<Shell>) -> String {
+    let shell = shell.unwrap_or(if cfg!(windows) {
+        Shell::PowerShell
+    } else {
+        Shell::Bash
+    });
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match (shell, c) {
+            (Shell::PowerShell, '\'' | '\u{2018}'..='\u{201B}') | (Shell::Elvish, '\'') => {
+                escaped.push(c);
+                escaped.push(c);
+            }
+            (_, '\'') => escaped.push_str("'\\''"),
+            (Shell::Tcsh, '!') => escaped.push_str("\\!"),
+            _ => escaped.push(c),
+        }
     }
+    format!("'{}'", escaped)
+}
 
-    let file_path = fish_functions_dir.join("try-rs.fish");
-    let content = r#"function try-rs
-    # Captures the output of the binary (stdout) which is the "cd" command
-    # The TUI is rendered on stderr, so it doesn't interfere.
-    set command (command try-rs $argv | string collect)
-
-    if test -n "$command"
-        eval $command
-    end
-end
-"#;
+// What the wrapper for `shell` expects on stdout to enter `path`, or open it with `editor`
+pub fn enter_command(shell: Option<Shell>, editor: Option<&str>, path: &Path) -> String {
+    let path = path.to_string_lossy();
+    match (shell, editor) {
+        (Some(s), Some(cmd)) if !s.evals() => record_run(words(cmd).chain([path.to_string()])),
+        (Some(s), None) if !s.evals() => serde_json::json!({ "cd": path }).to_string(),
+        (_, Some(cmd)) => format!("{} {}", cmd, quote(&path, shell)),
+        (_, None) => format!("cd {}", quote(&path, shell)),
+    }
+}
 
-    fs::write(&file_path, content)?;
-    eprintln!("Fish function created at: {}", file_path.display());
-    eprintln!(
-        "You may need to restart your shell or run 'source {}' to apply changes.",
-        file_path.display()
-    );
+// What the wrapper for `shell` expects on stdout to run `args` (a program and its
+// arguments)
+pub fn run_command(shell: Option<Shell>, args: &[String]) -> String {
+    match (shell, args.split_first()) {
+        (Some(s), _) if !s.evals() => record_run(args.iter().cloned()),
+        (_, Some((program, rest))) => {
+            let quoted: Vec<String> = rest.iter().map(|arg| quote(arg, shell)).collect();
+            format!("{} {}", program, quoted.join(" "))
+        }
+        (_, None) => String::new(),
+    }
+}
 
-    Ok(())
+// What the wrapper for `shell` expects on stdout to compare `left` and `right` with `tool`
+pub fn diff_command(shell: Option<Shell>, tool: &str, left: &Path, right: &Path) -> String {
+    let (left, right) = (left.to_string_lossy(), right.to_string_lossy());
+    match shell {
+        Some(s) if !s.evals() => {
+            record_run(words(tool).chain([left.to_string(), right.to_string()]))
+        }
+        _ => format!("{} {} {}", tool, quote(&left, shell), quote(&right, shell)),
+    }
 }
 
-pub fn setup_zsh() -> Result<()> {
-    let config_dir = dirs::config_dir().unwrap_or_else(|| {
-        dirs::home_dir()
-            .expect("Could not find home directory")
-            .join(".config")
-    });
+// Nushell can't eval and xonsh's quoting is Python's, so their wrappers get a JSON
+// record to act on instead of a command line: {"cd": path} to enter a directory,
+// {"run": [program, args...]} to run something. Nothing has to be split or unquoted
+// on their side.
+fn record_run(args: impl Iterator<Item = String>) -> String {
+    let args: Vec<String> = args.collect();
+    serde_json::json!({ "run": args }).to_string()
+}
 
-    let app_config_dir = config_dir.join("try-rs");
+// The words of a configured command like `code --wait`, which other shells eval as written
+fn words(command: &str) -> impl Iterator<Item = String> + '_ {
+    command.split_whitespace().map(str::to_string)
+}
 
-    if !app_config_dir.exists() {
-        fs::create_dir_all(&app_config_dir)?;
-    }
+// How try-rs hooks into one shell: a function named `try-rs` that runs the binary
+// with `--shell` and acts on what it prints, written to its own file and loaded
+// from the shell's startup file
+struct Wrapper {
+    shell: Shell,
+    name: &'static str,
+    script: &'static str,
+    file: fn() -> Result<PathBuf>,
+    startup: Option<fn() -> Result<PathBuf>>, // None: the shell finds `file` on its own
+    load: fn(&Path) -> String,                // The line in `startup` that loads `file`
+    create_startup: bool, // Create a missing startup file instead of printing the line
+    hint: Option<&'static str>,
+}
 
-    let file_path = app_config_dir.join("try-rs.zsh");
-    let content = r#"try-rs() {
-    # Captures the output of the binary (stdout) which is the "cd" command
-    # The TUI is rendered on stderr, so it doesn't interfere.
-    local output
-    output=$(command try-rs "$@")
+const WRAPPERS: &[Wrapper] = &[
+    Wrapper {
+        shell: Shell::Fish,
+        name: "Fish",
+        script: FISH,
+        file: || Ok(config_home()?.join("fish/functions/try-rs.fish")),
+        startup: None,
+        load: |file| format!("source {}", file.display()),
+        create_startup: false,
+        hint: None,
+    },
+    Wrapper {
+        shell: Shell::Zsh,
+        name: "Zsh",
+        script: ZSH,
+        file: || Ok(app_config_dir().join("try-rs.zsh")),
+        startup: Some(|| Ok(home_dir()?.join(".zshrc"))),
+        load: |file| format!("source {}", file.display()),
+        create_startup: false,
+        hint: None,
+    },
+    Wrapper {
+        shell: Shell::Bash,
+        name: "Bash",
+        script: BASH,
+        file: || Ok(app_config_dir().join("try-rs.bash")),
+        startup: Some(|| Ok(home_dir()?.join(".bashrc"))),
+        load: |file| format!("source {}", file.display()),
+        create_startup: false,
+        hint: None,
+    },
+    Wrapper {
+        shell: Shell::PowerShell,
+        name: "PowerShell",
+        script: POWERSHELL,
+        file: || Ok(app_config_dir().join("try-rs.ps1")),
+        startup: Some(powershell_profile),
+        load: |file| format!(". '{}'", file.display()),
+        create_startup: true,
+        hint: Some(
+            "If you get an error about running scripts, you may need to run: Set-ExecutionPolicy -Scope CurrentUser -ExecutionPolicy RemoteSigned",
+        ),
+    },
+    Wrapper {
+        shell: Shell::NuShell,
+        name: "Nushell",
+        script: NUSHELL,
+        file: || Ok(app_config_dir().join("try-rs.nu")),
+        startup: Some(|| Ok(config_home()?.join("nushell/config.nu"))),
+        load: |file| format!("source {}", file.display()),
+        create_startup: false,
+        hint: None,
+    },
+    Wrapper {
+        shell: Shell::Elvish,
+        name: "Elvish",
+        script: ELVISH,
+        file: || Ok(app_config_dir().join("try-rs.elv")),
+        startup: Some(|| Ok(config_home()?.join("elvish/rc.elv"))),
+        load: |file| format!("eval (slurp < '{}')", file.display()),
+        create_startup: true,
+        hint: None,
+    },
+    Wrapper {
+        shell: Shell::Xonsh,
+        name: "Xonsh",
+        script: XONSH,
+        file: || Ok(app_config_dir().join("try-rs.xsh")),
+        startup: Some(|| Ok(home_dir()?.join(".xonshrc"))),
+        load: |file| format!("source '{}'", file.display()),
+        create_startup: true,
+        hint: None,
+    },
+    Wrapper {
+        shell: Shell::Tcsh,
+        name: "tcsh",
+        script: TCSH,
+        file: || Ok(app_config_dir().join("try-rs.tcsh")),
+        startup: Some(|| Ok(home_dir()?.join(".tcshrc"))),
+        load: |file| format!("source {}", file.display()),
+        create_startup: true,
+        hint: None,
+    },
+];
+
+// The wrapper of `shell`; every shell has one
+fn wrapper(shell: Shell) -> &'static Wrapper {
+    WRAPPERS
+        .iter()
+        .find(|w| w.shell == shell)
+        .expect("every shell has a wrapper")
+}
 
-    if [ -n "$output" ]; then
-        eval "$output"
-    fi
+// Text of the wrapper function for `shell`
+pub fn wrapper_script(shell: Shell) -> String {
+    let script = wrapper(shell).script;
+    match shell {
+        // Its completions come with the wrapper (see `completions`)
+        Shell::NuShell => format!("{}\n{}", NUSHELL_COMPLETER, script),
+        _ => script.to_string(),
+    }
 }
-"#;
 
-    fs::write(&file_path, content)?;
-    eprintln!("ZSH function file created at: {}", file_path.display());
-
-    let home_dir = dirs::home_dir().expect("Could not find home directory");
-    let zshrc_path = home_dir.join(".zshrc");
-    let source_cmd = format!("source {}", file_path.display());
-
-    if zshrc_path.exists() {
-        let zshrc_content = fs::read_to_string(&zshrc_path)?;
-        if !zshrc_content.contains(&source_cmd) {
-            use std::io::Write;
-            let mut file = fs::OpenOptions::new().append(true).open(&zshrc_path)?;
-            writeln!(file, "\n# try-rs integration")?;
-            writeln!(file, "{}", source_cmd)?;
-            eprintln!("Added configuration to ~/.zshrc");
+// `try-rs --setup <shell>`: writes the wrapper and loads it from the startup file
+pub fn setup(shell: Shell) -> Result<()> {
+    let wrapper = wrapper(shell);
+    let file = (wrapper.file)()?;
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    fs::write(&file, wrapper_script(shell))
+        .with_context(|| format!("writing {}", file.display()))?;
+    eprintln!("{} function created at: {}", wrapper.name, file.display());
+
+    let load = (wrapper.load)(&file);
+    if let Some(startup) = wrapper.startup {
+        let startup = startup()?;
+        if startup.exists() {
+            let content = fs::read_to_string(&startup)?;
+            if content.contains(&load) {
+                eprintln!("Configuration already present in {}", startup.display());
+            } else {
+                let mut out = fs::OpenOptions::new().append(true).open(&startup)?;
+                writeln!(out, "\n# try-rs integration")?;
+                writeln!(out, "{}", load)?;
+                eprintln!("Added configuration to {}", startup.display());
+            }
+        } else if wrapper.create_startup {
+            if let Some(dir) = startup.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&startup, format!("# try-rs integration\n{}\n", load))
+                .with_context(|| format!("writing {}", startup.display()))?;
+            eprintln!("Created {} to load it", startup.display());
         } else {
-            eprintln!("Configuration already present in ~/.zshrc");
+            eprintln!("Could not find {}", startup.display());
+            eprintln!("Please add the following line to it:");
+            eprintln!("{}", load);
+            return Ok(());
         }
-    } else {
-        eprintln!("You need to source this file in your ~/.zshrc:");
-        eprintln!("{}", source_cmd);
     }
-
+    eprintln!(
+        "You may need to restart your shell or run '{}' to apply changes.",
+        load
+    );
+    if let Some(hint) = wrapper.hint {
+        eprintln!("{}", hint);
+    }
     Ok(())
 }
 
-pub fn setup_bash() -> Result<()> {
-    let config_dir = dirs::config_dir().unwrap_or_else(|| {
-        dirs::home_dir()
-            .expect("Could not find home directory")
-            .join(".config")
-    });
+// The profile of PowerShell 7, or of Windows PowerShell when only that one exists
+fn powershell_profile() -> Result<PathBuf> {
+    let documents = home_dir()?.join("Documents");
+    let ps7 = documents.join("PowerShell/Microsoft.PowerShell_profile.ps1");
+    let ps5 = documents.join("WindowsPowerShell/Microsoft.PowerShell_profile.ps1");
+    Ok(if !ps7.exists() && ps5.exists() {
+        ps5
+    } else {
+        ps7
+    })
+}
 
-    let app_config_dir = config_dir.join("try-rs");
+// The wrappers capture stdout, which carries what to do; the TUI is drawn on
+// stderr, so it doesn't interfere
 
-    if !app_config_dir.exists() {
-        fs::create_dir_all(&app_config_dir)?;
-    }
+const FISH: &str = r#"function try-rs
+    set command (command try-rs $argv --shell fish | string collect)
+
+    if test -n "$command"
+        eval $command
+    end
+end
+"#;
 
-    let file_path = app_config_dir.join("try-rs.bash");
-    let content = r#"try-rs() {
-    # Captures the output of the binary (stdout) which is the "cd" command
-    # The TUI is rendered on stderr, so it doesn't interfere.
+const ZSH: &str = r#"try-rs() {
     local output
-    output=$(command try-rs "$@")
+    output=$(command try-rs "$@" --shell zsh)
 
     if [ -n "$output" ]; then
         eval "$output"
@@ -115,180 +290,190 @@ pub fn setup_bash() -> Result<()> {
 }
 "#;
 
-    fs::write(&file_path, content)?;
-    eprintln!("Bash function file created at: {}", file_path.display());
-
-    let home_dir = dirs::home_dir().expect("Could not find home directory");
-    let bashrc_path = home_dir.join(".bashrc");
-    let source_cmd = format!("source {}", file_path.display());
-
-    if bashrc_path.exists() {
-        let bashrc_content = fs::read_to_string(&bashrc_path)?;
-        if !bashrc_content.contains(&source_cmd) {
-            use std::io::Write;
-            let mut file = fs::OpenOptions::new().append(true).open(&bashrc_path)?;
-            writeln!(file, "\n# try-rs integration")?;
-            writeln!(file, "{}", source_cmd)?;
-            eprintln!("Added configuration to ~/.bashrc");
-        } else {
-            eprintln!("Configuration already present in ~/.bashrc");
-        }
-    } else {
-        eprintln!("You need to source this file in your ~/.bashrc:");
-        eprintln!("{}", source_cmd);
-    }
+const BASH: &str = r#"try-rs() {
+    local output
+    output=$(command try-rs "$@" --shell bash)
 
-    Ok(())
+    if [ -n "$output" ]; then
+        eval "$output"
+    fi
 }
+"#;
 
-pub fn setup_powershell() -> Result<()> {
-    // 1. Create the function file in the app's config dir
-    let config_dir = dirs::config_dir().unwrap_or_else(|| {
-        dirs::home_dir()
-            .expect("Could not find home directory")
-            .join(".config")
-    });
-    let app_config_dir = config_dir.join("try-rs");
-    if !app_config_dir.exists() {
-        fs::create_dir_all(&app_config_dir)?;
-    }
-
-    let file_path = app_config_dir.join("try-rs.ps1");
-    let content = r#"
-# try-rs integration for PowerShell
+const POWERSHELL: &str = r#"# try-rs integration for PowerShell
 function try-rs {
-    # Captures the output of the binary (stdout) which is the "cd" or editor command
-    # The TUI is rendered on stderr, so it doesn't interfere.
-    $command = (try-rs.exe @args)
+    $command = (try-rs.exe @args --shell powershell)
 
     if ($command) {
         Invoke-Expression $command
     }
 }
 "#;
-    fs::write(&file_path, content.trim())?;
-    eprintln!(
-        "PowerShell function file created at: {}",
-        file_path.display()
-    );
 
-    // 2. Find the PowerShell profile and add the source command
-    let home_dir = dirs::home_dir().expect("Could not find home directory");
-    let profile_path_ps7 = home_dir
-        .join("Documents")
-        .join("PowerShell")
-        .join("Microsoft.PowerShell_profile.ps1");
-    let profile_path_ps5 = home_dir
-        .join("Documents")
-        .join("WindowsPowerShell")
-        .join("Microsoft.PowerShell_profile.ps1");
-
-    let profile_path = if profile_path_ps7.exists() {
-        profile_path_ps7
-    } else if profile_path_ps5.exists() {
-        profile_path_ps5
-    } else {
-        // If neither exists, default to the modern path.
-        profile_path_ps7
-    };
-
-    if let Some(parent) = profile_path.parent()
-        && !parent.exists()
-    {
-        fs::create_dir_all(parent)?;
-    }
+// The binary prints a record: {cd: path} to enter it, or {run: [program ...args]}
+// for the editor, diff tool or terminal; anything else (lists, paths) is passed on
+const NUSHELL: &str = r#"def --env --wrapped try-rs [...args: string@"nu-complete try-rs"] {
+    let output = (^try-rs ...$args --shell nu)
 
-    // The command to add to the profile. Note the dot-sourcing.
-    let source_cmd = format!(". '{}'", file_path.display());
-
-    if profile_path.exists() {
-        let profile_content = fs::read_to_string(&profile_path)?;
-        if !profile_content.contains(&source_cmd) {
-            use std::io::Write;
-            let mut file = fs::OpenOptions::new().append(true).open(&profile_path)?;
-            writeln!(file, "\n# try-rs integration")?;
-            writeln!(file, "{}", source_cmd)?;
-            eprintln!("Added configuration to {}", profile_path.display());
+    if ($output | str starts-with '{') {
+        let action = ($output | from json)
+        if ($action.run? | is-not-empty) {
+            run-external ...$action.run
         } else {
-            eprintln!(
-                "Configuration already present in {}",
-                profile_path.display()
-            );
+            cd $action.cd
         }
-    } else {
-        let mut file = fs::File::create(&profile_path)?;
-        writeln!(file, "# try-rs integration")?;
-        writeln!(file, "{}", source_cmd)?;
-        eprintln!(
-            "PowerShell profile created and configured at: {}",
-            profile_path.display()
-        );
+    } else if ($output | is-not-empty) {
+        print $output
     }
+}
+"#;
 
-    eprintln!(
-        "You may need to restart your shell or run '. {}' to apply changes.",
-        profile_path.display()
-    );
-    eprintln!(
-        "If you get an error about running scripts, you may need to run: Set-ExecutionPolicy -Scope CurrentUser -ExecutionPolicy RemoteSigned"
-    );
+// Loaded with `eval`, so the function is handed to the interactive namespace
+const ELVISH: &str = r#"fn try-rs {|@args|
+    var output = (e:try-rs $@args --shell elvish | slurp)
 
-    Ok(())
+    if (!=s $output '') {
+        eval $output
+    }
 }
+edit:add-var try-rs~ $try-rs~
+"#;
 
-pub fn setup_nushell() -> Result<()> {
-    let config_dir = dirs::config_dir()
-        .expect("Could not find config directory")
-        .join("nushell");
+// Gets the same records as Nushell, so no xonsh quoting is involved
+const XONSH: &str = r#"def _try_rs(args):
+    import json, subprocess
+
+    output = subprocess.run(['try-rs', *args, '--shell', 'xonsh'], stdout=subprocess.PIPE, text=True).stdout
+    if output.startswith('{'):
+        action = json.loads(output)
+        if 'run' in action:
+            subprocess.run(action['run'])
+        else:
+            cd @(action['cd'])
+    elif output:
+        print(output, end='')
+
+aliases['try-rs'] = _try_rs
+"#;
 
-    let app_config_dir = dirs::config_dir()
-        .expect("Could not find config directory")
-        .join("try-rs");
+// tcsh has no functions: an alias runs the binary, `\try-rs` skipping the alias itself.
+// The command is written to a temporary file and sourced rather than captured with
+// backquotes: inside "`...`" the arguments' own quotes would end the string, and
+// the captured text would go through another round of quoting before eval. `>!`
+// writes over the file even with noclobber set.
+const TCSH: &str = r#"alias try-rs 'set _try_rs_file = "`mktemp`"; \try-rs \!* --shell tcsh >\! "$_try_rs_file" && source "$_try_rs_file"; rm -f "$_try_rs_file"; unset _try_rs_file'
+"#;
 
-    if !app_config_dir.exists() {
-        fs::create_dir_all(&app_config_dir)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_posix_like_shells_close_and_reopen_single_quotes() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            assert_eq!(quote("a b", Some(shell)), "'a b'");
+            assert_eq!(quote("it's $HOME", Some(shell)), "'it'\\''s $HOME'");
+            assert_eq!(quote("wow!", Some(shell)), "'wow!'");
+        }
     }
 
-    let file_path = app_config_dir.join("try-rs.nu");
-    let content = r#"def --wrapped try-rs [...args] {
-    # Capture output. Stderr (TUI) goes directly to terminal.
-    let output = (try-rs.exe ...$args)
+    #[test]
+    fn quote_tcsh_escapes_history_expansion() {
+        assert_eq!(quote("wow!", Some(Shell::Tcsh)), "'wow\\!'");
+        assert_eq!(quote("it's", Some(Shell::Tcsh)), "'it'\\''s'");
+    }
 
-    if ($output | is-not-empty) {
+    #[test]
+    fn tcsh_alias_passes_the_arguments_outside_any_quotes() {
+        let alias = TCSH.trim_end();
+        let body = alias
+            .strip_prefix("alias try-rs '")
+            .and_then(|body| body.strip_suffix('\''))
+            .unwrap();
+        // The only single quotes delimit the alias; its double-quoted parts hold no arguments
+        assert!(!body.contains('\''));
+        assert!(body.contains("\\!*"));
+        for part in body.split('"').skip(1).step_by(2) {
+            assert!(!part.contains("\\!*"), "{:?}", part);
+        }
+        assert!(!body.contains("eval"));
+    }
 
-        # Grabs the path out of stdout returned by the binary and removes the single quotes
-        let $path = ($output | split row ' ').1 | str replace --all "\'" ''
-        cd $path
+    #[test]
+    fn quote_powershell_doubles_every_single_quote() {
+        let shell = Some(Shell::PowerShell);
+        assert_eq!(quote("it's $env:HOME", shell), "'it''s $env:HOME'");
+        assert_eq!(
+            quote("a\u{2018}b\u{2019}c\u{201A}d\u{201B}", shell),
+            "'a\u{2018}\u{2018}b\u{2019}\u{2019}c\u{201A}\u{201A}d\u{201B}\u{201B}'"
+        );
+        assert_eq!(quote("\u{201C}x\u{201D}", shell), "'\u{201C}x\u{201D}'");
+    }
+
+    #[test]
+    fn quote_elvish_only_doubles_ascii_quotes() {
+        let shell = Some(Shell::Elvish);
+        assert_eq!(quote("it's", shell), "'it''s'");
+        assert_eq!(quote("a\u{2019}b", shell), "'a\u{2019}b'");
     }
-}
-"#;
 
-    fs::write(&file_path, content)?;
-    eprintln!("Nushell function created at: {}", file_path.display());
-
-    // Modify config.nu to source the new file
-    let nu_config_path = config_dir.join("config.nu");
-    let source_cmd = format!("source {}", file_path.display());
-
-    if nu_config_path.exists() {
-        let nu_content = fs::read_to_string(&nu_config_path)?;
-        if !nu_content.contains(&source_cmd) {
-            use std::io::Write;
-            let mut file = fs::OpenOptions::new().append(true).open(&nu_config_path)?;
-            writeln!(file, "\n# try-rs integration")?;
-            writeln!(file, "{}", source_cmd)?;
-            eprintln!("Added configuration to {}", nu_config_path.display());
+    #[test]
+    fn quote_without_shell_follows_the_platform() {
+        let expected = if cfg!(windows) {
+            "'it''s'"
         } else {
-            eprintln!(
-                "Configuration already present in {}",
-                nu_config_path.display()
+            "'it'\\''s'"
+        };
+        assert_eq!(quote("it's", None), expected);
+    }
+
+    const ALL_SHELLS: [Shell; 8] = [
+        Shell::Fish,
+        Shell::Zsh,
+        Shell::Bash,
+        Shell::NuShell,
+        Shell::PowerShell,
+        Shell::Elvish,
+        Shell::Xonsh,
+        Shell::Tcsh,
+    ];
+
+    #[test]
+    fn enter_command_for_every_shell_and_editor() {
+        let path = Path::new("/tries/it's a \"test\"");
+        for shell in ALL_SHELLS {
+            let cd = enter_command(Some(shell), None, path);
+            let edit = enter_command(Some(shell), Some("code --wait"), path);
+            if shell.evals() {
+                let quoted = quote(&path.to_string_lossy(), Some(shell));
+                assert_eq!(cd, format!("cd {}", quoted), "{:?}", shell);
+                assert_eq!(edit, format!("code --wait {}", quoted), "{:?}", shell);
+            } else {
+                assert_eq!(cd, r#"{"cd":"/tries/it's a \"test\""}"#, "{:?}", shell);
+                assert_eq!(
+                    edit, r#"{"run":["code","--wait","/tries/it's a \"test\""]}"#,
+                    "{:?}",
+                    shell
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn enter_command_json_escapes_backslashes_and_control_characters() {
+        let path = Path::new("C:\\tries\\new\nline");
+        for shell in [Shell::NuShell, Shell::Xonsh] {
+            assert_eq!(
+                enter_command(Some(shell), None, path),
+                r#"{"cd":"C:\\tries\\new\nline"}"#
             );
         }
-    } else {
-        eprintln!("Could not find config.nu at {}", nu_config_path.display());
-        eprintln!("Please add the following line manually:");
-        eprintln!("{}", source_cmd);
     }
 
-    Ok(())
+    #[test]
+    fn enter_command_without_shell_quotes_for_the_platform() {
+        let path = Path::new("/tries/a b");
+        assert_eq!(enter_command(None, None, path), "cd '/tries/a b'");
+        assert_eq!(enter_command(None, Some("vim"), path), "vim '/tries/a b'");
+    }
 }
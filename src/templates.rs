@@ -0,0 +1,141 @@
+use crate::paths::app_config_dir;
+use crate::utils::copy_dir;
+use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+// Skeleton directories, copied as-is into new experiments: `templates/` next to the
+// config file that was loaded (like the hook scripts), or in the default config
+// directory without one
+pub fn templates_dir(config_file: Option<&Path>) -> PathBuf {
+    match config_file.and_then(Path::parent) {
+        Some(dir) => dir.join("templates"),
+        None => app_config_dir().join("templates"),
+    }
+}
+
+// The `[templates]` commands from config.toml, and where the skeleton directories live
+#[derive(Clone, Default)]
+pub struct Templates {
+    pub commands: HashMap<String, String>, // Template name -> command run in new experiments
+    pub skeletons: Option<PathBuf>,        // None offers no skeleton directories
+}
+
+impl Templates {
+    // Names of the `[templates]` commands and the skeleton directories, sorted
+    pub fn available(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.commands.keys().cloned().collect();
+        if let Some(Ok(entries)) = self.skeletons.as_deref().map(fs::read_dir) {
+            names.extend(
+                entries
+                    .flatten()
+                    .filter(|e| e.path().is_dir())
+                    .map(|e| e.file_name().to_string_lossy().into_owned()),
+            );
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    // Fills the new experiment `dir` from template `name`. A `[templates]` command wins
+    // over a skeleton directory of the same name.
+    pub fn apply(&self, name: &str, dir: &Path) -> Result<()> {
+        if let Some(command) = self.commands.get(name) {
+            log::debug!(
+                "template {}: running `{}` in {}",
+                name,
+                command,
+                dir.display()
+            );
+            return run(command, dir).with_context(|| format!("template '{}'", name));
+        }
+        if let Some(skeleton) = self.skeletons.as_ref().map(|s| s.join(name))
+            && skeleton.is_dir()
+        {
+            log::debug!("template {}: copying {}", name, skeleton.display());
+            return copy_dir(&skeleton, dir).with_context(|| format!("template '{}'", name));
+        }
+        let names = self.available();
+        if names.is_empty() {
+            let skeletons = self.skeletons.as_ref().map_or_else(
+                || "a templates directory".to_string(),
+                |s| s.display().to_string(),
+            );
+            bail!(
+                "No template named '{}' (add one to [templates] in config.toml or {})",
+                name,
+                skeletons
+            );
+        }
+        bail!(
+            "No template named '{}' (available: {})",
+            name,
+            names.join(", ")
+        );
+    }
+}
+
+// `command` run through the platform shell: sh, or cmd on Windows
+pub fn shell_command(command: &str) -> Command {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    cmd.arg(command);
+    cmd
+}
+
+// Runs `command` through the platform shell. Its output goes to stderr so the
+// shell wrapper only evals our own stdout.
+fn run(command: &str, dir: &Path) -> Result<()> {
+    let status = shell_command(command)
+        .current_dir(dir)
+        .stdout(Stdio::from(std::io::stderr()))
+        .status()
+        .with_context(|| format!("running `{}`", command))?;
+    if !status.success() {
+        bail!("`{}` failed ({})", command, status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skeletons_come_from_next_to_the_config_file() {
+        let config = Path::new("/etc/try/config.toml");
+        assert_eq!(templates_dir(Some(config)), Path::new("/etc/try/templates"));
+    }
+
+    #[test]
+    fn skeleton_directories_are_copied_and_commands_win() {
+        let dir = tempfile::tempdir().unwrap();
+        let skeletons = dir.path().join("templates");
+        fs::create_dir_all(skeletons.join("rust/src")).unwrap();
+        fs::write(skeletons.join("rust/src/main.rs"), "fn main() {}").unwrap();
+        fs::create_dir_all(skeletons.join("both")).unwrap();
+        let templates = Templates {
+            commands: HashMap::from([("both".to_string(), "echo command > made".to_string())]),
+            skeletons: Some(skeletons),
+        };
+        assert_eq!(templates.available(), ["both", "rust"]);
+
+        let new = dir.path().join("new");
+        fs::create_dir(&new).unwrap();
+        templates.apply("rust", &new).unwrap();
+        assert!(new.join("src/main.rs").is_file());
+        templates.apply("both", &new).unwrap();
+        assert!(new.join("made").is_file());
+        assert!(templates.apply("missing", &new).is_err());
+    }
+}
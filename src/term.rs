@@ -0,0 +1,51 @@
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+// Escape sequences for the terminal emulator. They are always written to stderr
+// (the tty), never to stdout, which the shell wrapper evals.
+
+// Names the terminal tab/window after `title` (and the tmux window when inside tmux)
+pub fn set_title(title: &str) {
+    let mut seq = format!("\x1b]2;{}\x07", title);
+    if std::env::var_os("TMUX").is_some() {
+        seq.push_str(&format!("\x1bk{}\x1b\\", title));
+    }
+    write_tty(&seq);
+}
+
+// OSC 7 tells terminals such as WezTerm or kitty the shell's new working directory
+pub fn report_cwd(path: &Path) {
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .unwrap_or_default();
+    let seq = format!(
+        "\x1b]7;file://{}{}\x1b\\",
+        host,
+        percent_encode(&path.to_string_lossy())
+    );
+    write_tty(&seq);
+}
+
+fn write_tty(seq: &str) {
+    let mut stderr = io::stderr();
+    if stderr.is_terminal() {
+        let _ = stderr.write_all(seq.as_bytes());
+        let _ = stderr.flush();
+    }
+}
+
+// Percent-encodes everything but unreserved characters and path separators
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
@@ -0,0 +1,430 @@
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier, Style};
+
+// Colors of the TUI. Built from one of the `PRESETS` (the `theme` key), then
+// adjusted key by key from `[colors]`.
+#[derive(Clone)]
+pub struct Theme {
+    pub title_try: Color,
+    pub title_rs: Color,
+    pub search_box: Color,
+    pub list_date: Color,
+    pub list_highlight_bg: Color,
+    pub list_highlight_fg: Color,
+    pub help_text: Color,
+    pub status_message: Color,
+    pub popup_bg: Color,
+    pub popup_text: Color,
+}
+
+pub const DEFAULT_THEME: &str = "catppuccin-mocha";
+
+// The built-in palettes, selectable by name
+pub const PRESETS: &[(&str, Theme)] = &[
+    (
+        "catppuccin-mocha",
+        Theme {
+            title_try: Color::Rgb(137, 180, 250),         // Blue
+            title_rs: Color::Rgb(243, 139, 168),          // Red
+            search_box: Color::Rgb(250, 179, 135),        // Peach
+            list_date: Color::Rgb(166, 173, 200),         // Subtext0
+            list_highlight_bg: Color::Rgb(88, 91, 112),   // Surface2
+            list_highlight_fg: Color::Rgb(205, 214, 244), // Text
+            help_text: Color::Rgb(147, 153, 178),         // Overlay2
+            status_message: Color::Rgb(249, 226, 175),    // Yellow
+            popup_bg: Color::Rgb(30, 30, 46),             // Base
+            popup_text: Color::Rgb(243, 139, 168),        // Red
+        },
+    ),
+    (
+        "catppuccin-macchiato",
+        Theme {
+            title_try: Color::Rgb(138, 173, 244),
+            title_rs: Color::Rgb(237, 135, 150),
+            search_box: Color::Rgb(245, 169, 127),
+            list_date: Color::Rgb(165, 173, 203),
+            list_highlight_bg: Color::Rgb(91, 96, 120),
+            list_highlight_fg: Color::Rgb(202, 211, 245),
+            help_text: Color::Rgb(147, 154, 183),
+            status_message: Color::Rgb(238, 212, 159),
+            popup_bg: Color::Rgb(36, 39, 58),
+            popup_text: Color::Rgb(237, 135, 150),
+        },
+    ),
+    (
+        "catppuccin-frappe",
+        Theme {
+            title_try: Color::Rgb(140, 170, 238),
+            title_rs: Color::Rgb(231, 130, 132),
+            search_box: Color::Rgb(239, 159, 118),
+            list_date: Color::Rgb(165, 173, 206),
+            list_highlight_bg: Color::Rgb(98, 104, 128),
+            list_highlight_fg: Color::Rgb(198, 208, 245),
+            help_text: Color::Rgb(148, 156, 187),
+            status_message: Color::Rgb(229, 200, 144),
+            popup_bg: Color::Rgb(48, 52, 70),
+            popup_text: Color::Rgb(231, 130, 132),
+        },
+    ),
+    (
+        "catppuccin-latte",
+        Theme {
+            title_try: Color::Rgb(30, 102, 245),
+            title_rs: Color::Rgb(210, 15, 57),
+            search_box: Color::Rgb(254, 100, 11),
+            list_date: Color::Rgb(108, 111, 133),
+            list_highlight_bg: Color::Rgb(172, 176, 190),
+            list_highlight_fg: Color::Rgb(76, 79, 105),
+            help_text: Color::Rgb(124, 127, 147),
+            status_message: Color::Rgb(223, 142, 29),
+            popup_bg: Color::Rgb(239, 241, 245),
+            popup_text: Color::Rgb(210, 15, 57),
+        },
+    ),
+    (
+        "gruvbox",
+        Theme {
+            title_try: Color::Rgb(131, 165, 152),
+            title_rs: Color::Rgb(251, 73, 52),
+            search_box: Color::Rgb(254, 128, 25),
+            list_date: Color::Rgb(168, 153, 132),
+            list_highlight_bg: Color::Rgb(80, 73, 69),
+            list_highlight_fg: Color::Rgb(235, 219, 178),
+            help_text: Color::Rgb(146, 131, 116),
+            status_message: Color::Rgb(250, 189, 47),
+            popup_bg: Color::Rgb(40, 40, 40),
+            popup_text: Color::Rgb(251, 73, 52),
+        },
+    ),
+    (
+        "gruvbox-light",
+        Theme {
+            title_try: Color::Rgb(7, 102, 120),
+            title_rs: Color::Rgb(157, 0, 6),
+            search_box: Color::Rgb(175, 58, 3),
+            list_date: Color::Rgb(124, 111, 100),
+            list_highlight_bg: Color::Rgb(213, 196, 161),
+            list_highlight_fg: Color::Rgb(60, 56, 54),
+            help_text: Color::Rgb(146, 131, 116),
+            status_message: Color::Rgb(181, 118, 20),
+            popup_bg: Color::Rgb(251, 241, 199),
+            popup_text: Color::Rgb(157, 0, 6),
+        },
+    ),
+    (
+        "nord",
+        Theme {
+            title_try: Color::Rgb(136, 192, 208),
+            title_rs: Color::Rgb(191, 97, 106),
+            search_box: Color::Rgb(208, 135, 112),
+            list_date: Color::Rgb(216, 222, 233),
+            list_highlight_bg: Color::Rgb(67, 76, 94),
+            list_highlight_fg: Color::Rgb(236, 239, 244),
+            help_text: Color::Rgb(129, 161, 193),
+            status_message: Color::Rgb(235, 203, 139),
+            popup_bg: Color::Rgb(46, 52, 64),
+            popup_text: Color::Rgb(191, 97, 106),
+        },
+    ),
+    (
+        "dracula",
+        Theme {
+            title_try: Color::Rgb(189, 147, 249),
+            title_rs: Color::Rgb(255, 121, 198),
+            search_box: Color::Rgb(255, 184, 108),
+            list_date: Color::Rgb(98, 114, 164),
+            list_highlight_bg: Color::Rgb(68, 71, 90),
+            list_highlight_fg: Color::Rgb(248, 248, 242),
+            help_text: Color::Rgb(98, 114, 164),
+            status_message: Color::Rgb(241, 250, 140),
+            popup_bg: Color::Rgb(40, 42, 54),
+            popup_text: Color::Rgb(255, 85, 85),
+        },
+    ),
+    (
+        "solarized-dark",
+        Theme {
+            title_try: Color::Rgb(38, 139, 210),
+            title_rs: Color::Rgb(220, 50, 47),
+            search_box: Color::Rgb(203, 75, 22),
+            list_date: Color::Rgb(131, 148, 150),
+            list_highlight_bg: Color::Rgb(7, 54, 66),
+            list_highlight_fg: Color::Rgb(147, 161, 161),
+            help_text: Color::Rgb(88, 110, 117),
+            status_message: Color::Rgb(181, 137, 0),
+            popup_bg: Color::Rgb(0, 43, 54),
+            popup_text: Color::Rgb(220, 50, 47),
+        },
+    ),
+    (
+        "solarized-light",
+        Theme {
+            title_try: Color::Rgb(38, 139, 210),
+            title_rs: Color::Rgb(220, 50, 47),
+            search_box: Color::Rgb(203, 75, 22),
+            list_date: Color::Rgb(101, 123, 131),
+            list_highlight_bg: Color::Rgb(238, 232, 213),
+            list_highlight_fg: Color::Rgb(88, 110, 117),
+            help_text: Color::Rgb(147, 161, 161),
+            status_message: Color::Rgb(181, 137, 0),
+            popup_bg: Color::Rgb(253, 246, 227),
+            popup_text: Color::Rgb(220, 50, 47),
+        },
+    ),
+    // The terminal's own colors; the selection is shown reversed
+    (
+        "no-color",
+        Theme {
+            title_try: Color::Reset,
+            title_rs: Color::Reset,
+            search_box: Color::Reset,
+            list_date: Color::Reset,
+            list_highlight_bg: Color::Reset,
+            list_highlight_fg: Color::Reset,
+            help_text: Color::Reset,
+            status_message: Color::Reset,
+            popup_bg: Color::Reset,
+            popup_text: Color::Reset,
+        },
+    ),
+];
+
+impl Theme {
+    // The preset called `name` (in any case), if there is one
+    pub fn from_preset(name: &str) -> Option<Self> {
+        find_preset(name).map(|(_, theme)| theme.clone())
+    }
+
+    // How the preset `name` is spelled in `PRESETS`
+    pub fn preset_name(name: &str) -> Option<&'static str> {
+        find_preset(name).map(|(preset, _)| *preset)
+    }
+
+    // Names of the presets, for messages listing them
+    pub fn preset_names() -> String {
+        let names: Vec<&str> = PRESETS.iter().map(|(name, _)| *name).collect();
+        names.join(", ")
+    }
+
+    // The theme in colors a terminal of `depth` can show
+    pub fn adapted(&self, depth: ColorDepth) -> Self {
+        let c = |color| depth.adapt(color);
+        Self {
+            title_try: c(self.title_try),
+            title_rs: c(self.title_rs),
+            search_box: c(self.search_box),
+            list_date: c(self.list_date),
+            list_highlight_bg: c(self.list_highlight_bg),
+            list_highlight_fg: c(self.list_highlight_fg),
+            help_text: c(self.help_text),
+            status_message: c(self.status_message),
+            popup_bg: c(self.popup_bg),
+            popup_text: c(self.popup_text),
+        }
+    }
+
+    // The selected row of a list. Without a highlight color it is reversed instead.
+    pub fn highlight_style(&self) -> Style {
+        if self.list_highlight_bg == Color::Reset {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+                .bg(self.list_highlight_bg)
+                .fg(self.list_highlight_fg)
+        }
+    }
+}
+
+fn find_preset(name: &str) -> Option<&'static (&'static str, Theme)> {
+    PRESETS
+        .iter()
+        .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::from_preset(DEFAULT_THEME).expect("the default theme is a preset")
+    }
+}
+
+// The colors a terminal can show. RGB colors (the presets, `[colors]` written as
+// "#rrggbb", the project markers) are brought down to the nearest one it has.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ColorDepth {
+    #[default]
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    NoColor, // NO_COLOR is set: the terminal's own colors only
+}
+
+impl ColorDepth {
+    // From NO_COLOR (https://no-color.org), COLORTERM and TERM. Terminals that
+    // support RGB without saying so in COLORTERM (common over ssh) get 256 colors;
+    // exporting COLORTERM=truecolor brings the full palette back.
+    pub fn detect() -> Self {
+        if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return ColorDepth::NoColor;
+        }
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" || cfg!(windows) {
+            return ColorDepth::TrueColor;
+        }
+        let term = std::env::var("TERM").unwrap_or_default();
+        if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else if term == "dumb" {
+            ColorDepth::NoColor
+        } else if term == "linux" || term == "ansi" || term.starts_with("vt") {
+            ColorDepth::Ansi16
+        } else {
+            ColorDepth::Ansi256
+        }
+    }
+
+    pub fn adapt(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorDepth::TrueColor, _) | (_, Color::Reset) => color,
+            (ColorDepth::NoColor, _) => Color::Reset,
+            (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(ansi256(r, g, b)),
+            (ColorDepth::Ansi256, _) => color,
+            (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => nearest_ansi16(r, g, b),
+            (ColorDepth::Ansi16, Color::Indexed(i)) if i >= 16 => {
+                let (r, g, b) = indexed_rgb(i);
+                nearest_ansi16(r, g, b)
+            }
+            (ColorDepth::Ansi16, _) => color,
+        }
+    }
+
+    // Brings every cell of a drawn frame down to this depth, so colors set anywhere
+    // in the UI (syntax highlighting, markers) follow it too
+    pub fn adapt_buffer(self, buffer: &mut Buffer) {
+        if self == ColorDepth::TrueColor {
+            return;
+        }
+        for cell in &mut buffer.content {
+            cell.fg = self.adapt(cell.fg);
+            cell.bg = self.adapt(cell.bg);
+        }
+    }
+}
+
+// The 16 ANSI colors with the RGB values of the xterm defaults, which the nearest
+// one is picked from
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// Levels of each channel in the 6x6x6 color cube of the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+fn nearest_ansi16(r: u8, g: u8, b: u8) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb, (r, g, b)))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+// Index of the closest color of the cube (16-231) or the grey ramp (232-255)
+fn ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| {
+        (0..6)
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - v as i32).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let grey_index = (average.saturating_sub(3) / 10).min(23) as u8;
+    let grey = 8 + grey_index * 10;
+    if distance((grey, grey, grey), (r, g, b)) < distance(cube, (r, g, b)) {
+        232 + grey_index
+    } else {
+        16 + (36 * ri + 6 * gi + bi) as u8
+    }
+}
+
+// RGB value of a color of the 256-color palette past the first 16
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    if index >= 232 {
+        let grey = 8 + (index - 232) * 10;
+        return (grey, grey, grey);
+    }
+    let i = (index - 16) as usize;
+    (
+        CUBE_LEVELS[i / 36],
+        CUBE_LEVELS[(i / 6) % 6],
+        CUBE_LEVELS[i % 6],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ansi256_maps_the_palette_onto_itself() {
+        for index in 16..=255 {
+            let (r, g, b) = indexed_rgb(index);
+            assert_eq!(ansi256(r, g, b), index, "{:?}", (r, g, b));
+        }
+    }
+
+    #[test]
+    fn ansi256_picks_greys_for_greys_and_the_cube_for_colors() {
+        assert_eq!(ansi256(0, 0, 0), 16);
+        assert_eq!(ansi256(255, 255, 255), 231);
+        assert_eq!(ansi256(250, 5, 5), 196);
+        assert_eq!(ansi256(128, 128, 128), 244);
+        assert_eq!(ansi256(30, 31, 29), 234);
+    }
+
+    #[test]
+    fn nearest_ansi16_picks_the_closest_xterm_color() {
+        for (color, (r, g, b)) in ANSI16 {
+            assert_eq!(nearest_ansi16(r, g, b), color);
+        }
+        assert_eq!(nearest_ansi16(250, 10, 10), Color::LightRed);
+        assert_eq!(nearest_ansi16(190, 20, 20), Color::Red);
+        assert_eq!(nearest_ansi16(120, 120, 130), Color::DarkGray);
+    }
+
+    #[test]
+    fn adapt_only_changes_what_the_terminal_lacks() {
+        let orange = Color::Rgb(255, 135, 0);
+        assert_eq!(ColorDepth::TrueColor.adapt(orange), orange);
+        assert_eq!(ColorDepth::Ansi256.adapt(orange), Color::Indexed(208));
+        assert_eq!(ColorDepth::Ansi256.adapt(Color::Cyan), Color::Cyan);
+        assert_eq!(
+            ColorDepth::Ansi16.adapt(Color::Indexed(196)),
+            Color::LightRed
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.adapt(Color::Indexed(1)),
+            Color::Indexed(1)
+        );
+        assert_eq!(ColorDepth::NoColor.adapt(Color::Cyan), Color::Reset);
+        assert_eq!(ColorDepth::NoColor.adapt(Color::Reset), Color::Reset);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::integrations;
+use anyhow::Result;
+use std::path::Path;
+
+// The `[integrations.tmux]` section of config.toml: what Ctrl+T opens
+#[derive(Clone, Default)]
+pub struct Tmux {
+    pub mode: TmuxMode,
+    pub session_prefix: String, // Put before the experiment name in session names
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum TmuxMode {
+    #[default]
+    Session, // One session per experiment, created or switched to
+    Window, // A new window in the current session (a session outside tmux)
+}
+
+impl TmuxMode {
+    pub const NAMES: &str = "session, window";
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "session" => Some(TmuxMode::Session),
+            "window" => Some(TmuxMode::Window),
+            _ => None,
+        }
+    }
+}
+
+pub fn inside() -> bool {
+    std::env::var_os("TMUX").is_some_and(|v| !v.is_empty())
+}
+
+// tmux turns `.` and `:` in session names into `_`; doing it here keeps
+// has-session and switch-client looking for the same name
+fn session_name(tmux: &Tmux, dir: &Path) -> String {
+    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+    format!("{}{}", tmux.session_prefix, name).replace(['.', ':'], "_")
+}
+
+// Opens `dir` in tmux. Inside tmux the client is switched right away and None
+// is returned; outside, attaching needs the terminal, so the command is handed
+// back for the shell wrapper to run.
+pub fn open(tmux: &Tmux, dir: &Path) -> Result<Option<Vec<String>>> {
+    let name = session_name(tmux, dir);
+    let dir_arg = dir.to_string_lossy().to_string();
+    if !inside() {
+        let args = ["tmux", "new-session", "-A", "-s", &name, "-c", &dir_arg];
+        return Ok(Some(args.iter().map(|s| s.to_string()).collect()));
+    }
+
+    match tmux.mode {
+        TmuxMode::Window => run(&["new-window", "-n", &name, "-c", &dir_arg])?,
+        TmuxMode::Session => {
+            // `=` makes tmux match the name exactly rather than as a prefix
+            let target = format!("={}", name);
+            if run(&["has-session", "-t", &target]).is_err() {
+                run(&["new-session", "-d", "-s", &name, "-c", &dir_arg])?;
+            }
+            run(&["switch-client", "-t", &target])?;
+        }
+    }
+    Ok(None)
+}
+
+fn run(args: &[&str]) -> Result<()> {
+    integrations::run("tmux", args)
+}
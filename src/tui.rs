@@ -1,69 +1,665 @@
 use anyhow::Result;
 use chrono::Local;
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, KeyCode, KeyEvent};
 use fuzzy_matcher::FuzzyMatcher;
 use fuzzy_matcher::skim::SkimMatcherV2;
 use ratatui::{prelude::*, widgets::*};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use std::{
-    fs,
+    cell::Cell,
+    collections::{HashMap, HashSet},
     io::{self},
-    path::PathBuf,
-    time::SystemTime,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
+use crate::archive::{self, Archive, ArchiveFormat};
+use crate::config::{Root, Settings, ThemeConfig, set_config_string};
+use crate::detect::{Detectors, ProjectKind};
+use crate::favorites;
+use crate::git::{self, CloneOptions, CloneProgress};
+use crate::git_state::{self, GitStatus};
+use crate::highlight::{Syntax, highlight};
+use crate::history::{self, VisitRecord};
+use crate::hooks::{Hook, Hooks};
+use crate::icons::{Icon, Icons};
+use crate::integrations::Integration;
+use crate::keys::{Action, KeyMap};
+use crate::meta::{self, Meta};
+use crate::ops;
+use crate::paths::Paths;
+use crate::preview::{
+    FilePreview, GIT_LOG_COMMITS, PreviewMode, RecentChanges, find_readme, format_age, git_log,
+    read_file_preview, recent_changes,
+};
+use crate::query::{self, Query};
+use crate::scan::{self, RawEntry, ScanSource, Scanner};
+use crate::theme::{ColorDepth, PRESETS, Theme};
+use crate::update::check_for_update;
+use crate::utils::{
+    DirSize, SIZE_LIMITS, date_prefixed, dir_size, dir_sizes_each, editor_url, expand_git_url,
+    extract_repo_name, format_size, git_init, is_git_url, normalize_name, normalize_selection,
+    open_url, preview_entries, project_editor, restore_from_trash, strip_date_prefix,
+};
+
+// The startup scan hands entries over in batches of this size, or sooner when
+// the disk is slow
+const SCAN_BATCH: usize = 256;
+const SCAN_BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+// Maximum number of directory entries read to build the preview
+const PREVIEW_MAX_SCAN: usize = 1000;
+
+// Experiments listed by the recent view (F8)
+const RECENT_VISITS: usize = 50;
+
+// How long the cursor has to rest before the preview worker reads anything, so
+// scrolling through the list doesn't read every entry passed on the way
+const PREVIEW_DEBOUNCE: Duration = Duration::from_millis(40);
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum AppMode {
     Normal,
     DeleteConfirm,
+    CloneConfirm,   // A typed URL waits for y/n before being cloned
+    TemplatePick,   // A new name waits for its template to be picked
+    ArchiveConfirm, // The selected entry waits for y/n before being archived
+    Archives,       // The archives of the tries dir are listed for restoring
+    EditNotes,      // The description and notes of the selected entry are being typed
+    Rename,         // A new name for the selected entry is being typed
+    Cloning,        // A clone runs in the background, its progress in a popup
+    Browse,         // Focus is on the preview pane's file list
+    Worktree,       // A branch for a worktree of the selected repository is being typed
+    Recent,         // The experiments entered most recently are listed for jumping back
+    ThemePick,      // The theme presets are listed, the one under the cursor previewed
+    MoveRoot,       // The other roots are listed for the selected entry to move to
+}
+
+// Ordering applied to the list when no search query is active
+#[derive(Clone, Copy, PartialEq, Debug, Default, clap::ValueEnum)]
+pub enum SortMode {
+    #[default]
+    Modified,
+    Oldest, // Least recently modified first, for cleanup sessions
+    Created,
+    Name,
+    Size, // Largest first; sizes are computed in the background
+}
+
+impl SortMode {
+    pub const NAMES: &str = "modified, oldest, created, name, size";
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "modified" => Some(SortMode::Modified),
+            "oldest" => Some(SortMode::Oldest),
+            "created" => Some(SortMode::Created),
+            "name" => Some(SortMode::Name),
+            "size" => Some(SortMode::Size),
+            _ => None,
+        }
+    }
+
+    // Cycles to the next mode (bound to F3 in the TUI)
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Modified => SortMode::Oldest,
+            SortMode::Oldest => SortMode::Created,
+            SortMode::Created => SortMode::Name,
+            SortMode::Name => SortMode::Size,
+            SortMode::Size => SortMode::Modified,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Modified => "Modified",
+            SortMode::Oldest => "Oldest",
+            SortMode::Created => "Created",
+            SortMode::Name => "Name",
+            SortMode::Size => "Size",
+        }
+    }
+
+    // Dates are newest first (except Oldest), names are alphabetical, sizes are largest first
+    pub fn arrow(self) -> &'static str {
+        match self {
+            SortMode::Modified | SortMode::Created | SortMode::Size => "↓",
+            SortMode::Oldest | SortMode::Name => "↑",
+        }
+    }
+
+    pub fn sort(self, entries: &mut [TryEntry]) {
+        match self {
+            SortMode::Modified => entries.sort_by_key(|e| std::cmp::Reverse(e.modified)),
+            SortMode::Oldest => entries.sort_by_key(|e| e.modified),
+            SortMode::Created => entries.sort_by_key(|e| std::cmp::Reverse(e.created)),
+            // The path breaks ties so names differing only by case keep a stable order
+            SortMode::Name => entries.sort_by(|a, b| {
+                a.name
+                    .to_lowercase()
+                    .cmp(&b.name.to_lowercase())
+                    .then_with(|| a.path.cmp(&b.path))
+            }),
+            // Entries whose size isn't known yet go last
+            SortMode::Size => entries.sort_by_key(|e| std::cmp::Reverse(e.size.map(|s| s.bytes))),
+        }
+        // Favorites stay on top, in the order of the mode (the sort is stable)
+        entries.sort_by_key(|e| !e.is_favorite);
+    }
 }
 
-// Data model (same as before)
+/// An experiment in the list, identified by its absolute path; `name` is only for
+/// display and matching. Built from a [`RawEntry`] with [`TryEntry::from_raw`].
 #[derive(Clone)]
 pub struct TryEntry {
+    pub path: PathBuf,
     pub name: String,
+    /// Name without trailing date/hash noise, for matching
+    pub normalized_name: String,
     pub modified: SystemTime,
     pub created: SystemTime,
     pub score: i64,
-    pub is_git: bool,
-    pub is_mise: bool,
-    pub is_cargo: bool,
-    pub is_maven: bool,
-    pub is_flutter: bool,
-    pub is_go: bool,
-    pub is_python: bool,
+    /// Characters of the name the search matched
+    pub match_indices: Vec<usize>,
+    /// Project kinds found by their marker files
+    pub kinds: Vec<Arc<ProjectKind>>,
+    /// Has a `.git`, even if a detector replaced the git kind
+    pub git: bool,
+    /// Known once computed for the size column or sort
+    pub size: Option<DirSize>,
+    pub is_favorite: bool,
+    /// Description and notes, searched along with the name
+    pub meta: Meta,
+    /// Known once checked for the git column
+    pub git_status: Option<GitStatus>,
 }
 
-#[derive(Clone)]
-pub struct Theme {
-    pub title_try: Color,
-    pub title_rs: Color,
-    pub search_box: Color,
-    pub list_date: Color,
-    pub list_highlight_bg: Color,
-    pub list_highlight_fg: Color,
-    pub help_text: Color,
-    pub status_message: Color,
-    pub popup_bg: Color,
-    pub popup_text: Color,
-}
-
-impl Default for Theme {
-    fn default() -> Self {
-        Self {
-            // Catppuccin Mocha Palette
-            title_try: Color::Rgb(137, 180, 250),         // Blue
-            title_rs: Color::Rgb(243, 139, 168),          // Red
-            search_box: Color::Rgb(250, 179, 135),        // Peach
-            list_date: Color::Rgb(166, 173, 200),         // Subtext0
-            list_highlight_bg: Color::Rgb(88, 91, 112),   // Surface2
-            list_highlight_fg: Color::Rgb(205, 214, 244), // Text
-            help_text: Color::Rgb(147, 153, 178),         // Overlay2
-            status_message: Color::Rgb(249, 226, 175),    // Yellow
-            popup_bg: Color::Rgb(30, 30, 46),             // Base
-            popup_text: Color::Rgb(243, 139, 168),        // Red
+impl TryEntry {
+    /// Interprets a scanned directory: display names and project kinds from its marker files
+    pub fn from_raw(raw: RawEntry) -> Self {
+        TryEntry {
+            normalized_name: normalize_nested(&raw.name),
+            name: raw.name,
+            modified: raw.modified,
+            created: raw.created,
+            score: 0,
+            match_indices: Vec::new(),
+            kinds: raw.kinds,
+            git: raw.git,
+            size: None,
+            is_favorite: false,
+            meta: raw.meta,
+            git_status: None,
+            path: raw.path,
+        }
+    }
+
+    /// Name of the tries root the entry lives in, used to disambiguate duplicates
+    pub fn root_label(&self) -> String {
+        self.path
+            .ancestors()
+            .nth(self.name.matches('/').count() + 1)
+            .and_then(Path::file_name)
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Points the entry at `path`, its renamed or restored directory: the subfolders
+    /// of a nested name are kept, the last component is the new directory name
+    pub fn relocate(&mut self, path: PathBuf) {
+        let leaf = path.file_name().unwrap_or_default().to_string_lossy();
+        self.name = match self.name.rfind('/') {
+            Some(i) => format!("{}{}", &self.name[..=i], leaf),
+            None => leaf.into_owned(),
+        };
+        self.normalized_name = normalize_nested(&self.name);
+        self.path = path;
+    }
+
+    /// The directory name, without the subfolders of a nested entry
+    pub fn leaf_name(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or(&self.name)
+    }
+
+    /// Value handed back to main; an absolute path resolves to itself when joined
+    pub fn selection(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+
+    /// Human readable project types detected in the entry
+    pub fn kind_labels(&self) -> Vec<&str> {
+        self.kinds.iter().map(|k| k.label.as_str()).collect()
+    }
+
+    /// Whether any of the detected kinds is `name` or one of its aliases
+    pub fn has_kind(&self, name: &str) -> bool {
+        self.kinds.iter().any(|k| k.is_named(name))
+    }
+
+    /// A repository of its own, or a worktree or submodule
+    pub fn is_git(&self) -> bool {
+        self.git
+    }
+}
+
+// Columns of the folder list, chosen and ordered with `columns = [...]` in config.toml
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Column {
+    Icon,
+    Created,
+    Name,
+    Markers,
+    Age,
+    Size,
+    Git, // Uncommitted changes and unpushed commits of git experiments
+}
+
+impl Column {
+    pub const DEFAULT: [Column; 6] = [
+        Column::Icon,
+        Column::Created,
+        Column::Name,
+        Column::Markers,
+        Column::Git,
+        Column::Age,
+    ];
+
+    pub const NAMES: &str = "icon, created, name, markers, git, age, size";
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "icon" => Some(Column::Icon),
+            "created" => Some(Column::Created),
+            "name" => Some(Column::Name),
+            "markers" => Some(Column::Markers),
+            "age" => Some(Column::Age),
+            "size" => Some(Column::Size),
+            "git" => Some(Column::Git),
+            _ => None,
+        }
+    }
+}
+
+// What Esc does in the main list, set with `esc_behavior` in config.toml
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum EscBehavior {
+    #[default]
+    ClearThenQuit, // Clear a non-empty query first, quit once it is empty
+    Quit,
+}
+
+impl EscBehavior {
+    pub const NAMES: &str = "clear-then-quit, quit";
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "clear-then-quit" => Some(EscBehavior::ClearThenQuit),
+            "quit" => Some(EscBehavior::Quit),
+            _ => None,
+        }
+    }
+}
+
+// How `list_table` draws the rows, from config.toml
+#[derive(Clone, Copy)]
+pub struct RowOptions<'a> {
+    pub dim_date: bool,     // Grey out a leading YYYY-MM-DD- (see `date_prefix`)
+    pub quick_select: bool, // Number the first nine rows (see `quick_select`)
+    pub indent: usize,      // Spaces between the highlight symbol and the row (see `indent`)
+    pub icons: &'a Icons,
+}
+
+// One entry of the folder list, before layout
+pub struct ListRow<'e> {
+    pub entry: &'e TryEntry,
+    pub index: usize,  // Position in the whole list, for the quick-select digit
+    pub label: String, // The name, with its root when it is ambiguous
+    pub marked: bool,
+}
+
+// Content of a fixed-width column; the name column is sized by `list_table`
+fn column_spans(
+    column: Column,
+    entry: &TryEntry,
+    marked: bool,
+    options: RowOptions,
+    theme: &Theme,
+) -> Vec<Span<'static>> {
+    match column {
+        Column::Icon => {
+            let icon = if marked {
+                Icon::Marked
+            } else if entry.is_favorite {
+                Icon::Favorite
+            } else {
+                Icon::Folder
+            };
+            vec![Span::raw(options.icons.get(icon).to_string())]
+        }
+        Column::Created => {
+            let created_dt: chrono::DateTime<Local> = entry.created.into();
+            vec![Span::styled(
+                created_dt.format("%Y-%m-%d").to_string(),
+                Style::default().fg(theme.list_date),
+            )]
+        }
+        Column::Markers => entry
+            .kinds
+            .iter()
+            .map(|kind| (kind.glyph(options.icons), kind.color))
+            .filter(|(glyph, _)| !glyph.is_empty())
+            .map(|(glyph, color)| Span::styled(format!("{} ", glyph), Style::default().fg(color)))
+            .collect(),
+        Column::Age => {
+            let secs = SystemTime::now()
+                .duration_since(entry.modified)
+                .unwrap_or(Duration::ZERO)
+                .as_secs();
+            let days = secs / 86400;
+            let hours = (secs % 86400) / 3600;
+            let minutes = (secs % 3600) / 60;
+            vec![Span::styled(
+                format!("({:02}d {:02}h {:02}m)", days, hours, minutes),
+                Style::default().fg(theme.list_date),
+            )]
+        }
+        // Fixed width, so rows stay aligned while sizes come in; "+" marks a
+        // walk that gave up early
+        Column::Size => {
+            let text = match entry.size {
+                Some(size) => format!(
+                    "{:>9}{}",
+                    format_size(size.bytes),
+                    if size.complete { " " } else { "+" }
+                ),
+                None => format!("{:>10}", "…"),
+            };
+            vec![Span::styled(text, Style::default().fg(theme.list_date))]
+        }
+        // Empty for clean repositories, so the column only draws the eye to work at risk
+        Column::Git => match entry.git_status {
+            Some(status) if status != GitStatus::default() => vec![Span::styled(
+                status.indicators(),
+                Style::default().fg(theme.status_message),
+            )],
+            _ => Vec::new(),
+        },
+        Column::Name => Vec::new(),
+    }
+}
+
+// Lays out the `window` of `rows` on screen as a table `width` columns wide, the
+// indent included. Fixed columns are as wide as their widest cell among all `rows`,
+// so they keep their width while scrolling, and are left out when empty in all of
+// them; the name column takes the rest, labels too long for it cut with "..." by
+// display width.
+pub fn list_table(
+    columns: &[Column],
+    rows: &[ListRow],
+    window: Range<usize>,
+    options: RowOptions,
+    width: u16,
+    theme: &Theme,
+) -> Table<'static> {
+    // `None` is the quick-select digit, in front of the configured columns
+    let mut shown: Vec<Option<Column>> = columns.iter().copied().map(Some).collect();
+    if options.quick_select {
+        shown.insert(0, None);
+    }
+    let cell = |column: Option<Column>, row: &ListRow| match column {
+        Some(column) => Line::from(column_spans(column, row.entry, row.marked, options, theme)),
+        // The first nine entries can be picked with Alt+1..9
+        None if row.index < 9 => Line::styled(
+            (row.index + 1).to_string(),
+            Style::default().fg(Color::DarkGray),
+        ),
+        None => Line::raw(" "),
+    };
+
+    let widths: Vec<u16> = shown
+        .iter()
+        .map(|&column| {
+            rows.iter()
+                .map(|row| cell(column, row).width() as u16)
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let rows = &rows[window];
+    let mut cells: Vec<Vec<Line<'static>>> = rows
+        .iter()
+        .map(|row| shown.iter().map(|&column| cell(column, row)).collect())
+        .collect();
+    let keep: Vec<usize> = (0..shown.len())
+        .filter(|&c| shown[c] == Some(Column::Name) || widths[c] > 0)
+        .collect();
+    let constraints: Vec<Constraint> = keep
+        .iter()
+        .map(|&c| match shown[c] {
+            Some(Column::Name) => Constraint::Fill(1),
+            _ => Constraint::Length(widths[c]),
+        })
+        .collect();
+
+    // The table splits its width the same way, less the indent
+    let areas = Layout::horizontal(&constraints).spacing(1).split(Rect::new(
+        0,
+        0,
+        width.saturating_sub(options.indent as u16),
+        1,
+    ));
+    if let Some(name) = keep.iter().position(|&c| shown[c] == Some(Column::Name)) {
+        let name_width = areas[name].width as usize;
+        for (row, cells) in rows.iter().zip(&mut cells) {
+            cells[keep[name]] = name_line(
+                &row.label,
+                &row.entry.match_indices,
+                name_width,
+                options.dim_date,
+                theme,
+            );
+        }
+    }
+
+    // The indent leads the first column, after the highlight symbol
+    let mut constraints = constraints;
+    if options.indent > 0 {
+        if let Some(Constraint::Length(first)) = constraints.first_mut() {
+            *first += options.indent as u16;
+        }
+        for cells in &mut cells {
+            if let Some(&first) = keep.first() {
+                cells[first]
+                    .spans
+                    .insert(0, Span::raw(" ".repeat(options.indent)));
+            }
+        }
+    }
+
+    let rows: Vec<Row> = cells
+        .into_iter()
+        .map(|mut cells| Row::new(keep.iter().map(|&c| std::mem::take(&mut cells[c]))))
+        .collect();
+    Table::new(rows, constraints).column_spacing(1)
+}
+
+// The name column of a row: `label` cut to `width`, with the characters the search
+// matched (by position, see `match_name`) highlighted and its date prefix greyed out
+fn name_line(
+    label: &str,
+    matched: &[usize],
+    width: usize,
+    dim_date: bool,
+    theme: &Theme,
+) -> Line<'static> {
+    let (shown, ellipsis) = if label.width() > width {
+        let room = width.saturating_sub(3);
+        let mut used = 0;
+        let shown: String = label
+            .chars()
+            .take_while(|c| {
+                used += c.width().unwrap_or(0);
+                used <= room
+            })
+            .collect();
+        (shown, &"..."[..width.min(3)])
+    } else {
+        (label.to_string(), "")
+    };
+    // The prefix is ASCII, so its length in bytes is its length in characters
+    let dimmed = if dim_date {
+        label.len() - strip_date_prefix(label).len()
+    } else {
+        0
+    };
+    let style_of = |i: usize| {
+        if matched.contains(&i) {
+            Style::default()
+                .fg(theme.title_try)
+                .add_modifier(Modifier::BOLD)
+        } else if i < dimmed {
+            Style::default().fg(theme.list_date)
+        } else {
+            Style::default()
+        }
+    };
+
+    // Runs of characters sharing a style make one span each
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_style = Style::default();
+    for (i, c) in shown.chars().enumerate() {
+        let style = style_of(i);
+        if style != run_style && !run.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut run), run_style));
+        }
+        run_style = style;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, run_style));
+    }
+    if !ellipsis.is_empty() {
+        spans.push(Span::raw(ellipsis));
+    }
+    Line::from(spans)
+}
+
+// Fuzzy score of `entry` for the search `text`, as the list ranks it: the name as
+// shown (see `normalize_names` / `date_prefix`), else the description and notes,
+// ranked below similar name matches
+pub fn match_score(
+    matcher: &SkimMatcherV2,
+    entry: &TryEntry,
+    text: &str,
+    normalize_names: bool,
+    date_prefix: bool,
+) -> Option<i64> {
+    match_name(matcher, entry, text, normalize_names, date_prefix).map(|(score, _)| score)
+}
+
+// `match_score` along with the positions of the matched characters in `entry.name`;
+// none when only the description or notes matched
+pub fn match_name(
+    matcher: &SkimMatcherV2,
+    entry: &TryEntry,
+    text: &str,
+    normalize_names: bool,
+    date_prefix: bool,
+) -> Option<(i64, Vec<usize>)> {
+    // The searched text can leave out a date prefix: `skipped` characters at `at`
+    let (haystack, at, skipped) = if normalize_names {
+        let leaf_start = entry.name.rfind('/').map_or(0, |i| i + 1);
+        let leaf = &entry.name[leaf_start..];
+        (
+            entry.normalized_name.as_str(),
+            entry.name[..leaf_start].chars().count(),
+            leaf.len() - strip_date_prefix(leaf).len(),
+        )
+    } else if date_prefix {
+        let stripped = strip_date_prefix(&entry.name);
+        (stripped, 0, entry.name.len() - stripped.len())
+    } else {
+        (entry.name.as_str(), 0, 0)
+    };
+    if let Some((score, indices)) = matcher.fuzzy_indices(haystack, text) {
+        let indices = indices
+            .into_iter()
+            .map(|i| if i >= at { i + skipped } else { i })
+            .collect();
+        return Some((score, indices));
+    }
+    [&entry.meta.description, &entry.meta.notes]
+        .into_iter()
+        .filter_map(|meta| matcher.fuzzy_match(meta, text))
+        .max()
+        .map(|score| (score / 2, Vec::new()))
+}
+
+// `normalize_name` of the last component of a nested name, keeping the subfolders
+fn normalize_nested(name: &str) -> String {
+    match name.rfind('/') {
+        Some(i) => format!("{}{}", &name[..=i], normalize_name(&name[i + 1..])),
+        None => normalize_name(name),
+    }
+}
+
+fn duplicate_names(entries: &[TryEntry]) -> HashSet<&str> {
+    let mut seen = HashSet::new();
+    let mut duplicates = HashSet::new();
+    for entry in entries {
+        if !seen.insert(entry.name.as_str()) {
+            duplicates.insert(entry.name.as_str());
         }
     }
+    duplicates
+}
+
+// Reads every experiment directory below `path`, looking `depth` levels deep
+// (see `scan_depth`)
+pub fn scan_entries(path: &Path, depth: usize, detectors: &Detectors) -> Vec<TryEntry> {
+    load_entries(
+        &Scanner::new(path)
+            .with_depth(depth)
+            .with_detectors(detectors),
+    )
+}
+
+// Builds the entries reported by `source`
+pub fn load_entries(source: &impl ScanSource) -> Vec<TryEntry> {
+    source.scan().into_iter().map(TryEntry::from_raw).collect()
+}
+
+// What the user chose in the TUI
+pub struct Outcome {
+    pub selection: Option<String>,
+    pub open_editor: bool,
+    pub scratch: bool,
+    pub diff: Option<(PathBuf, PathBuf)>, // Two marked experiments to compare
+    pub template: Option<String>,         // Template to fill a new experiment from
+    pub edit_file: Option<(PathBuf, PathBuf)>, // A file to open in the editor, and its experiment
+    pub spawn: Option<PathBuf>,           // An experiment to open with the terminal integration
+    pub cloned: Option<(String, String)>, // URL cloned in the TUI and the folder it went to
+    pub tries_dir: Option<PathBuf>,       // Root the TUI ended in, where new names go
+}
+
+// A clone running on a worker thread, shown by the progress popup
+pub struct CloneJob {
+    id: u64, // Worker results of other (cancelled) clones carry another id
+    url: String,
+    folder: String,
+    subpath: String, // Entered instead of the root when it exists
+    stage: String,
+    percent: Option<u8>,
+    cancel: Arc<AtomicBool>,
 }
 
 // Our TUI state
@@ -76,47 +672,352 @@ pub struct App {
     pub final_selection: Option<String>, // The final result (for the shell)
     pub mode: AppMode,
     pub status_message: Option<String>, // Feedback message for the user
-    pub base_path: PathBuf,             // Base directory for tries
+    pub status_since: Option<Instant>,  // When the status message was set
+    pub status_timeout: Option<Duration>, // How long status messages stay up (None: until typing)
     pub theme: Theme,                   // Application colors
-    pub editor_cmd: Option<String>,     // Editor command (e.g., "code", "nvim")
-    pub wants_editor: bool,             // Flag to indicate if we should open the editor
+    pub theme_name: String,             // Preset `theme` is built from
+    theme_colors: ThemeConfig,          // `[colors]`, kept over any preset picked with F9
+    pub theme_cursor: usize,
+    config_file: Option<PathBuf>, // Where a theme picked with F9 is saved
+    pub color_depth: ColorDepth,  // Every frame is brought down to it
+    pub icons: Icons,             // Glyphs of the list, preview and title
+    pub editor_cmd: Option<String>, // Editor command (e.g., "code", "nvim")
+    pub editor_url_template: Option<String>, // URL handed to the platform opener by Ctrl+O
+    pub wants_editor: bool,       // Flag to indicate if we should open the editor
+    pub wants_scratch: bool,      // Flag to create an auto-named scratch experiment
+    pub wants_diff: Option<(PathBuf, PathBuf)>, // Marked pair to open in the diff tool
+    pub wants_file: Option<(PathBuf, PathBuf)>, // Experiment and file to open in the editor
+    pub wants_spawn: Option<PathBuf>, // Experiment to open in tmux, Zellij or WezTerm
+    pub sort_mode: SortMode,      // Ordering used when the query is empty
+    pub normalize_names: bool,    // Match against names without date/hash suffixes
+    pub date_prefix: bool,        // Names start with YYYY-MM-DD-, ignored when matching
+    pub columns: Vec<Column>,     // Columns rendered in the folder list
+    pub esc_behavior: EscBehavior,
+    pub quick_select: bool, // Number the first nine entries for Alt+digit selection
+    pub pre_search_selection: Option<PathBuf>, // Entry selected before the query was typed
+    pub marked: HashSet<PathBuf>, // Entries marked for bulk actions
+    pub delete_pending: Vec<PathBuf>, // Targets captured when the delete popup opened
+    pub delete_at_risk: Vec<(PathBuf, GitStatus)>, // Targets with unsaved git work
+    pub delete_checking: HashSet<PathBuf>, // Targets whose git work is still being checked
+    pub delete_typed: String, // Confirmation typed when deleting unsaved git work
+    pub trashed: Vec<(TryEntry, PathBuf)>, // Last deleted batch and where it went, for Ctrl+Z
+    pub confirm_clone: bool, // Ask before cloning a typed URL
+    pub clone_host: Option<String>, // Expands bare `owner/repo` shorthands
+    pub clone_options: CloneOptions, // Depth, branch and filter of clones
+    pub git_init_commit: bool, // Ctrl+G also makes an initial commit
+    pub highlight_symbol: String, // Marker of the selected row
+    pub indent: usize,      // Spaces before every row
+    pub clone_pending: Option<String>, // URL shown in the clone popup
+    pub clone_job: Option<CloneJob>, // Clone in progress
+    pub clones_started: u64, // Numbers the clone jobs
+    pub cloned: Option<(String, String)>, // Finished clone, recorded in the history on exit
+    pub keymap: KeyMap,     // Chords of the list actions
+    pub templates: Vec<String>, // Offered when creating a new experiment
+    pub template_pending: Option<String>, // New name shown in the template popup
+    pub template_cursor: usize, // 0 is "empty", then one row per template
+    pub template: Option<String>, // Template picked for the new experiment
+    pub sizes: HashMap<PathBuf, DirSize>, // Sizes computed so far
+    pub sizing: HashSet<PathBuf>, // Sizes still being computed in the background
+    pub git_statuses: HashMap<PathBuf, Option<GitStatus>>, // Checked so far (None: git failed)
+    pub git_checking: HashSet<PathBuf>, // Git statuses still being checked
+    pub preview_mode: PreviewMode, // What the preview pane shows
+    pub recent: HashMap<PathBuf, RecentChanges>, // Recent-changes previews computed so far
+    pub recent_pending: HashSet<PathBuf>, // Recent-changes previews being computed
+    pub readmes: HashMap<PathBuf, Option<(PathBuf, FilePreview)>>, // READMEs loaded so far (None: there is none)
+    pub readme_pending: HashSet<PathBuf>,                          // READMEs being read
+    pub git_logs: HashMap<PathBuf, Vec<(SystemTime, String)>>,     // Last commits, for the summary
+    pub git_log_pending: HashSet<PathBuf>,                         // Logs still being read
+    pub preview_scroll: Option<(PathBuf, usize)>, // Lines scrolled past in the entry's preview
+    pub preview_view: Cell<(usize, usize)>, // Rows of the preview pane and lines it had, at the last draw
+    pub list_view: Cell<(usize, usize)>, // First row shown and rows of the list pane, at the last draw
+    pub browse_cursor: usize,            // File under the cursor while browsing the preview
+    pub browse_dir: PathBuf,             // Folder being browsed, relative to the selected entry
+    pub open_file: Option<(PathBuf, FilePreview)>, // File shown in the preview, until the cursor moves
+    pub pinned_preview: Option<(PathBuf, FilePreview)>, // File kept in the preview across selections
+    pub file_scroll: u16,                               // First line of the open file that is shown
+    pub tick_rate: Duration,                            // How often the screen refreshes while idle
+    pub tries_dir: PathBuf, // The root being shown; the archives view looks there too
+    pub roots: Vec<Root>,   // Switched between with F7
+    pub scan_depth: usize,  // Levels of subfolders searched for experiments
+    pub detectors: Detectors,
+    pub archive_format: ArchiveFormat,
+    pub terminal_integration: Integration,
+    pub archive_pending: Option<PathBuf>, // Entry shown in the archive popup
+    pub archives: Vec<Archive>,           // Listed by the archives view
+    pub archive_cursor: usize,
+    pub visits: Vec<VisitRecord>, // Listed by the recent view, newest first
+    pub visit_cursor: usize,
+    pub move_pending: Option<PathBuf>, // Entry shown in the move popup
+    pub move_cursor: usize,            // Among the roots other than the one shown
+    pub scanning: bool,                // The startup scan is still streaming entries in
+    pub favorites: HashSet<PathBuf>,   // Pinned to the top and kept by clean
+    paths: Paths,                      // Where the favorites are saved
+    hooks: Hooks,                      // on_delete runs before an experiment goes to the trash
+    pub notes_pending: Option<PathBuf>, // Entry whose notes are being edited
+    pub notes_draft: Meta,
+    pub notes_focus_notes: bool, // Typing goes to the notes rather than the description
+    pub rename_pending: Option<PathBuf>, // Entry being renamed
+    pub rename_input: String,    // Its new name, as typed so far
+    pub worktree_pending: Option<PathBuf>, // Repository a worktree is being made of
+    pub worktree_branch: String, // Its branch, as typed so far
+    events_tx: Sender<AppEvent>,
+    events_rx: Receiver<AppEvent>,
+    preview_tx: Sender<PreviewJob>, // The preview worker's queue
+}
+
+// What the preview of an entry reads from disk, done by the one preview worker
+enum PreviewJob {
+    Recent(PathBuf, bool), // Recent changes; true for a repository
+    Readme(PathBuf),
+    GitLog(PathBuf),
+}
+
+impl PreviewJob {
+    fn path(&self) -> &Path {
+        match self {
+            PreviewJob::Recent(path, _) | PreviewJob::Readme(path) | PreviewJob::GitLog(path) => {
+                path
+            }
+        }
+    }
+
+    fn run(self) -> WorkerResult {
+        match self {
+            PreviewJob::Recent(path, is_git) => {
+                let changes = recent_changes(&path, is_git);
+                WorkerResult::Recent(path, changes)
+            }
+            PreviewJob::Readme(path) => {
+                let readme = find_readme(&path).map(|file| {
+                    let content = read_file_preview(&file);
+                    (file, content)
+                });
+                WorkerResult::Readme(path, readme)
+            }
+            PreviewJob::GitLog(path) => {
+                let commits = git_log(&path, GIT_LOG_COMMITS);
+                WorkerResult::GitLog(path, commits)
+            }
+        }
+    }
+}
+
+// Runs preview jobs until the app goes away. Jobs are collected until the queue
+// stays quiet for PREVIEW_DEBOUNCE; only the ones for the last entry asked about
+// run, the others are reported back as dropped so they can be asked for again.
+fn preview_worker(jobs: Receiver<PreviewJob>, tx: Sender<AppEvent>) {
+    while let Ok(job) = jobs.recv() {
+        let mut batch = vec![job];
+        while let Ok(job) = jobs.recv_timeout(PREVIEW_DEBOUNCE) {
+            batch.push(job);
+        }
+        let latest = batch[batch.len() - 1].path().to_path_buf();
+        let mut dropped = HashSet::new();
+        for job in batch {
+            let result = if job.path() == latest {
+                job.run()
+            } else if dropped.insert(job.path().to_path_buf()) {
+                WorkerResult::PreviewDropped(job.path().to_path_buf())
+            } else {
+                continue;
+            };
+            if tx.send(AppEvent::Worker(result)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// Everything the main loop waits on, delivered through a single channel
+enum AppEvent {
+    Input(Event),
+    Worker(WorkerResult),
+    Tick,
+}
+
+// Results sent back by background workers
+enum WorkerResult {
+    Size(PathBuf, DirSize),
+    Recent(PathBuf, RecentChanges),
+    Readme(PathBuf, Option<(PathBuf, FilePreview)>),
+    GitLog(PathBuf, Vec<(SystemTime, String)>),
+    Update(String),
+    GitStatus(PathBuf, Option<GitStatus>),
+    GitInit(PathBuf, Result<(), String>),
+    Worktree(Result<PathBuf, String>), // The worktree experiment created
+    Archived(PathBuf, Result<PathBuf, String>), // Experiment, and the archive it went to
+    CloneProgress(u64, CloneProgress), // Clone job id, and the update
+    Cloned(u64, Result<String, String>), // Clone job id, and the folder it went to
+    Unarchived(Result<PathBuf, String>), // The experiment restored
+    Deleted(Vec<(PathBuf, Result<Option<PathBuf>, String>)>), // Each target, and its trash location
+    Moved(PathBuf, String, Result<PathBuf, String>), // Experiment, the root's name, and where it went
+    PreviewDropped(PathBuf), // Preview jobs skipped because the cursor moved on
+    Entries(PathBuf, Vec<TryEntry>, bool), // A batch of the startup scan; true once it is complete
 }
 
 impl App {
-    pub fn new(path: PathBuf, theme: Theme, editor_cmd: Option<String>) -> Self {
-        let mut entries = Vec::new();
-        if let Ok(read_dir) = fs::read_dir(&path) {
-            for entry in read_dir.flatten() {
-                if let Ok(metadata) = entry.metadata()
-                    && metadata.is_dir()
-                {
-                    let name = entry.file_name().to_string_lossy().to_string();
-                    let is_git = entry.path().join(".git").exists();
-                    let is_mise = entry.path().join("mise.toml").exists();
-                    let is_cargo = entry.path().join("Cargo.toml").exists();
-                    let is_maven = entry.path().join("pom.xml").exists();
-                    let is_flutter = entry.path().join("pubspec.yaml").exists();
-                    let is_go = entry.path().join("go.mod").exists();
-                    let is_python = entry.path().join("pyproject.toml").exists()
-                        || entry.path().join("requirements.txt").exists();
-                    entries.push(TryEntry {
-                        name,
-                        modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
-                        created: metadata.created().unwrap_or(SystemTime::UNIX_EPOCH),
-                        score: 0,
-                        is_git,
-                        is_mise,
-                        is_cargo,
-                        is_maven,
-                        is_flutter,
-                        is_go,
-                        is_python,
-                    });
-                }
-            }
-        }
-        // Initial sort: most recent first
-        entries.sort_by(|a, b| b.modified.cmp(&a.modified));
+    // Starts with an empty list and scans the tries dir on a worker thread, so the
+    // first frame never waits for the disk; entries stream in as they are found
+    pub fn new(settings: &Settings) -> Self {
+        let mut app = Self::with_entries(settings, Vec::new());
+        app.start_scan();
+        app
+    }
+
+    // Like `new`, but with the entries read from `source` up front instead of
+    // streamed in from the tries dir, e.g. a fixed list for an embedding tool
+    pub fn with_source(settings: &Settings, source: &impl ScanSource) -> Self {
+        let mut app = Self::with_entries(settings, Vec::new());
+        let mut entries = load_entries(source);
+        favorites::mark(&mut entries, &app.favorites);
+        app.all_entries = entries;
+        app.resort();
+        app
+    }
+
+    // Streams the entries of the tries dir in from a worker thread. Batches are
+    // tagged with their root, so a scan outrun by a root switch is dropped.
+    fn start_scan(&mut self) {
+        self.scanning = true;
+        let tx = self.events_tx.clone();
+        let root = self.tries_dir.clone();
+        let scan = Scanner::new(&root)
+            .with_depth(self.scan_depth)
+            .with_detectors(&self.detectors);
+        std::thread::spawn(move || {
+            let mut batch = Vec::new();
+            let mut last_sent = Instant::now();
+            scan.scan_each(|raw| {
+                batch.push(TryEntry::from_raw(raw));
+                if batch.len() >= SCAN_BATCH || last_sent.elapsed() >= SCAN_BATCH_INTERVAL {
+                    let batch = std::mem::take(&mut batch);
+                    let result = WorkerResult::Entries(root.clone(), batch, false);
+                    let _ = tx.send(AppEvent::Worker(result));
+                    last_sent = Instant::now();
+                }
+            });
+            let _ = tx.send(AppEvent::Worker(WorkerResult::Entries(root, batch, true)));
+        });
+    }
+
+    // Switches to the next of the [[roots]] and rescans
+    pub fn switch_root(&mut self) {
+        if self.roots.len() < 2 {
+            self.set_status("Only one root (add [[roots]] to config.toml)".to_string());
+            return;
+        }
+        let next = self
+            .roots
+            .iter()
+            .position(|r| r.path == self.tries_dir)
+            .map_or(0, |i| (i + 1) % self.roots.len());
+        let root = self.roots[next].clone();
+        self.tries_dir = root.path.clone();
+        self.all_entries.clear();
+        self.marked.clear();
+        self.selected_index = 0;
+        self.update_search();
+        self.start_scan();
+        self.set_status(format!("Root: {} ({})", root.name, root.path.display()));
+    }
+
+    // The roots the selected entry can be moved to: all but the one shown
+    fn move_destinations(&self) -> Vec<&Root> {
+        self.roots
+            .iter()
+            .filter(|r| r.path != self.tries_dir)
+            .collect()
+    }
+
+    // Opens the list of roots to move the selected entry to
+    pub fn begin_move_root(&mut self) {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else {
+            return;
+        };
+        if self.move_destinations().is_empty() {
+            self.set_status("Only one root (add [[roots]] to config.toml)".to_string());
+            return;
+        }
+        self.move_pending = Some(entry.path.clone());
+        self.move_cursor = 0;
+        self.mode = AppMode::MoveRoot;
+    }
+
+    // Moves the entry captured by `begin_move_root` to the root under the cursor on a
+    // worker thread: across filesystems it is copied, which takes a while
+    pub fn move_selected(&mut self) {
+        self.mode = AppMode::Normal;
+        let root = self
+            .move_destinations()
+            .get(self.move_cursor)
+            .map(|r| (*r).clone());
+        let Some(root) = root else {
+            return;
+        };
+        let Some(path) = self.move_pending.take() else {
+            return;
+        };
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        self.set_status(format!("Moving {} to {}…", name, root.name));
+        let tx = self.events_tx.clone();
+        std::thread::spawn(move || {
+            let result = ops::move_to_root(&path, &root.path).map_err(|e| format!("{:#}", e));
+            let _ = tx.send(AppEvent::Worker(WorkerResult::Moved(
+                path, root.name, result,
+            )));
+        });
+    }
+
+    // Popup text for the move view
+    fn move_summary(&self) -> String {
+        let name = self
+            .move_pending
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .unwrap_or_default()
+            .to_string_lossy();
+        let mut lines = vec![format!("Move '{}' to:", name)];
+        lines.extend(
+            self.move_destinations()
+                .iter()
+                .enumerate()
+                .map(|(i, root)| {
+                    let marker = if i == self.move_cursor { "→ " } else { "  " };
+                    format!("{}{}  ({})", marker, root.name, root.path.display())
+                }),
+        );
+        lines.push("(↑↓ to choose, Enter to move, Esc to cancel)".to_string());
+        lines.join("\n")
+    }
+
+    // Name of the root being shown, when [[roots]] are configured
+    fn root_name(&self) -> Option<&str> {
+        self.roots
+            .iter()
+            .find(|r| r.path == self.tries_dir)
+            .map(|r| r.name.as_str())
+    }
+
+    fn with_entries(settings: &Settings, mut entries: Vec<TryEntry>) -> Self {
+        // Initial sort: the configured mode, most recent first by default
+        let sort_mode = settings.sort_mode;
+        sort_mode.sort(&mut entries);
+        let (events_tx, events_rx) = mpsc::channel();
+        let (preview_tx, preview_rx) = mpsc::channel();
+        let tx = events_tx.clone();
+        std::thread::spawn(move || preview_worker(preview_rx, tx));
+
+        // Opt-in release check; it never delays startup and stays silent on failure
+        if settings.update_check {
+            let tx = events_tx.clone();
+            let paths = settings.paths.clone();
+            let url = settings.update_url.clone();
+            std::thread::spawn(move || {
+                if let Some(notice) = check_for_update(&paths, &url) {
+                    let _ = tx.send(AppEvent::Worker(WorkerResult::Update(notice)));
+                }
+            });
+        }
 
         Self {
             query: String::new(),
@@ -127,110 +1028,2136 @@ impl App {
             final_selection: None,
             mode: AppMode::Normal,
             status_message: None,
-            base_path: path,
-            theme,
-            editor_cmd,
+            status_since: None,
+            status_timeout: settings.status_timeout,
+            theme: settings.theme.clone(),
+            theme_name: settings.theme_name.clone(),
+            theme_colors: settings.colors.clone(),
+            theme_cursor: 0,
+            config_file: settings.config_file.clone(),
+            color_depth: settings.color_depth,
+            icons: settings.icons.clone(),
+            editor_cmd: settings.editor_cmd.clone(),
+            editor_url_template: settings.editor_url_template.clone(),
             wants_editor: false,
+            wants_scratch: false,
+            wants_diff: None,
+            wants_file: None,
+            wants_spawn: None,
+            sort_mode,
+            normalize_names: settings.normalize_names,
+            date_prefix: settings.date_prefix,
+            columns: settings.columns.clone(),
+            esc_behavior: settings.esc_behavior,
+            quick_select: settings.quick_select,
+            confirm_clone: settings.confirm_clone,
+            clone_host: settings.clone_host.clone(),
+            clone_options: settings.clone_options.clone(),
+            git_init_commit: settings.git_init_commit,
+            highlight_symbol: settings.highlight_symbol.clone(),
+            indent: settings.indent,
+            clone_pending: None,
+            clone_job: None,
+            clones_started: 0,
+            cloned: None,
+            keymap: settings.keymap.clone(),
+            templates: settings.templates.available(),
+            template_pending: None,
+            template_cursor: 0,
+            template: None,
+            pre_search_selection: None,
+            marked: HashSet::new(),
+            delete_pending: Vec::new(),
+            delete_at_risk: Vec::new(),
+            delete_checking: HashSet::new(),
+            delete_typed: String::new(),
+            trashed: Vec::new(),
+            sizes: HashMap::new(),
+            sizing: HashSet::new(),
+            git_statuses: HashMap::new(),
+            git_checking: HashSet::new(),
+            preview_mode: PreviewMode::default(),
+            recent: HashMap::new(),
+            recent_pending: HashSet::new(),
+            readmes: HashMap::new(),
+            readme_pending: HashSet::new(),
+            git_logs: HashMap::new(),
+            git_log_pending: HashSet::new(),
+            preview_scroll: None,
+            preview_view: Cell::new((0, 0)),
+            list_view: Cell::new((0, 0)),
+            browse_cursor: 0,
+            browse_dir: PathBuf::new(),
+            open_file: None,
+            pinned_preview: None,
+            file_scroll: 0,
+            tick_rate: settings.tick_rate,
+            tries_dir: settings.tries_dir.clone(),
+            roots: settings.roots.clone(),
+            scan_depth: settings.scan_depth,
+            detectors: settings.detectors.clone(),
+            archive_format: settings.archive_format,
+            terminal_integration: settings.terminal_integration.clone(),
+            archive_pending: None,
+            archives: Vec::new(),
+            archive_cursor: 0,
+            visits: Vec::new(),
+            visit_cursor: 0,
+            move_pending: None,
+            move_cursor: 0,
+            scanning: false,
+            favorites: favorites::load(&settings.paths),
+            paths: settings.paths.clone(),
+            hooks: settings.hooks.clone(),
+            notes_pending: None,
+            notes_draft: Meta::default(),
+            notes_focus_notes: false,
+            rename_pending: None,
+            rename_input: String::new(),
+            worktree_pending: None,
+            worktree_branch: String::new(),
+            events_tx,
+            events_rx,
+            preview_tx,
         }
     }
 
-    // Filter update logic
-    pub fn update_search(&mut self) {
-        let matcher = SkimMatcherV2::default();
+    // Starts computing the size of `path` on a worker thread, unless already known
+    pub fn request_size(&mut self, path: &Path) {
+        if self.sizes.contains_key(path) || !self.sizing.insert(path.to_path_buf()) {
+            return;
+        }
+        let tx = self.events_tx.clone();
+        let path = path.to_path_buf();
+        std::thread::spawn(move || {
+            let size = dir_size(&path, SIZE_LIMITS);
+            let _ = tx.send(AppEvent::Worker(WorkerResult::Size(path, size)));
+        });
+    }
 
-        if self.query.is_empty() {
-            self.filtered_entries = self.all_entries.clone();
+    // A repository whose status hasn't been checked (or asked for) yet
+    fn needs_git_status(&self, entry: &TryEntry) -> bool {
+        entry.is_git()
+            && !self.git_statuses.contains_key(&entry.path)
+            && !self.git_checking.contains(&entry.path)
+    }
+
+    // Checks the git status of `paths` one after the other on a worker thread
+    fn request_git_statuses(&mut self, paths: Vec<PathBuf>) {
+        if paths.is_empty() {
+            return;
+        }
+        self.git_checking.extend(paths.iter().cloned());
+        let tx = self.events_tx.clone();
+        std::thread::spawn(move || {
+            for path in paths {
+                let status = git_state::probe(&path);
+                let _ = tx.send(AppEvent::Worker(WorkerResult::GitStatus(path, status)));
+            }
+        });
+    }
+
+    // With the git column shown or a git: filter typed, every git experiment needs its status
+    pub fn request_list_git_statuses(&mut self) {
+        if !self.columns.contains(&Column::Git) && !Query::parse(&self.query).needs_git_status() {
+            return;
+        }
+        let paths = self
+            .all_entries
+            .iter()
+            .filter(|e| self.needs_git_status(e))
+            .map(|e| e.path.clone())
+            .collect();
+        self.request_git_statuses(paths);
+    }
+
+    // Queues the recent-changes preview of `entry` for the preview worker, unless cached
+    pub fn request_recent(&mut self, entry: &TryEntry) {
+        if self.recent.contains_key(&entry.path) || !self.recent_pending.insert(entry.path.clone())
+        {
+            return;
+        }
+        let job = PreviewJob::Recent(entry.path.clone(), entry.is_git());
+        let _ = self.preview_tx.send(job);
+    }
+
+    // Reads the entry's README on the preview worker, so big files never stall a frame
+    pub fn request_readme(&mut self, path: &Path) {
+        if self.readmes.contains_key(path) || !self.readme_pending.insert(path.to_path_buf()) {
+            return;
+        }
+        let _ = self.preview_tx.send(PreviewJob::Readme(path.to_path_buf()));
+    }
+
+    // Reads the last commits of a repository entry on the preview worker
+    pub fn request_git_log(&mut self, path: &Path) {
+        if self.git_logs.contains_key(path) || !self.git_log_pending.insert(path.to_path_buf()) {
+            return;
+        }
+        let _ = self.preview_tx.send(PreviewJob::GitLog(path.to_path_buf()));
+    }
+
+    // Lines of the selected entry's preview scrolled past
+    pub fn preview_offset(&self, path: &Path) -> usize {
+        match &self.preview_scroll {
+            Some((scrolled, offset)) if scrolled == path => *offset,
+            _ => 0,
+        }
+    }
+
+    // Rows of the list pane at the last draw, the size of a page
+    pub fn list_page(&self) -> usize {
+        self.list_view.get().1.max(1)
+    }
+
+    // Moves the selection `rows` up or down the list, stopping at either end
+    pub fn move_selection(&mut self, rows: usize, down: bool) {
+        let last = self.filtered_entries.len().saturating_sub(1);
+        self.selected_index = if down {
+            self.selected_index.saturating_add(rows).min(last)
         } else {
-            self.filtered_entries = self
-                .all_entries
-                .iter()
-                .filter_map(|entry| {
-                    matcher.fuzzy_match(&entry.name, &self.query).map(|score| {
-                        let mut e = entry.clone();
-                        e.score = score;
-                        e
-                    })
-                })
-                .collect();
+            self.selected_index.saturating_sub(rows)
+        };
+    }
+
+    // Scrolls the preview by a page (Shift+PageUp/PageDown)
+    pub fn scroll_preview(&mut self, down: bool) {
+        let Some(path) = self
+            .filtered_entries
+            .get(self.selected_index)
+            .map(|e| e.path.clone())
+        else {
+            return;
+        };
+        let (rows, lines) = self.preview_view.get();
+        let max = lines.saturating_sub(rows);
+        let current = self.preview_offset(&path).min(max);
+        let offset = if down {
+            (current + rows.max(1)).min(max)
+        } else {
+            current.saturating_sub(rows.max(1))
+        };
+        self.preview_scroll = Some((path, offset));
+    }
+
+    // Pages through the file open in the browser
+    pub fn scroll_file(&mut self, down: bool) {
+        let Some((_, content)) = &self.open_file else {
+            return;
+        };
+        let page = self.preview_view.get().0.max(1) as u16;
+        let last = content.lines().len().saturating_sub(1) as u16;
+        self.file_scroll = if down {
+            (self.file_scroll + page).min(last)
+        } else {
+            self.file_scroll.saturating_sub(page)
+        };
+    }
+
+    // Kicks off whatever background data the current preview mode needs
+    pub fn prepare_preview(&mut self) {
+        let Some(selected) = self.filtered_entries.get(self.selected_index).cloned() else {
+            return;
+        };
+        // A new selection starts at the top of its preview
+        if self
+            .preview_scroll
+            .as_ref()
+            .is_some_and(|(path, _)| *path != selected.path)
+        {
+            self.preview_scroll = None;
+        }
+        match self.preview_mode {
+            PreviewMode::Summary => {
+                self.request_size(&selected.path);
+                if self.needs_git_status(&selected) {
+                    self.request_git_statuses(vec![selected.path.clone()]);
+                }
+                if selected.is_git() {
+                    self.request_git_log(&selected.path);
+                }
+            }
+            PreviewMode::Readme => self.request_readme(&selected.path),
+            PreviewMode::Files => {}
+            PreviewMode::Recent => self.request_recent(&selected),
+        }
+    }
+
+    // With the size column shown or the list sorted by size, every entry needs its
+    // size: the missing ones are computed in one batch, one walker per CPU
+    pub fn request_list_sizes(&mut self) {
+        if self.sort_mode != SortMode::Size && !self.columns.contains(&Column::Size) {
+            return;
+        }
+        let paths: Vec<PathBuf> = self
+            .all_entries
+            .iter()
+            .filter(|e| !self.sizes.contains_key(&e.path) && !self.sizing.contains(&e.path))
+            .map(|e| e.path.clone())
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+        self.sizing.extend(paths.iter().cloned());
+        let tx = self.events_tx.clone();
+        std::thread::spawn(move || {
+            dir_sizes_each(&paths, SIZE_LIMITS, |i, size| {
+                let _ = tx.send(AppEvent::Worker(WorkerResult::Size(paths[i].clone(), size)));
+            });
+        });
+    }
 
-            // Sort by fuzzy score
-            self.filtered_entries.sort_by(|a, b| b.score.cmp(&a.score));
+    // Re-sorts the full list after entries or sizes changed. A cursor the user moved
+    // stays on its entry; otherwise the top (best) entry stays selected.
+    fn resort(&mut self) {
+        let selected = self
+            .filtered_entries
+            .get(self.selected_index)
+            .filter(|_| self.selected_index > 0)
+            .map(|e| e.path.clone());
+        let pre_search_selection = self.pre_search_selection.clone();
+        self.sort_mode.sort(&mut self.all_entries);
+        self.update_search();
+        self.pre_search_selection = pre_search_selection;
+        if let Some(index) =
+            selected.and_then(|path| self.filtered_entries.iter().position(|e| e.path == path))
+        {
+            self.selected_index = index;
         }
-        self.selected_index = 0; // Resets the selection to the top
     }
 
-    // Function to delete the selected item
-    pub fn delete_selected(&mut self) {
-        if let Some(entry_name) = self
-            .filtered_entries
-            .get(self.selected_index)
-            .map(|e| e.name.clone())
-        {
-            let path_to_remove = self.base_path.join(&entry_name);
+    // Stores a result finished by a worker
+    fn apply_result(&mut self, result: WorkerResult) {
+        match result {
+            WorkerResult::Size(path, size) => {
+                self.sizing.remove(&path);
+                for entry in self
+                    .all_entries
+                    .iter_mut()
+                    .chain(&mut self.filtered_entries)
+                    .filter(|e| e.path == path)
+                {
+                    entry.size = Some(size);
+                }
+                self.sizes.insert(path, size);
+                if self.sort_mode == SortMode::Size && self.query.is_empty() {
+                    self.resort();
+                }
+            }
+            WorkerResult::GitStatus(path, status) => {
+                self.git_checking.remove(&path);
+                if self.delete_checking.remove(&path) {
+                    self.check_git_work(&path, status);
+                }
+                for entry in self
+                    .all_entries
+                    .iter_mut()
+                    .chain(&mut self.filtered_entries)
+                    .filter(|e| e.path == path)
+                {
+                    entry.git_status = status;
+                }
+                self.git_statuses.insert(path, status);
+                // A git: filter only sees a repository once its status is known
+                if Query::parse(&self.query).needs_git_status() {
+                    self.refilter();
+                }
+            }
+            WorkerResult::Recent(path, changes) => {
+                self.recent_pending.remove(&path);
+                self.recent.insert(path, changes);
+            }
+            WorkerResult::Readme(path, readme) => {
+                self.readme_pending.remove(&path);
+                self.readmes.insert(path, readme);
+            }
+            WorkerResult::GitLog(path, commits) => {
+                self.git_log_pending.remove(&path);
+                self.git_logs.insert(path, commits);
+            }
+            WorkerResult::PreviewDropped(path) => {
+                self.recent_pending.remove(&path);
+                self.readme_pending.remove(&path);
+                self.git_log_pending.remove(&path);
+            }
+            WorkerResult::GitInit(path, result) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                // The repository can exist even when a later step (the first commit) failed
+                let is_git = path.join(".git").exists();
+                for entry in self
+                    .all_entries
+                    .iter_mut()
+                    .chain(&mut self.filtered_entries)
+                {
+                    if entry.path == path {
+                        entry.kinds = self.detectors.detect(&path);
+                        entry.git = is_git;
+                    }
+                }
+                if is_git {
+                    // The recent-changes preview and the status now come from git
+                    self.recent.remove(&path);
+                    self.git_statuses.remove(&path);
+                }
+                match result {
+                    Ok(()) => self.set_status(format!("Initialized a git repository in {}", name)),
+                    Err(e) => self.set_status(format!("git init in {} failed: {}", name, e)),
+                }
+            }
+            WorkerResult::Entries(root, mut entries, done) => {
+                if root != self.tries_dir {
+                    return;
+                }
+                self.scanning = !done;
+                if entries.is_empty() {
+                    return;
+                }
+                favorites::mark(&mut entries, &self.favorites);
+                self.all_entries.extend(entries);
+                self.resort();
+            }
+            WorkerResult::Archived(path, result) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                match result {
+                    Ok(written) => {
+                        self.all_entries.retain(|e| e.path != path);
+                        self.marked.remove(&path);
+                        self.update_search();
+                        let file = written.file_name().unwrap_or_default().to_string_lossy();
+                        self.set_status(format!("Archived {} to {} (F6 to restore)", name, file));
+                    }
+                    Err(e) => self.set_status(format!("Archiving {} failed: {}", name, e)),
+                }
+            }
+            WorkerResult::Deleted(results) => self.finish_delete(results),
+            WorkerResult::Moved(path, root, result) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                match result {
+                    Ok(moved) => {
+                        self.all_entries.retain(|e| e.path != path);
+                        self.marked.remove(&path);
+                        self.update_search();
+                        if self.favorites.remove(&path) {
+                            self.favorites.insert(moved);
+                            if let Err(e) = favorites::save(&self.paths, &self.favorites) {
+                                self.set_status(format!("Could not save the favorites: {}", e));
+                                return;
+                            }
+                        }
+                        self.set_status(format!("Moved {} to {}", name, root));
+                    }
+                    Err(e) => self.set_status(format!("Moving {} failed: {}", name, e)),
+                }
+            }
+            WorkerResult::Worktree(result) => match result {
+                Ok(path) => {
+                    self.final_selection = Some(path.to_string_lossy().into_owned());
+                    self.should_quit = true;
+                }
+                Err(e) => self.set_status(format!("Creating the worktree failed: {}", e)),
+            },
+            WorkerResult::CloneProgress(id, update) => {
+                if let Some(job) = self.clone_job.as_mut().filter(|job| job.id == id) {
+                    job.stage = update.stage;
+                    job.percent = update.percent;
+                }
+            }
+            WorkerResult::Cloned(id, result) => {
+                // A cancelled clone already closed its popup, and may finish while
+                // the next one runs
+                let Some(job) = self.clone_job.take_if(|job| job.id == id) else {
+                    return;
+                };
+                self.mode = AppMode::Normal;
+                match result {
+                    Ok(folder) => {
+                        let mut path = self.tries_dir.join(&folder);
+                        let subdir = path.join(&job.subpath);
+                        if !job.subpath.is_empty() && subdir.is_dir() {
+                            path = subdir;
+                        } else if !job.subpath.is_empty() {
+                            log::warn!(
+                                "'{}' does not exist in {}, entering the repository root",
+                                job.subpath,
+                                folder
+                            );
+                        }
+                        self.cloned = Some((job.url, folder));
+                        self.final_selection = Some(path.to_string_lossy().into_owned());
+                        self.should_quit = true;
+                    }
+                    Err(e) => {
+                        self.wants_editor = false;
+                        self.set_status(format!("Cloning {} failed: {}", job.url, e));
+                    }
+                }
+            }
+            WorkerResult::Unarchived(result) => match result {
+                Ok(path) => {
+                    if let Some(raw) = scan::probe(path.clone(), &self.detectors) {
+                        let mut entry = TryEntry::from_raw(raw);
+                        favorites::mark(std::slice::from_mut(&mut entry), &self.favorites);
+                        self.all_entries.push(entry);
+                        self.sort_mode.sort(&mut self.all_entries);
+                        self.update_search();
+                    }
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    self.set_status(format!("Restored {}", name));
+                }
+                Err(e) => self.set_status(format!("Restoring failed: {}", e)),
+            },
+            WorkerResult::Update(notice) => {
+                // Shown like a status message, without replacing one already there
+                if self.status_message.is_none() {
+                    self.set_status(notice);
+                }
+            }
+        }
+    }
+
+    // Formats the total size of `paths`, noting sizes that are still pending
+    pub fn size_summary<'a>(&self, paths: impl IntoIterator<Item = &'a PathBuf>) -> String {
+        let mut total = 0;
+        let mut pending = false;
+        let mut partial = false;
+        for path in paths {
+            match self.sizes.get(path) {
+                Some(size) => {
+                    total += size.bytes;
+                    partial |= !size.complete;
+                }
+                None => pending = true,
+            }
+        }
+        let mut summary = format_size(total);
+        if partial {
+            summary.push('+');
+        }
+        if pending {
+            summary.push_str(" (calculating...)");
+        }
+        summary
+    }
+
+    // Marks or unmarks the selected entry for bulk actions
+    pub fn toggle_mark(&mut self) {
+        if let Some(path) = self
+            .filtered_entries
+            .get(self.selected_index)
+            .map(|e| e.path.clone())
+            && !self.marked.remove(&path)
+        {
+            self.request_size(&path);
+            self.marked.insert(path);
+        }
+    }
+
+    // Quits to compare the two marked entries, in list order
+    pub fn begin_diff(&mut self) {
+        if self.marked.len() != 2 {
+            self.set_status(format!(
+                "Mark exactly two experiments to compare ({} marked)",
+                self.marked.len()
+            ));
+            return;
+        }
+        let mut pair = self
+            .all_entries
+            .iter()
+            .filter(|e| self.marked.contains(&e.path))
+            .map(|e| e.path.clone());
+        if let (Some(left), Some(right)) = (pair.next(), pair.next()) {
+            self.wants_diff = Some((left, right));
+            self.should_quit = true;
+        }
+    }
+
+    // Entries a delete would act on: every marked entry, or else the selected one
+    pub fn delete_targets(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            self.filtered_entries
+                .get(self.selected_index)
+                .map(|e| vec![e.path.clone()])
+                .unwrap_or_default()
+        } else {
+            self.marked.iter().cloned().collect()
+        }
+    }
+
+    // Picks the entry at `index` of the list right away (Alt+1..9)
+    pub fn quick_pick(&mut self, index: usize) {
+        match self.filtered_entries.get(index) {
+            Some(entry) => {
+                self.selected_index = index;
+                self.final_selection = Some(entry.selection());
+                self.should_quit = true;
+            }
+            None => self.set_status(format!(
+                "No entry {} (the list has {})",
+                index + 1,
+                self.filtered_entries.len()
+            )),
+        }
+    }
+
+    // The query as a name for a new experiment, if it isn't blank
+    pub fn query_selection(&self) -> Option<String> {
+        // A query with filters (`lang:rust`) is a search, never a name
+        let name = normalize_selection(&self.query);
+        (!name.is_empty() && !Query::parse(&self.query).has_filters()).then_some(name)
+    }
+
+    // Quits with the typed name, or clones the typed URL. With `confirm_clone`, a URL
+    // first opens the clone popup and is only cloned once confirmed; with templates
+    // available, a new name first opens the template popup.
+    pub fn select_query(&mut self, name: String, open_editor: bool) {
+        self.wants_editor = open_editor;
+        let is_url = is_git_url(&name, &self.tries_dir, self.clone_host.as_deref());
+        if self.confirm_clone && is_url {
+            self.clone_pending = Some(name);
+            self.mode = AppMode::CloneConfirm;
+        } else if is_url {
+            self.start_clone(&name);
+        } else if !self.templates.is_empty() {
+            self.template_pending = Some(name);
+            self.template_cursor = 0;
+            self.mode = AppMode::TemplatePick;
+        } else {
+            self.final_selection = Some(name);
+            self.should_quit = true;
+        }
+    }
+
+    // Popup text for the template picker: the new name, then "empty" and the templates
+    fn template_summary(&self) -> String {
+        let name = self.template_pending.as_deref().unwrap_or_default();
+        let mut lines = vec![format!("Create '{}' from:", name)];
+        let options = std::iter::once("(empty)").chain(self.templates.iter().map(String::as_str));
+        for (i, option) in options.enumerate() {
+            let marker = if i == self.template_cursor {
+                "→ "
+            } else {
+                "  "
+            };
+            lines.push(format!("{}{}", marker, option));
+        }
+        lines.push("(↑↓ to choose, Enter to create, Esc to cancel)".to_string());
+        lines.join("\n")
+    }
+
+    // The URL to clone for a typed `url[#subpath]`, the folder it lands in and the subpath
+    fn clone_target(&self, selection: &str) -> (String, String, String) {
+        let sparse = self.clone_options.sparse.first().map_or("", String::as_str);
+        let (url, subpath) = match selection.split_once('#') {
+            Some((url, subpath)) => (url, subpath.trim_matches('/')),
+            None => (selection, sparse.trim_matches('/')),
+        };
+        let url = expand_git_url(url, self.clone_host.as_deref());
+        let mut folder = extract_repo_name(&url);
+        if self.date_prefix {
+            folder = date_prefixed(&folder);
+        }
+        (url, folder, subpath.to_string())
+    }
+
+    // Popup text for the pending clone: the URL, the folder it lands in and the depth
+    fn clone_summary(&self) -> String {
+        let Some(selection) = &self.clone_pending else {
+            return String::new();
+        };
+        let (url, mut folder, subpath) = self.clone_target(selection);
+        if !subpath.is_empty() {
+            folder = format!("{}/{}", folder, subpath);
+        }
+        let depth = self.clone_options.describe();
+        format!("{}\ninto {} · {}\nClone? (y/n)", url, folder, depth)
+    }
+
+    // Clones the typed `url[#subpath]` on a worker thread, showing its progress.
+    // Once done the TUI quits into the clone.
+    pub fn start_clone(&mut self, selection: &str) {
+        let (url, folder, subpath) = self.clone_target(selection);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let tx = self.events_tx.clone();
+        let (tries_dir, options) = (self.tries_dir.clone(), self.clone_options.clone());
+        let (worker_url, worker_folder, worker_cancel) =
+            (url.clone(), folder.clone(), Arc::clone(&cancel));
+        self.clones_started += 1;
+        let id = self.clones_started;
+        std::thread::spawn(move || {
+            let result = ops::clone_with_progress(
+                &worker_url,
+                &tries_dir,
+                &worker_folder,
+                &options,
+                &worker_cancel,
+                |update| {
+                    let _ = tx.send(AppEvent::Worker(WorkerResult::CloneProgress(id, update)));
+                },
+            )
+            .map_err(|e| format!("{:#}", e));
+            let _ = tx.send(AppEvent::Worker(WorkerResult::Cloned(id, result)));
+        });
+        self.clone_job = Some(CloneJob {
+            id,
+            url,
+            folder,
+            subpath,
+            stage: "Connecting".to_string(),
+            percent: None,
+            cancel,
+        });
+        self.mode = AppMode::Cloning;
+    }
+
+    // Stops the running clone; the worker removes what was fetched so far
+    pub fn cancel_clone(&mut self) {
+        if let Some(job) = self.clone_job.take() {
+            job.cancel.store(true, Ordering::Relaxed);
+            self.set_status(format!("Cancelled cloning {}", job.url));
+        }
+        self.wants_editor = false;
+        self.mode = AppMode::Normal;
+    }
+
+    // Popup text for the running clone: the URL, the folder, and a progress bar
+    fn clone_progress_summary(&self, width: usize) -> String {
+        let Some(job) = &self.clone_job else {
+            return String::new();
+        };
+        let progress = match job.percent {
+            Some(percent) => {
+                let width = width.saturating_sub(8).clamp(10, 40);
+                let filled = width * percent.min(100) as usize / 100;
+                format!(
+                    "{}{} {:>3}%",
+                    "█".repeat(filled),
+                    "░".repeat(width - filled),
+                    percent
+                )
+            }
+            None => "…".to_string(),
+        };
+        format!(
+            "{}\ninto {}\n\n{}\n{}\n\n(Esc to cancel)",
+            job.url, job.folder, job.stage, progress
+        )
+    }
+
+    // Switches to the next sort mode and re-sorts the entries
+    pub fn cycle_sort(&mut self) {
+        self.sort_mode = self.sort_mode.next();
+        self.sort_mode.sort(&mut self.all_entries);
+        self.update_search();
+    }
+
+    // Adds a typed character to the query, remembering the selection it started from
+    pub fn push_query(&mut self, c: char) {
+        if self.query.is_empty() {
+            self.pre_search_selection = self
+                .filtered_entries
+                .get(self.selected_index)
+                .map(|e| e.path.clone());
+        }
+        self.query.push(c);
+        self.update_search();
+    }
+
+    // Filter update logic
+    pub fn update_search(&mut self) {
+        let matcher = SkimMatcherV2::default();
+        let mut restored = None;
+        let query = Query::parse(&self.query);
+        let now = SystemTime::now();
+
+        if self.query.is_empty() {
+            // The full list always follows the active sort mode, whatever order
+            // all_entries was left in
+            self.filtered_entries = self.all_entries.clone();
+            self.sort_mode.sort(&mut self.filtered_entries);
+            // Back to the full list: return to where the search started, if it still exists
+            restored = self
+                .pre_search_selection
+                .take()
+                .and_then(|path| self.filtered_entries.iter().position(|e| e.path == path));
+        } else if query.text.is_empty() {
+            // Only filters: the matches keep the sort mode order
+            self.filtered_entries = self
+                .all_entries
+                .iter()
+                .filter(|e| query.matches(e, now))
+                .cloned()
+                .collect();
+            self.sort_mode.sort(&mut self.filtered_entries);
+        } else {
+            self.filtered_entries = self
+                .all_entries
+                .iter()
+                .filter(|entry| query.matches(entry, now))
+                .filter_map(|entry| {
+                    let found = match_name(
+                        &matcher,
+                        entry,
+                        &query.text,
+                        self.normalize_names,
+                        self.date_prefix,
+                    );
+                    found.map(|(score, indices)| {
+                        let mut e = entry.clone();
+                        e.score = score;
+                        e.match_indices = indices;
+                        e
+                    })
+                })
+                .collect();
+
+            // Sort by fuzzy score (stable, so ties keep the sort mode order), favorites first
+            self.filtered_entries
+                .sort_by_key(|e| (!e.is_favorite, std::cmp::Reverse(e.score)));
+        }
+        self.selected_index = restored.unwrap_or(0); // Resets the selection to the top
+    }
+
+    // The directory being browsed: the selected entry, or a folder inside it
+    pub fn browse_path(&self) -> Option<PathBuf> {
+        self.filtered_entries
+            .get(self.selected_index)
+            .map(|e| e.path.join(&self.browse_dir))
+    }
+
+    // Files of the browsed directory, in the order the preview lists them
+    pub fn browse_files(&self) -> Vec<(String, bool)> {
+        self.browse_path()
+            .map(|dir| preview_entries(&dir, PREVIEW_MAX_SCAN, PREVIEW_MAX_SCAN))
+            .unwrap_or_default()
+    }
+
+    // Moves the focus to the preview's file list
+    pub fn begin_browse(&mut self) {
+        if self.filtered_entries.get(self.selected_index).is_some() {
+            self.preview_mode = PreviewMode::Files;
+            self.browse_cursor = 0;
+            self.browse_dir = PathBuf::new();
+            self.open_file = None;
+            self.mode = AppMode::Browse;
+        }
+    }
+
+    // Returns the focus to the main list
+    pub fn end_browse(&mut self) {
+        self.open_file = None;
+        self.browse_dir = PathBuf::new();
+        self.mode = AppMode::Normal;
+    }
+
+    // Enters the folder under the cursor, or shows the file (Enter, →)
+    pub fn browse_into(&mut self) {
+        let Some(dir) = self.browse_path() else {
+            return;
+        };
+        match self.browse_files().get(self.browse_cursor) {
+            Some((name, true)) => {
+                self.browse_dir.push(name);
+                self.browse_cursor = 0;
+            }
+            Some((name, false)) if self.open_file.is_none() => {
+                let path = dir.join(name);
+                let content = read_file_preview(&path);
+                self.open_file = Some((path, content));
+                self.file_scroll = 0;
+            }
+            _ => {}
+        }
+    }
+
+    // Closes the open file, or goes up a folder with the cursor on the one left (←)
+    pub fn browse_back(&mut self) {
+        if self.open_file.take().is_some() {
+            return;
+        }
+        let Some(left) = self
+            .browse_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+        else {
+            return;
+        };
+        self.browse_dir.pop();
+        self.browse_cursor = self
+            .browse_files()
+            .iter()
+            .position(|(name, _)| *name == left)
+            .unwrap_or(0);
+    }
+
+    // Moves the browser cursor by a page, or pages through the open file
+    pub fn browse_page(&mut self, down: bool) {
+        if self.open_file.is_some() {
+            self.scroll_file(down);
+            return;
+        }
+        let page = self.preview_view.get().0.max(1);
+        let last = self.browse_files().len().saturating_sub(1);
+        self.browse_cursor = if down {
+            (self.browse_cursor + page).min(last)
+        } else {
+            self.browse_cursor.saturating_sub(page)
+        };
+    }
+
+    // Quits to open the file under the cursor (or the open one) in the editor
+    pub fn edit_browsed_file(&mut self) {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else {
+            return;
+        };
+        let file = match (&self.open_file, self.browse_files().get(self.browse_cursor)) {
+            (Some((path, _)), _) => path.clone(),
+            (None, Some((name, false))) => entry.path.join(&self.browse_dir).join(name),
+            _ => return,
+        };
+        if self.editor_cmd.is_none() && project_editor(&entry.path).is_none() {
+            self.set_status("No editor configured in config.toml".to_string());
+            return;
+        }
+        self.wants_file = Some((entry.path.clone(), file));
+        self.should_quit = true;
+    }
+
+    // Pins the file open in the browser so it stays in the preview while the
+    // selection changes; without an open file, unpins instead
+    pub fn toggle_pin(&mut self) {
+        if let Some((path, content)) = &self.open_file {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let status = format!("Pinned {} (Ctrl+P again to unpin)", name);
+            self.pinned_preview = Some((path.clone(), content.clone()));
+            self.set_status(status);
+        } else if self.pinned_preview.take().is_some() {
+            self.set_status("Unpinned the preview".to_string());
+        } else {
+            self.set_status("Open a file (Tab, Enter) to pin it".to_string());
+        }
+    }
+
+    // Re-sorts and re-filters after `path` changed, keeping the cursor on it
+    fn resort_following(&mut self, path: &Path) {
+        let pre_search_selection = self.pre_search_selection.clone();
+        self.sort_mode.sort(&mut self.all_entries);
+        self.update_search();
+        self.pre_search_selection = pre_search_selection;
+        if let Some(index) = self.filtered_entries.iter().position(|e| e.path == path) {
+            self.selected_index = index;
+        }
+    }
+
+    // Re-runs the search as entries change, keeping the cursor on its entry if it still matches
+    fn refilter(&mut self) {
+        let selected = self
+            .filtered_entries
+            .get(self.selected_index)
+            .map(|e| e.path.clone());
+        self.update_search();
+        if let Some(index) =
+            selected.and_then(|path| self.filtered_entries.iter().position(|e| e.path == path))
+        {
+            self.selected_index = index;
+        }
+    }
+
+    // Stars the selected entry, or unstars it, and moves it with the cursor
+    pub fn toggle_favorite(&mut self) {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else {
+            return;
+        };
+        let path = entry.path.clone();
+        let favorite = !self.favorites.remove(&path);
+        if favorite {
+            self.favorites.insert(path.clone());
+        }
+        if let Err(e) = favorites::save(&self.paths, &self.favorites) {
+            // Keep the star as it is on disk, so the list doesn't promise what a
+            // restart would forget
+            if favorite {
+                self.favorites.remove(&path);
+            } else {
+                self.favorites.insert(path);
+            }
+            self.set_status(format!("Could not save the favorites: {}", e));
+            return;
+        }
+        for entry in self
+            .all_entries
+            .iter_mut()
+            .chain(&mut self.filtered_entries)
+            .filter(|e| e.path == path)
+        {
+            entry.is_favorite = favorite;
+        }
+        self.resort_following(&path);
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        self.set_status(if favorite {
+            format!("Added {} to the favorites", name)
+        } else {
+            format!("Removed {} from the favorites", name)
+        });
+    }
+
+    // Asks for the branch of a new worktree experiment of the selected repository
+    pub fn begin_worktree(&mut self) {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else {
+            return;
+        };
+        if !entry.is_git() {
+            self.set_status(format!("{} is not a git repository", entry.name));
+            return;
+        }
+        self.worktree_pending = Some(entry.path.clone());
+        self.worktree_branch.clear();
+        self.mode = AppMode::Worktree;
+    }
+
+    // Adds the worktree captured by `begin_worktree` on a worker thread; the TUI
+    // quits into it once it exists
+    pub fn create_worktree(&mut self) {
+        let branch = self.worktree_branch.trim().to_string();
+        if branch.is_empty() {
+            return;
+        }
+        self.mode = AppMode::Normal;
+        let Some(repo) = self.worktree_pending.take() else {
+            return;
+        };
+        let mut name = ops::worktree_name(&repo, &branch);
+        if self.date_prefix {
+            name = date_prefixed(&name);
+        }
+        self.set_status(format!("Creating worktree {}…", name));
+        let tx = self.events_tx.clone();
+        let (tries_dir, hooks) = (self.tries_dir.clone(), self.hooks.clone());
+        std::thread::spawn(move || {
+            let result = ops::worktree(&tries_dir, &repo, &branch, &name)
+                .inspect(|path| {
+                    // Hooks can't print over the TUI
+                    if let Err(e) = hooks.run(Hook::Create, path, true) {
+                        log::warn!("{:#}", e);
+                    }
+                })
+                .map_err(|e| format!("{:#}", e));
+            let _ = tx.send(AppEvent::Worker(WorkerResult::Worktree(result)));
+        });
+    }
+
+    // Opens the rename popup for the selected entry, starting from its current name
+    pub fn begin_rename(&mut self) {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else {
+            return;
+        };
+        self.rename_pending = Some(entry.path.clone());
+        self.rename_input = entry.leaf_name().to_string();
+        self.mode = AppMode::Rename;
+    }
+
+    // Renames the entry captured by `begin_rename`; everything keyed by its path
+    // (favorites, sizes, git statuses, marks) follows it
+    pub fn rename_selected(&mut self) {
+        let Some(path) = self.rename_pending.clone() else {
+            self.mode = AppMode::Normal;
+            return;
+        };
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        if normalize_selection(&self.rename_input) == name {
+            self.rename_pending = None;
+            self.mode = AppMode::Normal;
+            return;
+        }
+        // A failure keeps the popup open, so the name can be corrected
+        let renamed = match ops::rename(&self.paths, &path, &self.rename_input, true) {
+            Ok(renamed) => renamed,
+            Err(e) => {
+                self.set_status(format!("Renaming {} failed: {}", name, e));
+                return;
+            }
+        };
+        self.rename_pending = None;
+        self.mode = AppMode::Normal;
+        // `ops::rename` saved the favorites already
+        if self.favorites.remove(&path) {
+            self.favorites.insert(renamed.clone());
+        }
+        if self.marked.remove(&path) {
+            self.marked.insert(renamed.clone());
+        }
+        if let Some(size) = self.sizes.remove(&path) {
+            self.sizes.insert(renamed.clone(), size);
+        }
+        if let Some(status) = self.git_statuses.remove(&path) {
+            self.git_statuses.insert(renamed.clone(), status);
+        }
+        self.recent.remove(&path);
+        if self.pre_search_selection.as_ref() == Some(&path) {
+            self.pre_search_selection = Some(renamed.clone());
+        }
+        let new_name = renamed
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        for entry in self
+            .all_entries
+            .iter_mut()
+            .chain(&mut self.filtered_entries)
+            .filter(|e| e.path == path)
+        {
+            entry.relocate(renamed.clone());
+        }
+        self.set_status(format!("Renamed {} to {}", name, new_name));
+        self.resort_following(&renamed);
+        // A new name the query no longer matches would vanish from view
+        if !self.filtered_entries.iter().any(|e| e.path == renamed) {
+            self.query.clear();
+            self.resort_following(&renamed);
+        }
+    }
+
+    // Opens the description and notes of the selected entry for editing
+    pub fn begin_notes(&mut self) {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else {
+            return;
+        };
+        self.notes_pending = Some(entry.path.clone());
+        self.notes_draft = entry.meta.clone();
+        self.notes_focus_notes = false;
+        self.mode = AppMode::EditNotes;
+    }
+
+    // Writes the edited description and notes into the experiment
+    pub fn save_notes(&mut self) {
+        self.mode = AppMode::Normal;
+        let Some(path) = self.notes_pending.take() else {
+            return;
+        };
+        let mut draft = std::mem::take(&mut self.notes_draft);
+        draft.description = draft.description.trim().to_string();
+        draft.notes = draft.notes.trim_end().to_string();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if let Err(e) = meta::save(&path, &draft) {
+            self.set_status(format!("Could not save the notes of {}: {}", name, e));
+            return;
+        }
+        self.set_status(format!("Saved the notes of {}", name));
+        for entry in self
+            .all_entries
+            .iter_mut()
+            .chain(&mut self.filtered_entries)
+            .filter(|e| e.path == path)
+        {
+            entry.meta = draft.clone();
+        }
+    }
+
+    // The field of the notes popup that typing goes to
+    fn notes_field(&mut self) -> &mut String {
+        if self.notes_focus_notes {
+            &mut self.notes_draft.notes
+        } else {
+            &mut self.notes_draft.description
+        }
+    }
+
+    // Popup text for the notes editor; fields show their last `width` characters
+    fn notes_summary(&self, width: usize) -> String {
+        let name = self
+            .notes_pending
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .unwrap_or_default()
+            .to_string_lossy();
+        let field = |label: &str, text: &str, focused: bool| {
+            let text = text.replace('\n', " ⏎ ");
+            let cursor = if focused { "▏" } else { " " };
+            let marker = if focused { "→ " } else { "  " };
+            let chars: Vec<char> = text.chars().collect();
+            let shown: String = chars[chars.len().saturating_sub(width.saturating_sub(14))..]
+                .iter()
+                .collect();
+            format!("{}{}: {}{}", marker, label, shown, cursor)
+        };
+        [
+            format!("Notes for '{}'", name),
+            field(
+                "About",
+                &self.notes_draft.description,
+                !self.notes_focus_notes,
+            ),
+            field("Notes", &self.notes_draft.notes, self.notes_focus_notes),
+            "(Tab to switch, Alt+Enter for a new line, Enter to save, Esc to cancel)".to_string(),
+        ]
+        .join("\n")
+    }
+
+    // Puts the selected entry under git on a worker thread; the row updates when it's done
+    pub fn init_git(&mut self) {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else {
+            return;
+        };
+        if entry.is_git() {
+            self.set_status(format!("{} is already a git repository", entry.name));
+            return;
+        }
+        let tx = self.events_tx.clone();
+        let path = entry.path.clone();
+        let commit = self.git_init_commit;
+        std::thread::spawn(move || {
+            let result = git_init(&path, commit);
+            let _ = tx.send(AppEvent::Worker(WorkerResult::GitInit(path, result)));
+        });
+    }
+
+    // Opens the selected entry through `editor_url_template`, staying in the list
+    pub fn open_editor_url(&mut self) {
+        let Some(template) = &self.editor_url_template else {
+            self.set_status("No editor_url_template configured in config.toml".to_string());
+            return;
+        };
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else {
+            return;
+        };
+        let url = editor_url(template, &entry.path);
+        self.set_status(match open_url(&url) {
+            Ok(()) => format!("Opened {}", url),
+            Err(e) => format!("Could not open {}: {}", url, e),
+        });
+    }
+
+    // Quits to open the selected entry with the terminal integration (see `integrations::open`)
+    pub fn spawn_selected(&mut self) {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else {
+            return;
+        };
+        if !self.terminal_integration.installed() {
+            let program = self.terminal_integration.program();
+            self.set_status(format!("{} is not installed", program));
+            return;
+        }
+        self.wants_spawn = Some(entry.path.clone());
+        self.should_quit = true;
+    }
+
+    // Opens the delete popup, remembering the targets by path so a list refresh
+    // while it is open can't redirect the delete to another entry
+    pub fn begin_delete(&mut self) {
+        let targets = self.delete_targets();
+        if targets.is_empty() {
+            return;
+        }
+        for path in &targets {
+            self.request_size(path);
+        }
+        // Statuses are re-read, so the popup reflects the repositories right now. Until
+        // every one is back, the popup says it is checking and can't be confirmed.
+        let repos: Vec<PathBuf> = self
+            .all_entries
+            .iter()
+            .filter(|e| e.is_git() && targets.contains(&e.path))
+            .map(|e| e.path.clone())
+            .collect();
+        self.delete_at_risk.clear();
+        self.delete_checking = repos.iter().cloned().collect();
+        self.request_git_statuses(repos);
+        self.delete_typed.clear();
+        self.delete_pending = targets;
+        self.mode = AppMode::DeleteConfirm;
+    }
+
+    // Adds a delete target to `delete_at_risk` when its fresh `status` shows
+    // uncommitted changes or unpushed commits; a repository git can't read counts too
+    fn check_git_work(&mut self, path: &Path, status: Option<GitStatus>) {
+        let status = status.unwrap_or(GitStatus {
+            dirty: true,
+            ..GitStatus::default()
+        });
+        if status.at_risk() && self.delete_pending.iter().any(|p| p == path) {
+            self.delete_at_risk.push((path.to_path_buf(), status));
+        }
+    }
+
+    // Closes the delete popup without deleting anything
+    fn cancel_delete(&mut self) {
+        self.delete_pending.clear();
+        self.delete_at_risk.clear();
+        self.delete_checking.clear();
+        self.mode = AppMode::Normal;
+    }
+
+    // What has to be typed to delete unsaved git work: the folder name when a
+    // single repository is at risk, "delete" otherwise
+    fn delete_phrase(&self) -> String {
+        match self.delete_at_risk.as_slice() {
+            [(path, _)] => path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            _ => "delete".to_string(),
+        }
+    }
+
+    // Popup text for the delete confirmation
+    fn delete_summary(&self) -> String {
+        let targets = &self.delete_pending;
+        let size = self.size_summary(targets);
+        let what = if let [path] = targets.as_slice() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            format!("'{}' ({})", name, size)
+        } else {
+            format!("{} marked experiments ({})", targets.len(), size)
+        };
+        // Worktrees go through git, which has no trash to bring them back from
+        let worktrees = targets.iter().filter(|p| git::is_worktree(p)).count();
+        let action = match (worktrees, targets.len()) {
+            (0, _) => format!("Move {} to the trash", what),
+            (_, 1) => format!("Remove worktree {} permanently (no undo)", what),
+            _ => format!(
+                "Delete {}: {} worktree(s) removed permanently (no undo),\nthe rest moved to the trash",
+                what, worktrees
+            ),
+        };
+        if !self.delete_checking.is_empty() {
+            return format!(
+                "{}?\nChecking {} repository(ies) for unsaved git work… (Esc to cancel)",
+                action,
+                self.delete_checking.len()
+            );
+        }
+        if self.delete_at_risk.is_empty() {
+            return format!("{}? (y/n)", action);
+        }
+        let mut lines = vec!["Unsaved git work:".to_string()];
+        for (path, status) in &self.delete_at_risk {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            lines.push(format!("{}: {}", name, status.describe()));
+        }
+        lines.push(format!("{} anyway?", action));
+        lines.push(format!(
+            "Type '{}' and Enter to confirm, Esc to cancel:",
+            self.delete_phrase()
+        ));
+        lines.push(format!("{}▏", self.delete_typed));
+        lines.join("\n")
+    }
+
+    // Asks before archiving the selected entry
+    pub fn begin_archive(&mut self) {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else {
+            return;
+        };
+        let path = entry.path.clone();
+        self.request_size(&path);
+        self.archive_pending = Some(path);
+        self.mode = AppMode::ArchiveConfirm;
+    }
+
+    // Compresses the entry captured by `begin_archive` on a worker thread
+    pub fn archive_selected(&mut self) {
+        self.mode = AppMode::Normal;
+        let Some(path) = self.archive_pending.take() else {
+            return;
+        };
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        self.set_status(format!("Archiving {}…", name));
+        let tx = self.events_tx.clone();
+        let (tries_dir, format) = (self.tries_dir.clone(), self.archive_format);
+        std::thread::spawn(move || {
+            let result =
+                archive::archive(&path, &tries_dir, format).map_err(|e| format!("{:#}", e));
+            let _ = tx.send(AppEvent::Worker(WorkerResult::Archived(path, result)));
+        });
+    }
+
+    // Opens the list of archives
+    pub fn open_archives(&mut self) {
+        self.archives = archive::list(&self.tries_dir);
+        if self.archives.is_empty() {
+            self.set_status("There are no archives".to_string());
+            return;
+        }
+        self.archive_cursor = 0;
+        self.mode = AppMode::Archives;
+    }
+
+    // Unpacks the archive under the cursor on a worker thread and closes the list
+    pub fn restore_archive(&mut self) {
+        self.mode = AppMode::Normal;
+        if self.archive_cursor >= self.archives.len() {
+            return;
+        }
+        let archive = self.archives.remove(self.archive_cursor);
+        self.set_status(format!("Restoring {}…", archive.name));
+        let tx = self.events_tx.clone();
+        let tries_dir = self.tries_dir.clone();
+        std::thread::spawn(move || {
+            let result =
+                archive::restore(&archive.path, &tries_dir).map_err(|e| format!("{:#}", e));
+            let _ = tx.send(AppEvent::Worker(WorkerResult::Unarchived(result)));
+        });
+    }
+
+    // Popup text for the archives view: a window of rows around the cursor
+    fn archives_summary(&self, rows: usize) -> String {
+        let start = (self.archive_cursor + 1).saturating_sub(rows);
+        let mut lines: Vec<String> = self
+            .archives
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(rows)
+            .map(|(i, archive)| {
+                let marker = if i == self.archive_cursor {
+                    "→ "
+                } else {
+                    "  "
+                };
+                let date: chrono::DateTime<Local> = archive.modified.into();
+                format!(
+                    "{}{}  {}  {}",
+                    marker,
+                    archive.name,
+                    date.format("%Y-%m-%d"),
+                    format_size(archive.bytes)
+                )
+            })
+            .collect();
+        lines.push("(↑↓ to choose, Enter to restore, Esc to close)".to_string());
+        lines.join("\n")
+    }
+
+    // Opens the list of experiments entered most recently that still exist
+    pub fn open_recent(&mut self) {
+        self.visits = history::load_visits(&self.paths);
+        self.visits.retain(|visit| visit.path.is_dir());
+        self.visits.truncate(RECENT_VISITS);
+        if self.visits.is_empty() {
+            self.set_status("No experiments entered yet".to_string());
+            return;
+        }
+        self.visit_cursor = 0;
+        self.mode = AppMode::Recent;
+    }
+
+    // Leaves the TUI for the visited experiment under the cursor
+    pub fn enter_visit(&mut self) {
+        self.mode = AppMode::Normal;
+        if let Some(visit) = self.visits.get(self.visit_cursor) {
+            self.final_selection = Some(visit.path.to_string_lossy().into_owned());
+            self.should_quit = true;
+        }
+    }
+
+    // Opens the theme list with the current preset under the cursor
+    pub fn open_themes(&mut self) {
+        self.theme_cursor = PRESETS
+            .iter()
+            .position(|(name, _)| *name == self.theme_name)
+            .unwrap_or(0);
+        self.mode = AppMode::ThemePick;
+    }
+
+    // Draws the app in the preset `name` (with the `[colors]` overrides)
+    fn apply_theme(&mut self, name: &str) {
+        self.theme = self.theme_colors.theme(name, self.color_depth);
+    }
+
+    // Moves the theme cursor and previews the preset under it
+    pub fn move_theme_cursor(&mut self, down: bool) {
+        self.theme_cursor = if down {
+            (self.theme_cursor + 1).min(PRESETS.len() - 1)
+        } else {
+            self.theme_cursor.saturating_sub(1)
+        };
+        self.apply_theme(PRESETS[self.theme_cursor].0);
+    }
+
+    // Goes back to the theme the list was opened with
+    pub fn cancel_theme(&mut self) {
+        let name = self.theme_name.clone();
+        self.apply_theme(&name);
+        self.mode = AppMode::Normal;
+    }
+
+    // Keeps the previewed theme and saves it as `theme` in config.toml
+    pub fn save_theme(&mut self) {
+        self.mode = AppMode::Normal;
+        let name = PRESETS[self.theme_cursor].0;
+        self.theme_name = name.to_string();
+        let message = match &self.config_file {
+            Some(file) => match set_config_string(file, "theme", name) {
+                Ok(()) => format!("Theme: {} (saved to {})", name, file.display()),
+                Err(e) => format!("Theme: {} (not saved: {:#})", name, e),
+            },
+            None => format!("Theme: {} (no config file to save it to)", name),
+        };
+        self.set_status(message);
+    }
+
+    // Popup text for the theme list: a window of rows around the cursor
+    fn themes_summary(&self, rows: usize) -> String {
+        let start = (self.theme_cursor + 1).saturating_sub(rows);
+        let mut lines: Vec<String> = PRESETS
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(rows)
+            .map(|(i, (name, _))| {
+                let marker = if i == self.theme_cursor { "→ " } else { "  " };
+                let current = if *name == self.theme_name {
+                    "  (current)"
+                } else {
+                    ""
+                };
+                format!("{}{}{}", marker, name, current)
+            })
+            .collect();
+        lines.push("(↑↓ to preview, Enter to keep and save, Esc to cancel)".to_string());
+        lines.join("\n")
+    }
+
+    // Popup text for the recent view: a window of rows around the cursor
+    fn visits_summary(&self, rows: usize) -> String {
+        let start = (self.visit_cursor + 1).saturating_sub(rows);
+        let now = Local::now();
+        let mut lines: Vec<String> = self
+            .visits
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(rows)
+            .map(|(i, visit)| {
+                let marker = if i == self.visit_cursor { "→ " } else { "  " };
+                let age = (now - visit.visited_at).to_std().unwrap_or_default();
+                format!(
+                    "{}{}  {}  ({} ago)",
+                    marker,
+                    history::visit_label(&visit.path, &self.tries_dir),
+                    visit.visited_at.format("%Y-%m-%d %H:%M"),
+                    format_age(age)
+                )
+            })
+            .collect();
+        lines.push("(↑↓ to choose, Enter to jump back, Esc to close)".to_string());
+        lines.join("\n")
+    }
+
+    // Deletes the targets captured by `begin_delete` on a worker thread, where their
+    // delete hooks run too
+    pub fn delete_selected(&mut self) {
+        let targets = std::mem::take(&mut self.delete_pending);
+        let at_risk = std::mem::take(&mut self.delete_at_risk);
+        self.mode = AppMode::Normal;
+
+        // Abort if any target vanished or moved since the popup opened
+        if let Some(missing) = targets
+            .iter()
+            .find(|p| !p.is_dir() || !self.all_entries.iter().any(|e| &e.path == *p))
+        {
+            self.set_status(format!(
+                "Not deleted: {} no longer exists or was moved",
+                missing.display()
+            ));
+            return;
+        }
+
+        self.set_status(format!("Deleting {} experiment(s)…", targets.len()));
+        let tx = self.events_tx.clone();
+        let (tries_dir, hooks) = (self.tries_dir.clone(), self.hooks.clone());
+        std::thread::spawn(move || {
+            let results = targets
+                .into_iter()
+                .map(|path| {
+                    // Uncommitted changes are only dropped for the targets the phrase confirmed
+                    let force = at_risk.iter().any(|(p, _)| p == &path);
+                    let result = ops::delete(&hooks, &tries_dir, &path, true, force)
+                        .map_err(|e| format!("{:#}", e));
+                    (path, result)
+                })
+                .collect();
+            let _ = tx.send(AppEvent::Worker(WorkerResult::Deleted(results)));
+        });
+    }
+
+    // Drops the entries a delete worker removed. Deleting only moves to the trash;
+    // the batch can be brought back with Ctrl+Z. Worktrees are removed through git
+    // and can't be.
+    fn finish_delete(&mut self, results: Vec<(PathBuf, Result<Option<PathBuf>, String>)>) {
+        let mut trashed = Vec::new();
+        let mut removed = Vec::new();
+        let mut errors = Vec::new();
+
+        for (path, result) in results {
+            match result {
+                Ok(Some(location)) => {
+                    if let Some(entry) = self.all_entries.iter().find(|e| e.path == path) {
+                        trashed.push((entry.clone(), location));
+                    }
+                }
+                Ok(None) => removed.push(path),
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+
+        if !trashed.is_empty() || !removed.is_empty() {
+            removed.extend(trashed.iter().map(|(t, _)| t.path.clone()));
+            self.all_entries.retain(|e| !removed.contains(&e.path));
+            for path in &removed {
+                self.marked.remove(path);
+            }
+            self.update_search();
+        }
+
+        self.set_status(if let Some(err) = errors.first() {
+            format!("Error deleting {}", err)
+        } else if trashed.is_empty() {
+            format!("Removed {} worktree(s) permanently", removed.len())
+        } else if let [(entry, _)] = trashed.as_slice() {
+            format!("Moved {} to the trash (Ctrl+Z to undo)", entry.name)
+        } else {
+            format!(
+                "Moved {} experiments to the trash (Ctrl+Z to undo)",
+                trashed.len()
+            )
+        });
+        if !trashed.is_empty() {
+            self.trashed = trashed;
+        }
+    }
+
+    // Brings back the last batch moved to the trash, under its old name when still free
+    pub fn undo_delete(&mut self) {
+        if self.trashed.is_empty() {
+            self.set_status("Nothing to undo".to_string());
+            return;
+        }
+        let mut restored = 0;
+        let mut errors = Vec::new();
+        for (mut entry, location) in std::mem::take(&mut self.trashed) {
+            let root = entry
+                .path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+            match restore_from_trash(&location, &root, entry.leaf_name()) {
+                Ok(path) => {
+                    entry.relocate(path);
+                    self.all_entries.push(entry);
+                    restored += 1;
+                }
+                Err(e) => errors.push(format!("{}: {}", entry.name, e)),
+            }
+        }
+        self.sort_mode.sort(&mut self.all_entries);
+        self.update_search();
+        self.set_status(match errors.first() {
+            Some(err) => format!("Error restoring {}", err),
+            None => format!("Restored {} experiment(s)", restored),
+        });
+    }
+
+    // Shows `message` in the footer until it times out or the user types
+    pub fn set_status(&mut self, message: String) {
+        self.status_message = Some(message);
+        self.status_since = Some(Instant::now());
+    }
+
+    // Clears the status message once it has been up for `status_timeout`
+    pub fn expire_status(&mut self) {
+        if let (Some(since), Some(timeout)) = (self.status_since, self.status_timeout)
+            && since.elapsed() >= timeout
+        {
+            self.status_message = None;
+            self.status_since = None;
+        }
+    }
+}
+
+// Lines of an opened file as shown in the preview, from `scroll` on. Long lines
+// are cut at the pane width.
+fn file_lines(
+    path: &Path,
+    content: &FilePreview,
+    scroll: usize,
+    height: usize,
+    width: usize,
+) -> Vec<Line<'static>> {
+    // Highlighted from the top: fences and block comments carry over between lines
+    let syntax = match content {
+        FilePreview::Text { .. } => Syntax::for_path(path),
+        _ => Syntax::Plain,
+    };
+    let mut lines: Vec<Line> = highlight(&content.lines(), syntax, width)
+        .into_iter()
+        .skip(scroll)
+        .take(height)
+        .collect();
+    if let FilePreview::Text {
+        truncated: true, ..
+    } = content
+    {
+        lines.push(Line::from(Span::styled(
+            " (truncated) ",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines
+}
+
+fn draw_popup(f: &mut Frame, title: &str, message: &str, theme: &Theme) {
+    let area = f.area();
+
+    // 1. Define an area in the center (60% width, as tall as the message)
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(40),
+            Constraint::Length(message.lines().count() as u16 + 2),
+            Constraint::Percentage(40),
+        ])
+        .split(area);
+
+    let popup_area = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(20),
+            Constraint::Percentage(60),
+            Constraint::Percentage(20),
+        ])
+        .split(popup_layout[1])[1];
+
+    // 2. Clears the popup area (so the background text doesn't show through)
+    f.render_widget(Clear, popup_area);
+
+    // 3. Creates the block with a red border (alert)
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .style(Style::default().bg(theme.popup_bg));
+
+    let paragraph = Paragraph::new(message)
+        .block(block)
+        .style(
+            Style::default()
+                .fg(theme.popup_text)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center);
+
+    f.render_widget(paragraph, popup_area);
+}
+
+// Feeds terminal input and periodic ticks into the event channel. The input thread
+// blocks in `event::read`, so an idle TUI only wakes up once per tick.
+fn spawn_event_sources(app: &App) {
+    let tx = app.events_tx.clone();
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if tx.send(AppEvent::Input(event)).is_err() {
+                break;
+            }
+        }
+    });
+
+    let tx = app.events_tx.clone();
+    let tick_rate = app.tick_rate;
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(tick_rate);
+            if tx.send(AppEvent::Tick).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+// Splits `text` into spans, styling the characters at `indices`
+fn highlight_matches(text: &str, indices: &[usize], matched: Style) -> Vec<Span<'static>> {
+    text.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if indices.contains(&i) {
+                Span::styled(c.to_string(), matched)
+            } else {
+                Span::raw(c.to_string())
+            }
+        })
+        .collect()
+}
+
+// Applies a key press; behavior depends on the mode
+fn handle_key(app: &mut App, key: KeyEvent) {
+    match app.mode {
+        AppMode::Normal => {
+            // Alt+1..9 picks one of the first nine entries right away; without
+            // Alt, digits are typed into the query as usual
+            if let KeyCode::Char(c @ '1'..='9') = key.code
+                && app.quick_select
+                && key.modifiers.contains(event::KeyModifiers::ALT)
+            {
+                app.quick_pick(c as usize - '1' as usize);
+            } else if let Some(action) = app.keymap.action(&key) {
+                run_action(app, action);
+            } else {
+                match key.code {
+                    KeyCode::Char(c) => {
+                        app.status_message = None; // Clear status on type
+                        app.push_query(c);
+                    }
+                    KeyCode::Backspace => {
+                        app.query.pop();
+                        app.update_search();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        AppMode::Browse => match key.code {
+            // With a file open the arrows scroll it, otherwise they move the cursor
+            KeyCode::Up if app.open_file.is_some() => {
+                app.file_scroll = app.file_scroll.saturating_sub(1);
+            }
+            KeyCode::Down if app.open_file.is_some() => {
+                let len = app.open_file.as_ref().map_or(0, |(_, c)| c.lines().len());
+                if (app.file_scroll as usize) + 1 < len {
+                    app.file_scroll += 1;
+                }
+            }
+            KeyCode::Up if app.browse_cursor > 0 => {
+                app.browse_cursor -= 1;
+            }
+            KeyCode::Down if app.browse_cursor + 1 < app.browse_files().len() => {
+                app.browse_cursor += 1;
+            }
+            // Enter walks into folders and shows or hides files
+            KeyCode::Enter if app.open_file.is_some() => app.open_file = None,
+            KeyCode::Enter | KeyCode::Right => app.browse_into(),
+            KeyCode::Left | KeyCode::Backspace => app.browse_back(),
+            KeyCode::Tab | KeyCode::Esc => app.end_browse(),
+            _ => match app.keymap.action(&key) {
+                Some(Action::Pin) => app.toggle_pin(),
+                Some(Action::Quit) => app.should_quit = true,
+                Some(Action::Browse) => app.end_browse(),
+                Some(Action::Edit) => app.edit_browsed_file(),
+                Some(Action::ScrollUp | Action::PageUp) => app.browse_page(false),
+                Some(Action::ScrollDown | Action::PageDown) => app.browse_page(true),
+                _ => {}
+            },
+        },
+
+        // Nothing can be confirmed before every repository's git work is known
+        AppMode::DeleteConfirm if !app.delete_checking.is_empty() => match key.code {
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => app.cancel_delete(),
+            _ if app.keymap.action(&key) == Some(Action::Quit) => app.should_quit = true,
+            _ => {}
+        },
+
+        // Unsaved git work: y/n is too easy to hit, the phrase has to be typed out
+        AppMode::DeleteConfirm if !app.delete_at_risk.is_empty() => match key.code {
+            KeyCode::Enter if app.delete_typed == app.delete_phrase() => app.delete_selected(),
+            KeyCode::Esc => app.cancel_delete(),
+            KeyCode::Backspace => {
+                app.delete_typed.pop();
+            }
+            _ if app.keymap.action(&key) == Some(Action::Quit) => app.should_quit = true,
+            KeyCode::Char(c) if !key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                app.delete_typed.push(c);
+            }
+            _ => {}
+        },
+
+        AppMode::DeleteConfirm => match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                app.delete_selected();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => app.cancel_delete(),
+            _ if app.keymap.action(&key) == Some(Action::Quit) => app.should_quit = true,
+            _ => {}
+        },
+
+        AppMode::ArchiveConfirm => match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => app.archive_selected(),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                app.archive_pending = None;
+                app.mode = AppMode::Normal;
+            }
+            _ if app.keymap.action(&key) == Some(Action::Quit) => app.should_quit = true,
+            _ => {}
+        },
+
+        AppMode::Archives => match key.code {
+            KeyCode::Up => app.archive_cursor = app.archive_cursor.saturating_sub(1),
+            KeyCode::Down if app.archive_cursor + 1 < app.archives.len() => {
+                app.archive_cursor += 1;
+            }
+            KeyCode::Enter => app.restore_archive(),
+            KeyCode::Esc => app.mode = AppMode::Normal,
+            _ => match app.keymap.action(&key) {
+                Some(Action::Quit) => app.should_quit = true,
+                Some(Action::Archives) => app.mode = AppMode::Normal,
+                _ => {}
+            },
+        },
+
+        AppMode::Recent => match key.code {
+            KeyCode::Up => app.visit_cursor = app.visit_cursor.saturating_sub(1),
+            KeyCode::Down if app.visit_cursor + 1 < app.visits.len() => {
+                app.visit_cursor += 1;
+            }
+            KeyCode::Enter => app.enter_visit(),
+            KeyCode::Esc => app.mode = AppMode::Normal,
+            _ => match app.keymap.action(&key) {
+                Some(Action::Quit) => app.should_quit = true,
+                Some(Action::Recent) => app.mode = AppMode::Normal,
+                _ => {}
+            },
+        },
+
+        AppMode::MoveRoot => match key.code {
+            KeyCode::Up => app.move_cursor = app.move_cursor.saturating_sub(1),
+            KeyCode::Down if app.move_cursor + 1 < app.move_destinations().len() => {
+                app.move_cursor += 1;
+            }
+            KeyCode::Enter => app.move_selected(),
+            KeyCode::Esc => {
+                app.move_pending = None;
+                app.mode = AppMode::Normal;
+            }
+            _ => match app.keymap.action(&key) {
+                Some(Action::Quit) => app.should_quit = true,
+                Some(Action::MoveRoot) => app.mode = AppMode::Normal,
+                _ => {}
+            },
+        },
+
+        AppMode::ThemePick => match key.code {
+            KeyCode::Up => app.move_theme_cursor(false),
+            KeyCode::Down => app.move_theme_cursor(true),
+            KeyCode::Enter => app.save_theme(),
+            KeyCode::Esc => app.cancel_theme(),
+            _ => match app.keymap.action(&key) {
+                Some(Action::Quit) => app.should_quit = true,
+                Some(Action::Theme) => app.cancel_theme(),
+                _ => {}
+            },
+        },
+
+        AppMode::Rename => match key.code {
+            KeyCode::Enter => app.rename_selected(),
+            KeyCode::Esc => {
+                app.rename_pending = None;
+                app.mode = AppMode::Normal;
+            }
+            KeyCode::Backspace => {
+                app.rename_input.pop();
+            }
+            _ if app.keymap.action(&key) == Some(Action::Quit) => app.should_quit = true,
+            KeyCode::Char(c) if !key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                app.rename_input.push(c);
+            }
+            _ => {}
+        },
+
+        AppMode::Worktree => match key.code {
+            KeyCode::Enter => app.create_worktree(),
+            KeyCode::Esc => {
+                app.worktree_pending = None;
+                app.mode = AppMode::Normal;
+            }
+            KeyCode::Backspace => {
+                app.worktree_branch.pop();
+            }
+            _ if app.keymap.action(&key) == Some(Action::Quit) => app.should_quit = true,
+            KeyCode::Char(c) if !key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                app.worktree_branch.push(c);
+            }
+            _ => {}
+        },
+
+        AppMode::EditNotes => match key.code {
+            // Alt+Enter or Ctrl+J start a new line of notes; plain Enter saves
+            KeyCode::Enter
+                if app.notes_focus_notes && key.modifiers.contains(event::KeyModifiers::ALT) =>
+            {
+                app.notes_draft.notes.push('\n');
+            }
+            KeyCode::Char('j')
+                if app.notes_focus_notes
+                    && key.modifiers.contains(event::KeyModifiers::CONTROL) =>
+            {
+                app.notes_draft.notes.push('\n');
+            }
+            KeyCode::Enter => app.save_notes(),
+            KeyCode::Esc => {
+                app.notes_pending = None;
+                app.mode = AppMode::Normal;
+            }
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => {
+                app.notes_focus_notes = !app.notes_focus_notes;
+            }
+            KeyCode::Backspace => {
+                app.notes_field().pop();
+            }
+            _ if app.keymap.action(&key) == Some(Action::Quit) => app.should_quit = true,
+            KeyCode::Char(c) if !key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                app.notes_field().push(c);
+            }
+            _ => {}
+        },
+
+        AppMode::TemplatePick => match key.code {
+            KeyCode::Up => app.template_cursor = app.template_cursor.saturating_sub(1),
+            KeyCode::Down if app.template_cursor < app.templates.len() => {
+                app.template_cursor += 1;
+            }
+            KeyCode::Enter => {
+                app.template = app
+                    .template_cursor
+                    .checked_sub(1)
+                    .and_then(|i| app.templates.get(i).cloned());
+                app.final_selection = app.template_pending.take();
+                app.should_quit = true;
+            }
+            KeyCode::Esc => {
+                app.template_pending = None;
+                app.wants_editor = false;
+                app.mode = AppMode::Normal;
+            }
+            _ if app.keymap.action(&key) == Some(Action::Quit) => app.should_quit = true,
+            _ => {}
+        },
 
-            match fs::remove_dir_all(&path_to_remove) {
-                Ok(_) => {
-                    self.all_entries.retain(|e| e.name != entry_name);
-                    self.update_search();
-                    self.status_message = Some(format!("Deleted: {}", path_to_remove.display()));
-                }
-                Err(e) => {
-                    self.status_message = Some(format!("Error deleting: {}", e));
+        AppMode::CloneConfirm => match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                if let Some(selection) = app.clone_pending.take() {
+                    app.start_clone(&selection);
                 }
             }
-        }
-        self.mode = AppMode::Normal;
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                app.clone_pending = None;
+                app.wants_editor = false;
+                app.mode = AppMode::Normal;
+            }
+            _ if app.keymap.action(&key) == Some(Action::Quit) => app.should_quit = true,
+            _ => {}
+        },
+
+        AppMode::Cloning => match key.code {
+            KeyCode::Esc => app.cancel_clone(),
+            _ if app.keymap.action(&key) == Some(Action::Quit) => {
+                app.cancel_clone();
+                app.should_quit = true;
+            }
+            _ => {}
+        },
     }
 }
 
-fn draw_popup(f: &mut Frame, title: &str, message: &str, theme: &Theme) {
-    let area = f.area();
-
-    // 1. Define an area in the center (60% width, 20% height)
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(40),
-            Constraint::Length(3),
-            Constraint::Percentage(40),
-        ])
-        .split(area);
-
-    let popup_area = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(20),
-            Constraint::Percentage(60),
-            Constraint::Percentage(20),
-        ])
-        .split(popup_layout[1])[1];
-
-    // 2. Clears the popup area (so the background text doesn't show through)
-    f.render_widget(Clear, popup_area);
-
-    // 3. Creates the block with a red border (alert)
-    let block = Block::default()
-        .title(title)
-        .borders(Borders::ALL)
-        .style(Style::default().bg(theme.popup_bg));
-
-    let paragraph = Paragraph::new(message)
-        .block(block)
-        .style(
-            Style::default()
-                .fg(theme.popup_text)
-                .add_modifier(Modifier::BOLD),
-        )
-        .alignment(Alignment::Center);
-
-    f.render_widget(paragraph, popup_area);
+// Runs a list action bound in the keymap (see `keys`)
+fn run_action(app: &mut App, action: Action) {
+    match action {
+        Action::Quit => app.should_quit = true,
+        // Only enter delete mode if something is selected or marked
+        Action::Delete => app.begin_delete(),
+        Action::Mark => app.toggle_mark(),
+        // Restore what the last delete moved to the trash
+        Action::Undo => app.undo_delete(),
+        Action::Pin => app.toggle_pin(),
+        Action::Favorite => app.toggle_favorite(),
+        Action::Notes => app.begin_notes(),
+        Action::Rename => app.begin_rename(),
+        Action::Spawn => app.spawn_selected(),
+        Action::Worktree => app.begin_worktree(),
+        Action::Root => app.switch_root(),
+        Action::MoveRoot => app.begin_move_root(),
+        Action::ScrollUp => app.scroll_preview(false),
+        Action::ScrollDown => app.scroll_preview(true),
+        Action::GitInit => app.init_git(),
+        Action::OpenUrl => app.open_editor_url(),
+        Action::Edit => {
+            // Globally configured editor or via .try-rs-editor
+            let has_editor = app.editor_cmd.is_some()
+                || app
+                    .filtered_entries
+                    .get(app.selected_index)
+                    .is_some_and(|e| project_editor(&e.path).is_some());
+            if has_editor {
+                if !app.filtered_entries.is_empty() {
+                    app.final_selection =
+                        Some(app.filtered_entries[app.selected_index].selection());
+                    app.wants_editor = true;
+                    app.should_quit = true;
+                } else if let Some(name) = app.query_selection() {
+                    app.select_query(name, true);
+                }
+            } else {
+                app.set_status("No editor configured in config.toml".to_string());
+            }
+        }
+        Action::Up => app.selected_index = app.selected_index.saturating_sub(1),
+        Action::Down => {
+            if app.selected_index < app.filtered_entries.len().saturating_sub(1) {
+                app.selected_index += 1;
+            }
+        }
+        Action::PageUp => app.move_selection(app.list_page(), false),
+        Action::PageDown => app.move_selection(app.list_page(), true),
+        Action::HalfPageUp => app.move_selection(app.list_page().div_ceil(2), false),
+        Action::HalfPageDown => app.move_selection(app.list_page().div_ceil(2), true),
+        Action::First => app.selected_index = 0,
+        Action::Last => app.move_selection(usize::MAX, true),
+        Action::Sort => app.cycle_sort(),
+        Action::Preview => app.preview_mode = app.preview_mode.next(),
+        Action::Diff => app.begin_diff(),
+        Action::Browse => app.begin_browse(),
+        Action::Archive => app.begin_archive(),
+        Action::Archives => app.open_archives(),
+        Action::Recent => app.open_recent(),
+        Action::Theme => app.open_themes(),
+        Action::Confirm => {
+            if !app.filtered_entries.is_empty() {
+                app.final_selection = Some(app.filtered_entries[app.selected_index].selection());
+                app.should_quit = true;
+            } else if let Some(name) = app.query_selection() {
+                app.select_query(name, false);
+            } else if !app.scanning {
+                // Nothing typed and nothing to select: make a scratch experiment
+                app.wants_scratch = true;
+                app.should_quit = true;
+            }
+        }
+        Action::Back => {
+            if app.esc_behavior == EscBehavior::ClearThenQuit && !app.query.is_empty() {
+                app.query.clear();
+                app.update_search();
+            } else {
+                app.should_quit = true;
+            }
+        }
+    }
 }
 
 pub fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stderr>>,
     mut app: App,
-) -> Result<(Option<String>, bool)> {
+) -> Result<Outcome> {
+    spawn_event_sources(&app);
+
     while !app.should_quit {
+        app.expire_status();
+        app.prepare_preview();
+        app.request_list_sizes();
+        app.request_list_git_statuses();
+
         terminal.draw(|f| {
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
@@ -247,9 +3174,15 @@ pub fn run_app(
                 .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
                 .split(chunks[2]);
 
+            let logo = app.icons.get(Icon::Logo);
+            let (title_start, title_end) = if logo.is_empty() {
+                ("try".to_string(), "")
+            } else {
+                (format!("{} try", logo), logo)
+            };
             let title = Paragraph::new(Line::from(vec![
                 Span::styled(
-                    "🦀 try",
+                    title_start,
                     Style::default()
                         .fg(app.theme.title_try)
                         .add_modifier(Modifier::BOLD),
@@ -266,7 +3199,7 @@ pub fn run_app(
                     Style::default().fg(Color::DarkGray),
                 ),
                 Span::styled(
-                    "🦀",
+                    title_end,
                     Style::default()
                         .fg(app.theme.title_rs)
                         .add_modifier(Modifier::BOLD),
@@ -275,155 +3208,411 @@ pub fn run_app(
             .alignment(Alignment::Center);
             f.render_widget(title, chunks[0]);
 
+            // Parsed once per frame for the sort label and the match highlighting
+            let query = Query::parse(&app.query);
+            let search_title = match app.root_name() {
+                Some(root) => format!(" Search/New · {} ", root),
+                None => " Search/New ".to_string(),
+            };
+            let mut search_block = Block::default().borders(Borders::ALL).title(search_title);
+            let unknown = query.unknown_kinds(&app.detectors);
+            if !unknown.is_empty() {
+                search_block = search_block.title_bottom(
+                    Line::from(Span::styled(
+                        format!(
+                            " Unknown lang: {} (known: {}) ",
+                            unknown.join(", "),
+                            app.detectors.names().join(", ")
+                        ),
+                        Style::default().fg(app.theme.status_message),
+                    ))
+                    .right_aligned(),
+                );
+            } else if app.query.is_empty() {
+                search_block = search_block.title_bottom(
+                    Line::from(Span::styled(
+                        format!(" Filters: {} ", query::OPERATORS),
+                        Style::default().fg(Color::DarkGray),
+                    ))
+                    .right_aligned(),
+                );
+            }
             let search_text = Paragraph::new(app.query.clone())
                 .style(Style::default().fg(app.theme.search_box))
-                .block(Block::default().borders(Borders::ALL).title(" Search/New "));
+                .block(search_block);
             f.render_widget(search_text, chunks[1]);
 
-            let items: Vec<ListItem> = app
+            // Names shared by several entries get their root appended so they can be told apart
+            let duplicate_names = duplicate_names(&app.filtered_entries);
+
+            // Available width: the block borders and the highlight symbol (reserved on
+            // every row)
+            let symbol_width = Span::raw(app.highlight_symbol.as_str()).width();
+            let table_width = (content_chunks[0].width as usize).saturating_sub(2 + symbol_width);
+
+            // Only the rows on screen are built. The first one shown stays put until the
+            // selection leaves the pane, and the last page is never left half empty.
+            let list_height = content_chunks[0].height.saturating_sub(2) as usize;
+            let total = app.filtered_entries.len();
+            let (mut window_start, _) = app.list_view.get();
+            if app.selected_index < window_start {
+                window_start = app.selected_index;
+            } else if app.selected_index >= window_start + list_height {
+                window_start = app.selected_index + 1 - list_height;
+            }
+            window_start = window_start.min(total.saturating_sub(list_height));
+            app.list_view.set((window_start, list_height));
+
+            let rows: Vec<ListRow> = app
                 .filtered_entries
                 .iter()
-                .map(|entry| {
-                    let now = SystemTime::now();
-                    let elapsed = now
-                        .duration_since(entry.modified)
-                        .unwrap_or(std::time::Duration::ZERO);
-                    let secs = elapsed.as_secs();
-                    let days = secs / 86400;
-                    let hours = (secs % 86400) / 3600;
-                    let minutes = (secs % 3600) / 60;
-                    let date_str = format!("({:02}d {:02}h {:02}m)", days, hours, minutes);
-
-                    // Calculate available width (block borders take 2 columns)
-                    let width = content_chunks[0].width.saturating_sub(5) as usize;
-
-                    let date_text = date_str.to_string();
-                    let date_width = date_text.chars().count();
-                    let git_icon = if entry.is_git { " " } else { "" };
-                    let git_width = if entry.is_git { 2 } else { 0 };
-                    let mise_icon = if entry.is_mise { "󰬔 " } else { "" };
-                    let mise_width = if entry.is_mise { 2 } else { 0 };
-                    let cargo_icon = if entry.is_cargo { " " } else { "" };
-                    let cargo_width = if entry.is_cargo { 2 } else { 0 };
-                    let maven_icon = if entry.is_maven { " " } else { "" };
-                    let maven_width = if entry.is_maven { 2 } else { 0 };
-                    let flutter_icon = if entry.is_flutter { " " } else { "" };
-                    let flutter_width = if entry.is_flutter { 2 } else { 0 };
-                    let go_icon = if entry.is_go { " " } else { "" };
-                    let go_width = if entry.is_go { 2 } else { 0 };
-                    let python_icon = if entry.is_python { " " } else { "" };
-                    let python_width = if entry.is_python { 2 } else { 0 };
-                    let icon_width = 2; // "📁" takes 2 columns
-
-                    let created_dt: chrono::DateTime<Local> = entry.created.into();
-                    let created_text = created_dt.format("%Y-%m-%d").to_string();
-                    let created_width = created_text.chars().count();
-
-                    // Calculate space for name
-                    let reserved = date_width
-                        + git_width
-                        + mise_width
-                        + cargo_width
-                        + maven_width
-                        + flutter_width
-                        + go_width
-                        + python_width
-                        + icon_width
-                        + created_width
-                        + 2; // +2 for gaps
-                    let available_for_name = width.saturating_sub(reserved);
-                    let name_len = entry.name.chars().count();
-
-                    let (display_name, padding) = if name_len > available_for_name {
-                        let safe_len = available_for_name.saturating_sub(3);
-                        let truncated: String = entry.name.chars().take(safe_len).collect();
-                        (format!("{}...", truncated), 1)
+                .enumerate()
+                .map(|(index, entry)| ListRow {
+                    entry,
+                    index,
+                    label: if duplicate_names.contains(entry.name.as_str()) {
+                        format!("{} ({})", entry.name, entry.root_label())
                     } else {
-                        (
-                            entry.name.clone(),
-                            width.saturating_sub(
-                                icon_width
-                                    + created_width
-                                    + 1
-                                    + name_len
-                                    + date_width
-                                    + git_width
-                                    + mise_width
-                                    + cargo_width
-                                    + maven_width
-                                    + flutter_width
-                                    + go_width
-                                    + python_width,
-                            ),
-                        )
-                    };
-
-                    let content = Line::from(vec![
-                        Span::raw("📁"),
-                        Span::styled(created_text, Style::default().fg(app.theme.list_date)),
-                        Span::raw(format!(" {}", display_name)),
-                        Span::raw(" ".repeat(padding)),
-                        Span::styled(cargo_icon, Style::default().fg(Color::Rgb(230, 100, 50))),
-                        Span::styled(maven_icon, Style::default().fg(Color::Rgb(255, 150, 50))),
-                        Span::styled(flutter_icon, Style::default().fg(Color::Rgb(2, 123, 222))),
-                        Span::styled(go_icon, Style::default().fg(Color::Rgb(0, 173, 216))),
-                        Span::styled(python_icon, Style::default().fg(Color::Yellow)),
-                        Span::styled(mise_icon, Style::default().fg(Color::Rgb(250, 179, 135))),
-                        Span::styled(git_icon, Style::default().fg(Color::Rgb(240, 80, 50))),
-                        Span::styled(date_text, Style::default().fg(app.theme.list_date)),
-                    ]);
-                    ListItem::new(content)
+                        entry.name.clone()
+                    },
+                    marked: app.marked.contains(&entry.path),
                 })
                 .collect();
+            let window_end = (window_start + list_height).min(total);
+            let table = list_table(
+                &app.columns,
+                &rows,
+                window_start..window_end,
+                RowOptions {
+                    dim_date: app.date_prefix,
+                    quick_select: app.quick_select,
+                    indent: app.indent,
+                    icons: &app.icons,
+                },
+                table_width as u16,
+                &app.theme,
+            );
 
-            let list = List::new(items)
-                .block(Block::default().borders(Borders::ALL).title(" Folders "))
-                .highlight_style(
-                    Style::default()
-                        .bg(app.theme.list_highlight_bg)
-                        .fg(app.theme.list_highlight_fg)
-                        .add_modifier(Modifier::BOLD),
-                )
-                .highlight_symbol("→ ");
+            // Show the active sort key; search text always ranks by score
+            let (sort_label, sort_arrow) = if query.text.is_empty() {
+                (app.sort_mode.label(), app.sort_mode.arrow())
+            } else {
+                ("Score", "↓")
+            };
+            let mut list_title = Line::from(vec![
+                Span::raw(" Folders "),
+                Span::styled(
+                    format!("[{} {}] ", sort_label, sort_arrow),
+                    Style::default().fg(app.theme.list_date),
+                ),
+            ]);
+            if app.scanning {
+                list_title.push_span(Span::styled(
+                    format!("scanning… {} ", app.all_entries.len()),
+                    Style::default().fg(app.theme.list_date),
+                ));
+            }
+
+            let list_block = Block::default().borders(Borders::ALL).title(list_title);
+            let table = table
+                .block(list_block)
+                .row_highlight_style(app.theme.highlight_style().add_modifier(Modifier::BOLD))
+                .highlight_symbol(app.highlight_symbol.as_str());
 
-            let mut state = ListState::default();
-            state.select(Some(app.selected_index));
-            f.render_stateful_widget(list, content_chunks[0], &mut state);
+            let mut state = TableState::default()
+                .with_selected(Some(app.selected_index.saturating_sub(window_start)));
+            f.render_stateful_widget(table, content_chunks[0], &mut state);
+
+            // Where the shown rows are in a list longer than the pane, on its right border
+            if total > list_height {
+                let mut scrollbar_state = ScrollbarState::new(total - list_height)
+                    .position(window_start)
+                    .viewport_content_length(list_height);
+                let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                    .begin_symbol(None)
+                    .end_symbol(None)
+                    .thumb_style(Style::default().fg(app.theme.list_date));
+                f.render_stateful_widget(
+                    scrollbar,
+                    content_chunks[0].inner(Margin::new(0, 1)),
+                    &mut scrollbar_state,
+                );
+            }
 
             // Preview Widget
+            let mut preview_title = Line::from(format!(" Preview · {} ", app.preview_mode.label()));
+            let mut preview_block = if app.mode == AppMode::Browse {
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(app.theme.title_try))
+            } else {
+                Block::default().borders(Borders::ALL)
+            };
+            // Inner size of the pane: the block borders take 2 rows and 2 columns
+            let preview_height = content_chunks[1].height.saturating_sub(2) as usize;
+            let preview_width = content_chunks[1].width.saturating_sub(2) as usize;
             if let Some(selected) = app.filtered_entries.get(app.selected_index) {
-                let preview_path = app.base_path.join(&selected.name);
+                let preview_path = &selected.path;
                 let mut preview_lines = Vec::new();
+                let dim = Style::default().fg(Color::DarkGray);
+
+                // Title: the entry's name with the query matches highlighted,
+                // plus its full path at the bottom while searching
+                let mut title_spans = vec![Span::raw(" ")];
+                title_spans.extend(highlight_matches(
+                    &selected.name,
+                    &selected.match_indices,
+                    Style::default()
+                        .fg(app.theme.title_try)
+                        .add_modifier(Modifier::BOLD),
+                ));
+                title_spans.push(Span::raw(format!(" · {} ", app.preview_mode.label())));
+                preview_title = Line::from(title_spans);
+                if !app.query.is_empty() {
+                    preview_block = preview_block.title_bottom(Line::from(Span::styled(
+                        format!(" {} ", preview_path.display()),
+                        dim,
+                    )));
+                }
 
-                if let Ok(entries) = fs::read_dir(&preview_path) {
-                    // Limit items to height of block to avoid reading too much
-                    for e in entries
-                        .take(content_chunks[1].height.saturating_sub(2) as usize)
-                        .flatten()
-                    {
-                        let file_name = e.file_name().to_string_lossy().to_string();
-                        let is_dir = e.file_type().map(|t| t.is_dir()).unwrap_or(false);
-                        let icon = if is_dir { "📁 " } else { "📄 " };
-                        preview_lines.push(Line::from(vec![
-                            Span::styled(icon, Style::default().fg(app.theme.title_try)),
-                            Span::raw(file_name),
-                        ]));
+                // Everything but a file list can be paged through with PageUp/PageDown
+                let mut scrollable = true;
+                let readme = match app.readmes.get(preview_path) {
+                    Some(readme) if app.preview_mode == PreviewMode::Readme => readme.as_ref(),
+                    _ => None,
+                };
+                let readme_loading = app.preview_mode == PreviewMode::Readme
+                    && !app.readmes.contains_key(preview_path);
+                match app.preview_mode {
+                    PreviewMode::Summary => {
+                        let now = SystemTime::now();
+                        let created: chrono::DateTime<Local> = selected.created.into();
+                        let age = now.duration_since(selected.modified).unwrap_or_default();
+                        let size = if app.sizes.contains_key(preview_path) {
+                            app.size_summary([preview_path])
+                        } else {
+                            "calculating...".to_string()
+                        };
+                        // Each kind in its color, after its icon
+                        let mut kinds = Vec::new();
+                        for kind in &selected.kinds {
+                            if !kinds.is_empty() {
+                                kinds.push(Span::raw(", "));
+                            }
+                            let glyph = kind.glyph(&app.icons);
+                            let text = if glyph.is_empty() {
+                                kind.label.clone()
+                            } else {
+                                format!("{} {}", glyph, kind.label)
+                            };
+                            kinds.push(Span::styled(text, Style::default().fg(kind.color)));
+                        }
+                        if kinds.is_empty() {
+                            kinds.push(Span::raw("-"));
+                        }
+                        // Empty (and so skipped) for experiments that aren't repositories
+                        let git = match app.git_statuses.get(preview_path) {
+                            _ if !selected.is_git() => String::new(),
+                            Some(Some(status)) => status.describe(),
+                            Some(None) => "unknown".to_string(),
+                            None => "checking...".to_string(),
+                        };
+                        let text = |value: String| {
+                            if value.is_empty() {
+                                Vec::new()
+                            } else {
+                                vec![Span::raw(value)]
+                            }
+                        };
+                        let rows = [
+                            ("Path", text(preview_path.display().to_string())),
+                            (
+                                "Created",
+                                text(created.format("%Y-%m-%d %H:%M").to_string()),
+                            ),
+                            ("Modified", text(format!("{} ago", format_age(age)))),
+                            ("Type", kinds),
+                            ("Size", text(size)),
+                            ("Git", text(git)),
+                            ("About", text(selected.meta.description.clone())),
+                        ];
+                        for (label, value) in rows {
+                            if value.is_empty() {
+                                continue;
+                            }
+                            let mut spans = vec![Span::styled(
+                                format!("{:<9}", label),
+                                Style::default().fg(app.theme.title_try),
+                            )];
+                            spans.extend(value);
+                            preview_lines.push(Line::from(spans));
+                        }
+                        // Notes are wrapped to the pane, keeping their own line breaks
+                        if !selected.meta.notes.is_empty() {
+                            preview_lines.push(Line::from(""));
+                            preview_lines.push(Line::from(Span::styled(
+                                "Notes",
+                                Style::default().fg(app.theme.title_try),
+                            )));
+                            for line in selected.meta.notes.lines() {
+                                let chars: Vec<char> = line.chars().collect();
+                                if chars.is_empty() {
+                                    preview_lines.push(Line::from(""));
+                                }
+                                for chunk in chars.chunks(preview_width.max(1)) {
+                                    preview_lines
+                                        .push(Line::from(chunk.iter().collect::<String>()));
+                                }
+                            }
+                        }
+                        // The last commits, with their age, cut at the pane width
+                        if selected.is_git() {
+                            preview_lines.push(Line::from(""));
+                            preview_lines.push(Line::from(Span::styled(
+                                "Commits",
+                                Style::default().fg(app.theme.title_try),
+                            )));
+                            match app.git_logs.get(preview_path) {
+                                None => preview_lines
+                                    .push(Line::from(Span::styled(" (loading...) ", dim))),
+                                Some(commits) if commits.is_empty() => preview_lines
+                                    .push(Line::from(Span::styled(" (no commits) ", dim))),
+                                Some(commits) => {
+                                    for (time, subject) in commits {
+                                        let age = now.duration_since(*time).unwrap_or_default();
+                                        let age = format!("{:>4} ", format_age(age));
+                                        let room = preview_width.saturating_sub(age.len());
+                                        preview_lines.push(Line::from(vec![
+                                            Span::styled(age, dim),
+                                            Span::raw(
+                                                subject.chars().take(room).collect::<String>(),
+                                            ),
+                                        ]));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    // Without a README the README mode shows the file list
+                    PreviewMode::Readme | PreviewMode::Files => {
+                        if let Some((path, content)) = readme {
+                            let name = path.file_name().unwrap_or_default().to_string_lossy();
+                            preview_title = Line::from(format!(" {}/{} ", selected.name, name));
+                            preview_lines.extend(file_lines(
+                                path,
+                                content,
+                                0,
+                                usize::MAX,
+                                preview_width,
+                            ));
+                        } else if readme_loading {
+                            preview_lines.push(Line::from(Span::styled(" (loading...) ", dim)));
+                        } else if let Some((path, content)) = &app.open_file {
+                            scrollable = false;
+                            let name = path.strip_prefix(preview_path).unwrap_or(path);
+                            preview_title =
+                                Line::from(format!(" {}/{} ", selected.name, name.display()));
+                            preview_lines.extend(file_lines(
+                                path,
+                                content,
+                                app.file_scroll as usize,
+                                preview_height,
+                                preview_width,
+                            ));
+                        } else {
+                            scrollable = false;
+                            // While browsing, scroll so the cursor stays visible; otherwise
+                            // limit items to height of block to avoid reading too much
+                            let (max_items, skip) = if app.mode == AppMode::Browse {
+                                let skip = app
+                                    .browse_cursor
+                                    .saturating_sub(preview_height.saturating_sub(1));
+                                (skip + preview_height, skip)
+                            } else {
+                                (preview_height, 0)
+                            };
+                            let dir = if app.mode == AppMode::Browse {
+                                preview_path.join(&app.browse_dir)
+                            } else {
+                                preview_path.clone()
+                            };
+                            if app.mode == AppMode::Browse && !app.browse_dir.as_os_str().is_empty()
+                            {
+                                preview_title = Line::from(format!(
+                                    " {}/{} ",
+                                    selected.name,
+                                    app.browse_dir.display()
+                                ));
+                            }
+                            for (i, (file_name, is_dir)) in
+                                preview_entries(&dir, max_items, PREVIEW_MAX_SCAN)
+                                    .into_iter()
+                                    .enumerate()
+                                    .skip(skip)
+                            {
+                                let glyph =
+                                    app.icons.get(if is_dir { Icon::Dir } else { Icon::File });
+                                let icon = if glyph.is_empty() {
+                                    String::new()
+                                } else {
+                                    format!("{} ", glyph)
+                                };
+                                let mut line = Line::from(vec![
+                                    Span::styled(icon, Style::default().fg(app.theme.title_try)),
+                                    Span::raw(file_name),
+                                ]);
+                                if app.mode == AppMode::Browse && i == app.browse_cursor {
+                                    line = line.style(app.theme.highlight_style());
+                                }
+                                preview_lines.push(line);
+                            }
+                        }
                     }
+                    PreviewMode::Recent => match app.recent.get(preview_path) {
+                        Some(changes) => {
+                            preview_lines
+                                .extend(changes.lines.iter().map(|l| Line::from(l.as_str())));
+                            if changes.truncated {
+                                preview_lines.push(Line::from(Span::styled(" (truncated) ", dim)));
+                            }
+                        }
+                        None => preview_lines.push(Line::from(Span::styled(" (loading...) ", dim))),
+                    },
+                }
+
+                // A pinned file replaces the preview, except while browsing for another one
+                if let Some((path, content)) = &app.pinned_preview
+                    && app.mode != AppMode::Browse
+                {
+                    let parent = path
+                        .parent()
+                        .and_then(Path::file_name)
+                        .unwrap_or_default()
+                        .to_string_lossy();
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    preview_title = Line::from(format!(" 📌 {}/{} ", parent, name));
+                    preview_lines = file_lines(path, content, 0, usize::MAX, preview_width);
+                    scrollable = true;
                 }
 
                 if preview_lines.is_empty() {
-                    preview_lines.push(Line::from(Span::styled(
-                        " (empty) ",
-                        Style::default().fg(Color::DarkGray),
-                    )));
+                    preview_lines.push(Line::from(Span::styled(" (empty) ", dim)));
                 }
 
+                let scroll = if scrollable {
+                    app.preview_offset(preview_path)
+                        .min(preview_lines.len().saturating_sub(preview_height))
+                } else {
+                    0
+                };
+                let lines = if scrollable { preview_lines.len() } else { 0 };
+                app.preview_view.set((preview_height, lines));
                 let preview = Paragraph::new(preview_lines)
-                    .block(Block::default().borders(Borders::ALL).title(" Preview "));
+                    .block(preview_block.title(preview_title))
+                    .scroll((scroll as u16, 0));
                 f.render_widget(preview, content_chunks[1]);
             } else {
-                let preview = Block::default().borders(Borders::ALL).title(" Preview ");
-                f.render_widget(preview, content_chunks[1]);
+                f.render_widget(preview_block.title(preview_title), content_chunks[1]);
             }
 
             // --- Footer Widget (Help) ---
@@ -435,19 +3624,63 @@ pub fn run_app(
                         .fg(app.theme.status_message)
                         .add_modifier(Modifier::BOLD),
                 )])
-            } else {
+            } else if !app.marked.is_empty() {
+                // Running total of what deleting the marked entries would free
+                Line::from(vec![Span::styled(
+                    format!(
+                        "{} marked · {}",
+                        app.marked.len(),
+                        app.size_summary(&app.marked)
+                    ),
+                    Style::default()
+                        .fg(app.theme.status_message)
+                        .add_modifier(Modifier::BOLD),
+                )])
+            } else if app.mode == AppMode::Browse {
                 Line::from(vec![
                     Span::styled("↑↓", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(": Navigate  "),
-                    Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(": Select  "),
-                    Span::styled("Ctrl-D", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(": Delete  "),
-                    Span::styled("Ctrl-E", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(": Edit    "),
-                    Span::styled("Esc/Ctrl+C", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(": Exit"),
+                    Span::raw(": Navigate/Scroll  "),
+                    Span::styled("Enter/→", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(": Open Folder/File  "),
+                    Span::styled("←", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(": Back  "),
+                    Span::styled(
+                        app.keymap.label(Action::Edit),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(": Editor  "),
+                    Span::styled("Tab/Esc", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(": Back to List"),
                 ])
+            } else {
+                // Labels follow the [keys] section of the config
+                let keys = &app.keymap;
+                let (up, down) = (keys.label(Action::Up), keys.label(Action::Down));
+                let navigate = if up.chars().count() == 1 && down.chars().count() == 1 {
+                    format!("{}{}", up, down)
+                } else {
+                    format!("{}/{}", up, down)
+                };
+                let exit = format!("{}/{}", keys.label(Action::Back), keys.label(Action::Quit));
+                let mut spans = Vec::new();
+                for (key, label) in [
+                    (navigate.as_str(), ": Navigate  "),
+                    (keys.label(Action::Confirm), ": Select  "),
+                    (keys.label(Action::Browse), ": Files  "),
+                    (keys.label(Action::Delete), ": Delete  "),
+                    (keys.label(Action::Edit), ": Edit    "),
+                    (keys.label(Action::Mark), ": Mark  "),
+                    (keys.label(Action::Sort), ": Sort  "),
+                    (keys.label(Action::Preview), ": Preview  "),
+                    (exit.as_str(), ": Exit"),
+                ] {
+                    spans.push(Span::styled(
+                        key.to_string(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ));
+                    spans.push(Span::raw(label));
+                }
+                Line::from(spans)
             };
 
             let help_message = Paragraph::new(help_text)
@@ -457,98 +3690,577 @@ pub fn run_app(
             f.render_widget(help_message, chunks[3]);
 
             // --- DRAWING THE POPUP (If in DeleteConfirm mode) ---
-            if app.mode == AppMode::DeleteConfirm
-                && let Some(selected) = app.filtered_entries.get(app.selected_index)
+            if app.mode == AppMode::DeleteConfirm {
+                draw_popup(f, " WARNING ", &app.delete_summary(), &app.theme);
+            }
+            if app.mode == AppMode::CloneConfirm {
+                draw_popup(f, " CLONE ", &app.clone_summary(), &app.theme);
+            }
+            if app.mode == AppMode::Cloning {
+                // The popup spans 60% of the screen, minus its borders
+                let width = (f.area().width as usize * 3 / 5).saturating_sub(2);
+                let msg = app.clone_progress_summary(width);
+                draw_popup(f, " CLONING ", &msg, &app.theme);
+            }
+            if app.mode == AppMode::ArchiveConfirm
+                && let Some(path) = &app.archive_pending
+            {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let msg = format!(
+                    "Archive '{}' ({}) as {} and remove it? (y/n)",
+                    name,
+                    app.size_summary([path]),
+                    app.archive_format.extension()
+                );
+                draw_popup(f, " ARCHIVE ", &msg, &app.theme);
+            }
+            if app.mode == AppMode::Archives {
+                let rows = (f.area().height as usize / 2).saturating_sub(3).max(1);
+                draw_popup(f, " ARCHIVES ", &app.archives_summary(rows), &app.theme);
+            }
+            if app.mode == AppMode::Recent {
+                let rows = (f.area().height as usize / 2).saturating_sub(3).max(1);
+                draw_popup(f, " RECENT ", &app.visits_summary(rows), &app.theme);
+            }
+            if app.mode == AppMode::MoveRoot {
+                draw_popup(f, " MOVE ", &app.move_summary(), &app.theme);
+            }
+            if app.mode == AppMode::ThemePick {
+                let rows = (f.area().height as usize / 2).saturating_sub(3).max(1);
+                draw_popup(f, " THEME ", &app.themes_summary(rows), &app.theme);
+            }
+            if app.mode == AppMode::TemplatePick {
+                draw_popup(f, " TEMPLATE ", &app.template_summary(), &app.theme);
+            }
+            if app.mode == AppMode::Rename {
+                let msg = format!(
+                    "New name:\n{}▏\n(Enter to rename, Esc to cancel)",
+                    app.rename_input
+                );
+                draw_popup(f, " RENAME ", &msg, &app.theme);
+            }
+            if app.mode == AppMode::Worktree
+                && let Some(repo) = &app.worktree_pending
             {
-                let msg = format!("Delete '{}'? (y/n)", selected.name);
-                draw_popup(f, " WARNING ", &msg, &app.theme);
+                let name = repo.file_name().unwrap_or_default().to_string_lossy();
+                let msg = format!(
+                    "Branch for a worktree of {}:\n{}▏\n(Enter to create, Esc to cancel)",
+                    name, app.worktree_branch
+                );
+                draw_popup(f, " WORKTREE ", &msg, &app.theme);
             }
+            if app.mode == AppMode::EditNotes {
+                // The popup spans 60% of the screen, minus its borders
+                let width = (f.area().width as usize * 3 / 5).saturating_sub(2);
+                draw_popup(f, " NOTES ", &app.notes_summary(width), &app.theme);
+            }
+
+            app.color_depth.adapt_buffer(f.buffer_mut());
         })?;
 
-        // --- KEY HANDLING ---
-        if event::poll(std::time::Duration::from_millis(50))?
-            && let Event::Key(key) = event::read()?
-            && key.is_press()
-        {
-            // Behavior depends on the mode
-            match app.mode {
-                AppMode::Normal => match key.code {
-                    KeyCode::Char(c) => {
-                        // Ctrl+C to quit
-                        if c == 'c' && key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                            app.should_quit = true;
-                        }
-                        // Ctrl+D to delete
-                        else if c == 'd' && key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                            // Only enter delete mode if something is selected
-                            if !app.filtered_entries.is_empty() {
-                                app.mode = AppMode::DeleteConfirm;
-                            }
-                        } else if c == 'e' && key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                            // Ctrl+E to open editor
-                            if app.editor_cmd.is_some() {
-                                if !app.filtered_entries.is_empty() {
-                                    app.final_selection =
-                                        Some(app.filtered_entries[app.selected_index].name.clone());
-                                    app.wants_editor = true;
-                                    app.should_quit = true;
-                                } else if !app.query.is_empty() {
-                                    app.final_selection = Some(app.query.clone());
-                                    app.wants_editor = true;
-                                    app.should_quit = true;
-                                }
-                            } else {
-                                app.status_message =
-                                    Some("No editor configured in config.toml".to_string());
-                            }
-                        } else {
-                            app.query.push(c);
-                            app.status_message = None; // Clear status on type
-                            app.update_search();
-                        }
-                    }
-                    KeyCode::Backspace => {
-                        app.query.pop();
-                        app.update_search();
-                    }
-                    KeyCode::Up => {
-                        if app.selected_index > 0 {
-                            app.selected_index -= 1;
-                        }
-                    }
-                    KeyCode::Down => {
-                        if app.selected_index < app.filtered_entries.len().saturating_sub(1) {
-                            app.selected_index += 1;
-                        }
-                    }
-                    KeyCode::Enter => {
-                        if !app.filtered_entries.is_empty() {
-                            app.final_selection =
-                                Some(app.filtered_entries[app.selected_index].name.clone());
-                        } else if !app.query.is_empty() {
-                            app.final_selection = Some(app.query.clone());
-                        }
-                        app.should_quit = true;
-                    }
-                    KeyCode::Esc => app.should_quit = true,
-                    _ => {}
-                },
+        // Wait for the next event (key, worker result or tick), then handle
+        // everything else that queued up before drawing again
+        let mut event = app.events_rx.recv()?;
+        loop {
+            match event {
+                AppEvent::Input(Event::Key(key)) if key.is_press() => handle_key(&mut app, key),
+                AppEvent::Worker(result) => app.apply_result(result),
+                // Resizes and ticks only need a redraw (ticks keep the ages current)
+                AppEvent::Input(_) | AppEvent::Tick => {}
+            }
+            if app.should_quit {
+                break;
+            }
+            match app.events_rx.try_recv() {
+                Ok(next) => event = next,
+                Err(_) => break,
+            }
+        }
+    }
 
-                AppMode::DeleteConfirm => match key.code {
-                    KeyCode::Char('y') | KeyCode::Char('Y') => {
-                        app.delete_selected();
-                    }
-                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                        app.mode = AppMode::Normal;
-                    }
-                    KeyCode::Char('c') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                        app.should_quit = true;
-                    }
-                    _ => {}
-                },
+    Ok(Outcome {
+        selection: app.final_selection,
+        open_editor: app.wants_editor,
+        scratch: app.wants_scratch,
+        diff: app.wants_diff,
+        template: app.template,
+        edit_file: app.wants_file,
+        spawn: app.wants_spawn,
+        cloned: app.cloned,
+        tries_dir: Some(app.tries_dir),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icons::IconMode;
+    use ratatui::buffer::Buffer;
+    use std::fs;
+
+    fn entry_raw(name: &str) -> RawEntry {
+        RawEntry {
+            path: PathBuf::from("/tries").join(name),
+            name: name.to_string(),
+            modified: SystemTime::UNIX_EPOCH,
+            created: SystemTime::UNIX_EPOCH,
+            kinds: Vec::new(),
+            git: false,
+            meta: Meta::default(),
+        }
+    }
+
+    fn entry(name: &str) -> TryEntry {
+        TryEntry::from_raw(entry_raw(name))
+    }
+
+    // The rows of `table` drawn `width` columns wide, as text
+    fn draw(table: Table, width: u16, height: u16, selected: usize) -> Vec<String> {
+        let area = Rect::new(0, 0, width, height);
+        let mut buffer = Buffer::empty(area);
+        let mut state = TableState::default().with_selected(Some(selected));
+        StatefulWidget::render(table.highlight_symbol("> "), area, &mut buffer, &mut state);
+        (0..height)
+            .map(|y| {
+                let mut line = String::new();
+                let mut x = 0;
+                // A wide character covers the cell after it
+                while x < width {
+                    let symbol = buffer[(x, y)].symbol();
+                    line.push_str(symbol);
+                    x += symbol.width().max(1) as u16;
+                }
+                line.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn list_table_indents_after_the_highlight_symbol_and_cuts_wide_names() {
+        let entries = [entry("日本語のプロジェクト"), entry("b")];
+        let rows: Vec<ListRow> = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| ListRow {
+                entry,
+                index,
+                label: entry.name.clone(),
+                marked: false,
+            })
+            .collect();
+        let icons = Icons::new(IconMode::Ascii);
+        let options = RowOptions {
+            dim_date: false,
+            quick_select: true,
+            indent: 2,
+            icons: &icons,
+        };
+        let theme = Theme::default();
+        // 14 columns: "> " + 12 for the table, of which 2 indent, the digit and a space
+        let table = list_table(&[Column::Name], &rows, 0..2, options, 12, &theme);
+        let lines = draw(table, 14, 2, 0);
+        assert_eq!(lines, [">   1 日本...", "    2 b"]);
+    }
+
+    #[test]
+    fn entries_with_the_same_name_are_told_apart_by_root() {
+        let mut other = entry("api");
+        other.path = PathBuf::from("/work/api");
+        let mut nested = entry("clients/api");
+        nested.path = PathBuf::from("/work/clients/api");
+        let entries = [entry("api"), entry("web"), other, nested];
+
+        assert_eq!(duplicate_names(&entries), HashSet::from(["api"]));
+        assert_eq!(entries[0].root_label(), "tries");
+        assert_eq!(entries[2].root_label(), "work");
+        // The subfolders of a nested name are not the root
+        assert_eq!(entries[3].root_label(), "work");
+        assert_eq!(entries[0].selection(), "/tries/api");
+        assert_eq!(entries[2].selection(), "/work/api");
+    }
+
+    #[test]
+    fn sorting_by_name_breaks_ties_by_path() {
+        let mut entries = [entry("Api"), entry("api"), entry("api")];
+        entries[1].path = PathBuf::from("/b/api");
+        entries[2].path = PathBuf::from("/a/api");
+        SortMode::Name.sort(&mut entries);
+        let paths: Vec<_> = entries.iter().map(|e| e.selection()).collect();
+        assert_eq!(paths, ["/a/api", "/b/api", "/tries/Api"]);
+    }
+
+    // An app listing the experiments `names`, created in `dir`
+    fn app_with(dir: &Path, names: &[&str]) -> App {
+        for name in names {
+            fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        App::with_source(&Settings::for_tests(dir), &Scanner::new(dir))
+    }
+
+    fn select(app: &mut App, name: &str) {
+        app.selected_index = app
+            .filtered_entries
+            .iter()
+            .position(|e| e.name == name)
+            .unwrap();
+    }
+
+    // Applies what the workers send back, up to the first result `wanted` picks
+    fn wait_for(app: &mut App, wanted: impl Fn(&WorkerResult) -> bool) {
+        loop {
+            let AppEvent::Worker(result) =
+                app.events_rx.recv_timeout(Duration::from_secs(10)).unwrap()
+            else {
+                continue;
+            };
+            let done = wanted(&result);
+            app.apply_result(result);
+            if done {
+                return;
             }
         }
     }
 
-    Ok((app.final_selection, app.wants_editor))
+    // Experiments that exist nowhere on disk
+    struct InMemory(Vec<(&'static str, u64)>);
+
+    impl ScanSource for InMemory {
+        fn scan(&self) -> Vec<RawEntry> {
+            self.0
+                .iter()
+                .map(|&(name, age)| {
+                    let mut raw = entry_raw(name);
+                    raw.modified = SystemTime::now() - Duration::from_secs(age);
+                    raw
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn the_app_lists_entries_from_any_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = InMemory(vec![("old", 300), ("new", 10), ("newer/nested", 1)]);
+        let mut app = App::with_source(&Settings::for_tests(dir.path()), &source);
+        let names: Vec<&str> = app
+            .filtered_entries
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(names, ["newer/nested", "new", "old"]);
+
+        app.query = "nest".to_string();
+        app.update_search();
+        assert_eq!(app.filtered_entries.len(), 1);
+        assert_eq!(
+            app.filtered_entries[0].path,
+            Path::new("/tries/newer/nested")
+        );
+        assert!(!app.scanning);
+    }
+
+    #[test]
+    fn clearing_the_query_brings_back_the_sort_mode_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = InMemory(vec![("beta", 30), ("alpha", 20), ("gamma", 10)]);
+        let mut app = App::with_source(&Settings::for_tests(dir.path()), &source);
+        app.sort_mode = SortMode::Name;
+        // Whatever order the entries were left in, e.g. by a clone added at the end
+        app.all_entries.reverse();
+
+        app.query = "a".to_string();
+        app.update_search();
+        app.query.clear();
+        app.update_search();
+        let names: Vec<&str> = app
+            .filtered_entries
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(names, ["alpha", "beta", "gamma"]);
+    }
+
+    #[test]
+    fn quick_select_past_the_end_of_the_list_says_so() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = InMemory(vec![("a", 2), ("b", 1)]);
+        let mut app = App::with_source(&Settings::for_tests(dir.path()), &source);
+        app.quick_pick(4);
+        assert!(!app.should_quit && app.final_selection.is_none());
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("No entry 5 (the list has 2)")
+        );
+
+        app.quick_pick(1);
+        assert!(app.should_quit);
+        assert_eq!(app.final_selection.as_deref(), Some("/tries/a"));
+    }
+
+    #[test]
+    fn delete_removes_the_entry_captured_when_the_popup_opened() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app_with(dir.path(), &["a", "b"]);
+        select(&mut app, "a");
+        app.begin_delete();
+        assert_eq!(app.delete_pending, [dir.path().join("a")]);
+
+        // The list changes under the popup; the target stays the same
+        app.query = "b".to_string();
+        app.update_search();
+        select(&mut app, "b");
+        app.delete_selected();
+        wait_for(&mut app, |r| matches!(r, WorkerResult::Deleted(_)));
+        assert!(!dir.path().join("a").exists());
+        assert!(dir.path().join("b").is_dir());
+        assert!(app.all_entries.iter().all(|e| e.name != "a"));
+    }
+
+    #[test]
+    fn delete_is_abandoned_when_the_target_went_away() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app_with(dir.path(), &["a", "b"]);
+        select(&mut app, "a");
+        app.begin_delete();
+        fs::rename(dir.path().join("a"), dir.path().join("c")).unwrap();
+        app.delete_selected();
+        assert!(app.mode == AppMode::Normal);
+        assert!(
+            app.status_message
+                .as_deref()
+                .unwrap()
+                .starts_with("Not deleted")
+        );
+        assert!(dir.path().join("b").is_dir() && dir.path().join("c").is_dir());
+    }
+
+    #[test]
+    fn delete_popup_checks_git_work_on_a_worker() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        fs::create_dir(&repo).unwrap();
+        let ok = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&repo)
+            .args(["init", "-q"])
+            .status()
+            .unwrap()
+            .success();
+        assert!(ok);
+        fs::write(repo.join("new.txt"), "unsaved").unwrap();
+        let mut app = app_with(dir.path(), &[]);
+        select(&mut app, "repo");
+
+        app.begin_delete();
+        assert!(app.mode == AppMode::DeleteConfirm);
+        assert!(app.delete_summary().contains("Checking 1 repository"));
+        // Confirming has to wait for the check
+        handle_key(&mut app, KeyEvent::from(KeyCode::Char('y')));
+        assert!(app.mode == AppMode::DeleteConfirm && repo.is_dir());
+
+        wait_for(&mut app, |r| matches!(r, WorkerResult::GitStatus(..)));
+        assert!(app.delete_checking.is_empty());
+        assert_eq!(app.delete_at_risk.len(), 1);
+        assert!(app.delete_summary().contains("Type 'repo'"));
+    }
+
+    #[test]
+    fn renaming_a_worktree_moves_it_through_git() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let tries = dir.path().join("tries");
+        fs::create_dir_all(&tries).unwrap();
+        let git = |args: &[&str]| {
+            let ok = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status
+                .success();
+            assert!(ok, "git {:?}", args);
+        };
+        fs::create_dir(&repo).unwrap();
+        git(&["init", "-q"]);
+        fs::write(repo.join("a.txt"), "a").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-qm", "init"]);
+        crate::git::worktree_add(&repo, &tries.join("wt"), "wt").unwrap();
+
+        let settings = Settings::for_tests(&tries);
+        let mut app = App::with_source(&settings, &Scanner::new(&tries));
+        select(&mut app, "wt");
+        app.toggle_favorite();
+        app.begin_rename();
+        app.rename_input = "renamed".to_string();
+        app.rename_selected();
+
+        let renamed = tries.join("renamed");
+        assert!(app.mode == AppMode::Normal);
+        assert!(crate::git::is_worktree(&renamed));
+        assert!(app.all_entries.iter().any(|e| e.path == renamed));
+        assert!(favorites::load(&settings.paths).contains(&renamed));
+        // git knows the new path, so the repository can still remove it
+        git(&["worktree", "remove", &renamed.to_string_lossy()]);
+        assert!(!renamed.exists());
+    }
+
+    // The characters of `name` that `match_name` highlights for `text`
+    fn highlighted(name: &str, text: &str, normalize_names: bool, date_prefix: bool) -> String {
+        let matcher = SkimMatcherV2::default();
+        let (_, indices) =
+            match_name(&matcher, &entry(name), text, normalize_names, date_prefix).unwrap();
+        let chars: Vec<char> = name.chars().collect();
+        indices.into_iter().map(|i| chars[i]).collect()
+    }
+
+    #[test]
+    fn match_name_maps_positions_back_past_a_date_prefix() {
+        assert_eq!(
+            highlighted("2024-01-15-parser", "parse", false, true),
+            "parse"
+        );
+        assert_eq!(
+            highlighted("2024-01-15-parser", "parse", true, false),
+            "parse"
+        );
+        // Without either setting the date is searched like the rest
+        assert_eq!(
+            highlighted("2024-01-15-parser", "2024", false, false),
+            "2024"
+        );
+    }
+
+    #[test]
+    fn match_name_maps_positions_of_nested_normalised_names() {
+        let name = "clients/2024-01-15-parser-abc123";
+        assert_eq!(highlighted(name, "parser", true, false), "parser");
+        assert_eq!(highlighted(name, "clients", true, false), "clients");
+        // A match across the folder and the leaf skips the date in between
+        assert_eq!(highlighted("ab/2024-01-15-cd", "bc", true, false), "bc");
+        // Folder lengths count characters, not bytes
+        assert_eq!(highlighted("café/2024-01-15-x", "éx", true, false), "éx");
+    }
+
+    #[test]
+    fn git_column_only_shows_repositories_with_something_to_say() {
+        let theme = Theme::default();
+        let icons = Icons::new(IconMode::Ascii);
+        let options = RowOptions {
+            dim_date: false,
+            quick_select: false,
+            indent: 0,
+            icons: &icons,
+        };
+        let cell = |status: Option<GitStatus>| {
+            let mut entry = entry("repo");
+            entry.git_status = status;
+            Line::from(column_spans(Column::Git, &entry, false, options, &theme)).to_string()
+        };
+        assert_eq!(cell(None), "");
+        assert_eq!(cell(Some(GitStatus::default())), "");
+        let status = GitStatus {
+            dirty: true,
+            ahead: 1,
+            ..GitStatus::default()
+        };
+        assert_eq!(cell(Some(status)), status.indicators());
+        assert!(!status.indicators().is_empty());
+    }
+
+    #[test]
+    fn list_table_sizes_columns_over_every_row() {
+        let mut entries = [entry("a"), entry("b")];
+        entries[1].git_status = Some(GitStatus {
+            dirty: true,
+            ..GitStatus::default()
+        });
+        let rows: Vec<ListRow> = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| ListRow {
+                entry,
+                index,
+                label: entry.name.clone(),
+                marked: false,
+            })
+            .collect();
+        let icons = Icons::new(IconMode::Ascii);
+        let options = RowOptions {
+            dim_date: false,
+            quick_select: false,
+            indent: 0,
+            icons: &icons,
+        };
+        let theme = Theme::default();
+        let columns = [Column::Git, Column::Name];
+        // Only the clean first row is on screen, yet the git column keeps its width
+        let first = draw(
+            list_table(&columns, &rows, 0..1, options, 20, &theme),
+            22,
+            1,
+            0,
+        );
+        let second = draw(
+            list_table(&columns, &rows, 1..2, options, 20, &theme),
+            22,
+            1,
+            0,
+        );
+        let name_at = |line: &str, name: char| line.chars().position(|c| c == name);
+        assert_eq!(name_at(&first[0], 'a'), name_at(&second[0], 'b'));
+    }
+
+    #[test]
+    fn name_line_cuts_wide_characters_by_display_width() {
+        let theme = Theme::default();
+        // Eleven characters, two columns each
+        let label = "日本語のプロジェクト実";
+        for width in 0..24 {
+            let line = name_line(label, &[], width, false, &theme);
+            assert!(line.width() <= width, "{} > {}", line.width(), width);
+        }
+        let line = name_line(label, &[], 9, false, &theme);
+        assert_eq!(line.to_string(), "日本語...");
+        let line = name_line(label, &[], 22, false, &theme);
+        assert_eq!(line.to_string(), label);
+    }
+
+    #[test]
+    fn preview_worker_only_reads_the_last_entry_of_a_burst() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b, c) = (
+            dir.path().join("a"),
+            dir.path().join("b"),
+            dir.path().join("c"),
+        );
+        for path in [&a, &b, &c] {
+            fs::create_dir(path).unwrap();
+        }
+        let (jobs_tx, jobs_rx) = mpsc::channel();
+        let (tx, rx) = mpsc::channel();
+        jobs_tx.send(PreviewJob::Readme(a.clone())).unwrap();
+        jobs_tx.send(PreviewJob::GitLog(a.clone())).unwrap();
+        jobs_tx.send(PreviewJob::Readme(b.clone())).unwrap();
+        jobs_tx.send(PreviewJob::Readme(c.clone())).unwrap();
+        drop(jobs_tx);
+        preview_worker(jobs_rx, tx);
+
+        let results: Vec<_> = rx.into_iter().collect();
+        assert_eq!(results.len(), 3);
+        let dropped: Vec<_> = results
+            .iter()
+            .filter_map(|event| match event {
+                AppEvent::Worker(WorkerResult::PreviewDropped(path)) => Some(path.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(dropped, vec![a, b]);
+        assert!(matches!(
+            results.last(),
+            Some(AppEvent::Worker(WorkerResult::Readme(path, None))) if *path == c
+        ));
+    }
 }
@@ -0,0 +1,93 @@
+use crate::paths::Paths;
+use std::fs;
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Releases API queried by `update_check` unless `update_url` overrides it
+pub const DEFAULT_UPDATE_URL: &str =
+    "https://api.github.com/repos/tassiovirginio/try-rs/releases/latest";
+
+// Last check time and the newest tag seen then, as `unix_secs<TAB>tag`
+const UPDATE_STATE_FILE: &str = "update-check";
+
+// The network is asked at most this often; in between the cached tag is used
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+// Upper bound on the request, which only ever runs on a background thread
+const REQUEST_TIMEOUT_SECS: &str = "5";
+
+// `try-rs --build-info`: version, commit and build date as JSON on stdout
+pub fn print_build_info() {
+    let info = serde_json::json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_hash": env!("TRY_RS_GIT_HASH"),
+        "build_date": env!("TRY_RS_BUILD_DATE"),
+    });
+    println!("{}", info);
+}
+
+// Returns a notice when a release newer than this build exists. Meant to run on a
+// worker thread: it may wait on the network, and every failure just yields None.
+pub fn check_for_update(paths: &Paths, url: &str) -> Option<String> {
+    let latest = match cached_tag(paths) {
+        Some(tag) => tag,
+        None => {
+            let tag = fetch_latest_tag(url);
+            // Remember the attempt even when offline, so we don't retry on every start
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+            let _ = fs::create_dir_all(&paths.state_dir);
+            let _ = fs::write(
+                paths.state_file(UPDATE_STATE_FILE),
+                format!("{}\t{}\n", now, tag.as_deref().unwrap_or("")),
+            );
+            tag?
+        }
+    };
+    is_newer(&latest, env!("CARGO_PKG_VERSION")).then(|| {
+        format!(
+            "try-rs {} is available (you have {})",
+            latest,
+            env!("CARGO_PKG_VERSION")
+        )
+    })
+}
+
+// The tag stored by the last check, if it happened less than CHECK_INTERVAL ago
+fn cached_tag(paths: &Paths) -> Option<String> {
+    let contents = fs::read_to_string(paths.state_file(UPDATE_STATE_FILE)).ok()?;
+    let (checked, tag) = contents.trim_end().split_once('\t')?;
+    let checked = UNIX_EPOCH + Duration::from_secs(checked.parse().ok()?);
+    let age = SystemTime::now().duration_since(checked).ok()?;
+    (age < CHECK_INTERVAL).then(|| tag.to_string())
+}
+
+// Asks the releases API for the latest tag through curl, which keeps TLS out of the binary
+fn fetch_latest_tag(url: &str) -> Option<String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", REQUEST_TIMEOUT_SECS])
+        .args(["-H", "Accept: application/vnd.github+json"])
+        .arg(url)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let release: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    let tag = release["tag_name"].as_str()?;
+    (!tag.is_empty()).then(|| tag.to_string())
+}
+
+// Compares dotted versions numerically, ignoring a leading "v" and any pre-release suffix
+fn is_newer(tag: &str, current: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|n| n.parse().unwrap_or(0))
+            .collect()
+    };
+    parse(tag) > parse(current)
+}
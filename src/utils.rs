@@ -1,4 +1,10 @@
-use std::path::PathBuf;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 // Helper function to replace "~" with the actual home path
 pub fn expand_path(path_str: &str) -> PathBuf {
@@ -11,17 +17,554 @@ pub fn expand_path(path_str: &str) -> PathBuf {
     PathBuf::from(path_str)
 }
 
-// Checks if the string looks like a Git URL
-pub fn is_git_url(s: &str) -> bool {
+// Default strftime template for auto-named scratch experiments
+pub const DEFAULT_AUTO_NAME: &str = "scratch-%Y-%m-%d";
+
+// Expands the `auto_name` template and creates the scratch experiment,
+// appending -2, -3, ... until the name is free. Returns the name used.
+pub fn create_scratch(tries_dir: &Path, template: &str) -> io::Result<String> {
+    let now = chrono::Local::now();
+    let mut base = String::new();
+    if write!(base, "{}", now.format(template)).is_err() || base.trim().is_empty() {
+        // Invalid template: fall back to the default one
+        base = now.format(DEFAULT_AUTO_NAME).to_string();
+    }
+    create_unique_dir(tries_dir, &base)
+}
+
+// `base`, or `base-2`, `base-3`, ... : the first candidate `try_name` accepts.
+// `try_name` returns Ok(false) when the name is taken.
+pub fn first_free_name(
+    base: &str,
+    mut try_name: impl FnMut(&str) -> io::Result<bool>,
+) -> io::Result<String> {
+    let mut name = base.to_string();
+    let mut n = 2;
+    while !try_name(&name)? {
+        name = format!("{}-{}", base, n);
+        n += 1;
+    }
+    Ok(name)
+}
+
+// Creates a new directory under `tries_dir` named after `base`, suffixed if taken.
+// `create_dir` fails on existing directories, so two racing instances never share one.
+pub fn create_unique_dir(tries_dir: &Path, base: &str) -> io::Result<String> {
+    first_free_name(base, |name| match fs::create_dir(tries_dir.join(name)) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(e),
+    })
+}
+
+// Clones happen in a hidden sibling directory and are renamed into place once complete,
+// so a half-finished clone never shows up as an experiment
+pub const TEMP_CLONE_PREFIX: &str = ".tmp-";
+
+pub fn temp_clone_dir(tries_dir: &Path, name: &str) -> PathBuf {
+    tries_dir.join(format!(
+        "{}{}-{}",
+        TEMP_CLONE_PREFIX,
+        name,
+        std::process::id()
+    ))
+}
+
+// Moves a finished clone to `name`, or to `name-2`, ... if another instance got there first.
+// Returns the name used. The name is claimed with `create_dir`, which fails when it is
+// taken, so two instances never rename onto the same one.
+pub fn rename_into_place(temp: &Path, tries_dir: &Path, name: &str) -> io::Result<String> {
+    first_free_name(name, |candidate| {
+        rename_if_free(temp, &tries_dir.join(candidate))
+    })
+}
+
+// Renames `from` to `target` unless something already is there, in which case
+// it returns false. Checking and renaming separately would let another instance
+// slip in between.
+fn rename_if_free(from: &Path, target: &Path) -> io::Result<bool> {
+    match fs::create_dir(target) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Ok(false),
+        Err(e) => return Err(e),
+    }
+    // Renaming over the empty placeholder replaces it; Windows wants it gone first
+    if cfg!(windows) {
+        fs::remove_dir(target)?;
+    }
+    match fs::rename(from, target) {
+        Ok(()) => Ok(true),
+        Err(e) => {
+            let _ = fs::remove_dir(target);
+            Err(e)
+        }
+    }
+}
+
+// Deleted experiments are moved here, inside their tries root, until restored
+pub const TRASH_DIR: &str = ".trash";
+
+// Compressed experiments are kept here, inside their tries root (see `archive`)
+pub const ARCHIVE_DIR: &str = ".archive";
+
+// Moves an experiment into the trash of `tries_dir`, also when it sits in a subfolder,
+// so `try-rs restore` finds it. Returns where it ended up, suffixed like clones are
+// when the trash already holds that name.
+pub fn move_to_trash(path: &Path, tries_dir: &Path) -> io::Result<PathBuf> {
+    let Some(name) = path.file_name() else {
+        return Err(io::Error::other("not an experiment directory"));
+    };
+    let trash = tries_dir.join(TRASH_DIR);
+    fs::create_dir_all(&trash)?;
+    let name = rename_into_place(path, &trash, &name.to_string_lossy())?;
+    Ok(trash.join(name))
+}
+
+// Renames the experiment at `path` to `new_name` within its tries root and returns
+// the new path. Unlike clones, a taken name is an error: the user chose it.
+pub fn rename_experiment(path: &Path, new_name: &str) -> io::Result<PathBuf> {
+    let target = rename_target(path, new_name)?;
+    if !rename_if_free(path, &target)? {
+        return Err(taken(&target));
+    }
+    Ok(target)
+}
+
+// Where renaming the experiment at `path` to `new_name` puts it, if that is a free,
+// valid name next to it. The trash, the archives and clones in progress keep theirs.
+pub fn rename_target(path: &Path, new_name: &str) -> io::Result<PathBuf> {
+    let new_name = normalize_selection(new_name);
+    if !is_single_name(&new_name) {
+        return Err(io::Error::other(format!(
+            "'{}' is not a valid name",
+            new_name
+        )));
+    }
+    if new_name == TRASH_DIR || new_name == ARCHIVE_DIR || new_name.starts_with(TEMP_CLONE_PREFIX) {
+        return Err(io::Error::other(format!(
+            "'{}' is reserved for try-rs",
+            new_name
+        )));
+    }
+    let Some(root) = path.parent() else {
+        return Err(io::Error::other("not an experiment directory"));
+    };
+    let target = root.join(&new_name);
+    if target.exists() {
+        return Err(taken(&target));
+    }
+    Ok(target)
+}
+
+fn taken(target: &Path) -> io::Error {
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("'{}' already exists", name),
+    )
+}
+
+// Whether `name` is one plain directory name: not empty, no separator, not `.` or
+// `..`, so joining it to a directory can't leave that directory
+pub fn is_single_name(name: &str) -> bool {
+    !name.is_empty()
+        && Path::new(name).components().count() == 1
+        && !name.contains(['/', '\\'])
+        && name != "."
+        && name != ".."
+}
+
+// Moves the experiment at `path` into the tries root `root`, under the same name.
+// A taken name is an error, like for renames. Across filesystems the experiment is
+// copied and the original only removed once the copy is complete.
+pub fn move_experiment(path: &Path, root: &Path) -> io::Result<PathBuf> {
+    let Some(name) = path.file_name() else {
+        return Err(io::Error::other("not an experiment directory"));
+    };
+    let target = root.join(name);
+    match rename_if_free(path, &target) {
+        Ok(true) => Ok(target),
+        Ok(false) => Err(taken(&target)),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            fs::create_dir(&target).map_err(|e| match e.kind() {
+                io::ErrorKind::AlreadyExists => taken(&target),
+                _ => e,
+            })?;
+            if let Err(e) = copy_dir(path, &target) {
+                let _ = fs::remove_dir_all(&target);
+                return Err(e);
+            }
+            fs::remove_dir_all(path)?;
+            Ok(target)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// Copies the directory `from` to `to`, which may already exist. Symlinks are
+// copied as links, never followed, so a link out of the tree stays a link.
+pub fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            copy_symlink(&entry.path(), &target)?;
+        } else if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(link: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(link)?, target)
+}
+
+#[cfg(windows)]
+fn copy_symlink(link: &Path, target: &Path) -> io::Result<()> {
+    let points_to = fs::read_link(link)?;
+    // Windows tells file and directory links apart; a dangling one is made a file link
+    if fs::metadata(link).is_ok_and(|m| m.is_dir()) {
+        std::os::windows::fs::symlink_dir(points_to, target)
+    } else {
+        std::os::windows::fs::symlink_file(points_to, target)
+    }
+}
+
+// Moves a trashed experiment back into `tries_dir` as `name` (suffixed if taken)
+pub fn restore_from_trash(trashed: &Path, tries_dir: &Path, name: &str) -> io::Result<PathBuf> {
+    let name = rename_into_place(trashed, tries_dir, name)?;
+    Ok(tries_dir.join(name))
+}
+
+// The pid of a temporary clone directory, for names of the exact `.tmp-<name>-<pid>`
+// shape `temp_clone_dir` creates
+fn temp_clone_pid(dir_name: &str) -> Option<u32> {
+    let rest = dir_name.strip_prefix(TEMP_CLONE_PREFIX)?;
+    let (name, pid) = rest.rsplit_once('-')?;
+    if name.is_empty() || pid.is_empty() || !pid.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    pid.parse().ok()
+}
+
+// Whether process `pid` is running; None when that can't be told
+fn process_alive(pid: u32) -> Option<bool> {
+    if cfg!(target_os = "linux") {
+        return Some(Path::new("/proc").join(pid.to_string()).exists());
+    }
+    if cfg!(unix) {
+        // `kill -0` only checks that the process exists (EPERM still means it does)
+        let output = std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stderr(std::process::Stdio::piped())
+            .output()
+            .ok()?;
+        let denied = String::from_utf8_lossy(&output.stderr).contains("ermitted");
+        return Some(output.status.success() || denied);
+    }
+    let output = std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+}
+
+// Removes temporary clone directories left behind by runs that crashed or were killed.
+// Only directories named exactly like `temp_clone_dir` names them are considered, and
+// only once their process is gone: clones still being written are left alone, and so
+// is anything whose owner can't be checked.
+pub fn clean_stale_clones(tries_dir: &Path) {
+    let Ok(read_dir) = fs::read_dir(tries_dir) else {
+        return;
+    };
+    for entry in read_dir.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(pid) = temp_clone_pid(&name) else {
+            continue;
+        };
+        if !entry.file_type().is_ok_and(|t| t.is_dir()) || pid == std::process::id() {
+            continue;
+        }
+        if process_alive(pid) == Some(false) {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+// Cleans up a typed or pasted name: surrounding whitespace and a single
+// trailing path separator are dropped, so "foo ", "foo/" and " foo" all mean "foo"
+pub fn normalize_selection(s: &str) -> String {
+    let s = s.trim();
+    let s = s
+        .strip_suffix('/')
+        .or_else(|| cfg!(windows).then(|| s.strip_suffix('\\')).flatten())
+        .unwrap_or(s);
+    s.to_string()
+}
+
+// Whether joining `name` to the tries dir stays below it: a relative path of plain
+// names, so nested experiments pass but `..`, absolute paths and the trash don't
+pub fn stays_inside(name: &str) -> bool {
+    let mut components = Path::new(name).components().peekable();
+    components.peek().is_some()
+        && components.all(|c| matches!(c, Component::Normal(_)))
+        && !matches!(
+            Path::new(name).iter().next().and_then(|c| c.to_str()),
+            Some(TRASH_DIR | ARCHIVE_DIR)
+        )
+}
+
+// The name without a leading `YYYY-MM-DD-`, as written by `date_prefix` (and tobi/try)
+pub fn strip_date_prefix(name: &str) -> &str {
+    let bytes = name.as_bytes();
+    let is_prefix = bytes.len() > 11
+        && bytes[..11].iter().enumerate().all(|(i, b)| match i {
+            4 | 7 | 10 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        });
+    if is_prefix { &name[11..] } else { name }
+}
+
+// Prefixes the last component of `name` with today's date, unless it already has one
+pub fn date_prefixed(name: &str) -> String {
+    let (parent, leaf) = match name.rfind(['/', '\\']) {
+        Some(i) => name.split_at(i + 1),
+        None => ("", name),
+    };
+    if strip_date_prefix(leaf) != leaf {
+        return name.to_string();
+    }
+    format!(
+        "{}{}-{}",
+        parent,
+        chrono::Local::now().format("%Y-%m-%d"),
+        leaf
+    )
+}
+
+// Strips trailing date/hash-like tokens so "parser-2024-01-15-abc123" matches as "parser",
+// and a leading date so "2024-01-15-parser" does too
+pub fn normalize_name(name: &str) -> String {
+    let name = strip_date_prefix(name);
+    let is_noise = |token: &str| {
+        let all_digits = token.chars().all(|c| c.is_ascii_digit());
+        let hash_like = token.len() >= 6
+            && token.chars().all(|c| c.is_ascii_hexdigit())
+            && token.chars().any(|c| c.is_ascii_digit());
+        !token.is_empty() && (all_digits || hash_like)
+    };
+
+    let mut end = name.len();
+    while let Some(sep) = name[..end].rfind(['-', '_', '.', ' ']) {
+        if !is_noise(&name[sep + 1..end]) {
+            break;
+        }
+        end = sep;
+    }
+    // Never reduce a name to nothing
+    if end == 0 {
+        name.to_string()
+    } else {
+        name[..end].to_string()
+    }
+}
+
+// Reads the per-experiment editor override from `.try-rs-editor`, if any. The file
+// comes with whatever got cloned, and the editor ends up in a command line the
+// shell runs, so only a bare program name found on PATH is honoured.
+pub fn project_editor(dir: &Path) -> Option<String> {
+    let contents = fs::read_to_string(dir.join(".try-rs-editor")).ok()?;
+    let editor = contents
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#'))?;
+    let plain = editor
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_.+".contains(c));
+    if !plain || editor.starts_with(['-', '.']) || !on_path(editor) {
+        log::warn!(
+            "ignoring .try-rs-editor in {}: '{}' is not a program on PATH",
+            dir.display(),
+            editor
+        );
+        return None;
+    }
+    Some(editor.to_string())
+}
+
+// Whether `command` is an executable in one of the PATH directories
+pub fn on_path(command: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        let file = dir.join(command);
+        file.is_file() || (cfg!(windows) && file.with_extension("exe").is_file())
+    })
+}
+
+// Fills `{path}` in an editor URL template (`vscode://file/{path}`) with the
+// percent-encoded path. Separators stay as `/` so the URL keeps its shape.
+pub fn editor_url(template: &str, path: &Path) -> String {
+    let mut path = path.to_string_lossy().to_string();
+    if cfg!(windows) {
+        path = path.replace('\\', "/");
+    }
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/:".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "%{:02X}", byte);
+        }
+    }
+    template.replace("{path}", &encoded)
+}
+
+// Hands a URL to the platform opener without waiting for it
+pub fn open_url(url: &str) -> io::Result<()> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(windows) {
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    cmd.arg(url)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+// Runs `git init` in `dir`, then `git add -A && git commit -m initial` when `commit`
+// is set. The error is git's own message, short enough for the status line.
+pub fn git_init(dir: &Path, commit: bool) -> Result<(), String> {
+    let git = |args: &[&str]| -> Result<(), String> {
+        let output = std::process::Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .map_err(|e| format!("git: {}", e))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(stderr
+            .lines()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or("git failed")
+            .to_string())
+    };
+    git(&["init", "--quiet"])?;
+    if commit {
+        git(&["add", "-A"])?;
+        git(&["commit", "--quiet", "-m", "initial"])?;
+    }
+    Ok(())
+}
+
+// Checks if the string looks like a Git URL, or a `gh:`/`gl:` shorthand. A bare
+// `owner/repo` only counts with a `clone_host` to clone it from, since it is just
+// as likely a new nested experiment, and never when `owner` is a folder in `tries_dir`.
+pub fn is_git_url(s: &str, tries_dir: &Path, clone_host: Option<&str>) -> bool {
+    // A "#subdir" suffix selects where to enter after cloning
+    let s = s.split('#').next().unwrap_or(s);
     s.starts_with("http://")
         || s.starts_with("https://")
         || s.starts_with("git@")
         || s.starts_with("ssh://")
         || s.ends_with(".git")
+        || match shorthand(s) {
+            Some((Some(_), _)) => true,
+            Some((None, path)) => {
+                let owner = path.split('/').next().unwrap_or(path);
+                clone_host.is_some() && !tries_dir.join(owner).exists()
+            }
+            None => false,
+        }
+}
+
+// Splits `gh:owner/repo` (or `github:`), `gl:owner/repo` and bare `owner/repo`
+// into the host (None for the configured one) and the repository path. Bare
+// shorthands have exactly one slash, so `./a/b` still names a nested experiment.
+fn shorthand(s: &str) -> Option<(Option<&str>, &str)> {
+    let (host, path) = if let Some(path) = s.strip_prefix("gh:").or(s.strip_prefix("github:")) {
+        (Some("github.com"), path)
+    } else if let Some(path) = s.strip_prefix("gl:") {
+        (Some("gitlab.com"), path)
+    } else {
+        (None, s)
+    };
+    let segment = |part: &str| {
+        !part.is_empty()
+            && !part.starts_with('.')
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    let (owner, repo) = path.split_once('/')?;
+    // GitLab groups nest, so prefixed shorthands may have more segments
+    let valid = if host.is_some() {
+        segment(owner) && repo.split('/').all(segment)
+    } else {
+        segment(owner) && segment(repo)
+    };
+    valid.then_some((host, path))
+}
+
+// Turns a shorthand into the HTTPS URL to clone, using `clone_host` for bare
+// `owner/repo` (a host like "codeberg.org" or a base URL); anything else, and a
+// bare shorthand without `clone_host`, is returned unchanged
+pub fn expand_git_url(url: &str, clone_host: Option<&str>) -> String {
+    let Some((host, path)) = shorthand(url) else {
+        return url.to_string();
+    };
+    let base = match (host, clone_host) {
+        (Some(host), _) => format!("https://{}", host),
+        (None, Some(base)) if base.contains("://") => base.to_string(),
+        (None, Some(host)) => format!("https://{}", host),
+        (None, None) => return url.to_string(),
+    };
+    format!("{}/{}", base.trim_end_matches('/'), path)
 }
 
 // Extracts a clean repository name (e.g., "github.com/tobi/try.git" -> "try")
 pub fn extract_repo_name(url: &str) -> String {
+    // Drop "?ref=main" style queries and "#readme" style fragments
+    let url = url.split(['?', '#']).next().unwrap_or(url);
+
+    // Drop browser paths after the repo. GitLab puts them behind a "-" segment, which
+    // no group or project can be named: ".../group/sub/repo/-/tree/main". GitHub has
+    // no subgroups, so "tree"/"blob" followed by a ref right after host/owner/repo is
+    // one: "github.com/owner/repo/tree/main/src". A GitLab project named "tree" is a
+    // last segment and stays.
+    let url = match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let segments: Vec<&str> = rest.split('/').collect();
+            let gitlab = segments.iter().skip(3).position(|s| *s == "-");
+            let github = matches!(segments.get(3), Some(&"tree" | &"blob"))
+                && segments.get(4).is_some_and(|s| !s.is_empty());
+            match (gitlab, github) {
+                (Some(i), _) => format!("{}://{}", scheme, segments[..i + 3].join("/")),
+                (None, true) => format!("{}://{}", scheme, segments[..3].join("/")),
+                (None, false) => url.to_string(),
+            }
+        }
+        None => url.to_string(),
+    };
+
     // Remove trailing slash and .git suffix
     let clean_url = url.trim_end_matches('/').trim_end_matches(".git");
 
@@ -34,3 +577,411 @@ pub fn extract_repo_name(url: &str) -> String {
     // Generic name if detection fails
     "cloned-repo".to_string()
 }
+
+// Directories that are skipped when previewing an experiment
+pub const PREVIEW_SKIP_DIRS: &[&str] = &["node_modules", ".git", "target"];
+
+// Caps for recursive walks, so huge or network-mounted trees can't hang the UI
+#[derive(Clone, Copy)]
+pub struct WalkLimits {
+    pub max_depth: usize,
+    pub max_entries: usize,
+    pub budget: Duration,
+}
+
+// Upper bounds for walking a single experiment when computing its size
+pub const SIZE_LIMITS: WalkLimits = WalkLimits {
+    max_depth: 64,
+    max_entries: 500_000,
+    budget: Duration::from_secs(5),
+};
+
+// Result of a bounded recursive size walk
+#[derive(Clone, Copy, Default)]
+pub struct DirSize {
+    pub bytes: u64,
+    pub complete: bool, // false if the walk hit one of its limits
+}
+
+// Sums file sizes below `path`, giving up once any of the `limits` is reached.
+// Symlinks are counted but never followed, so link cycles can't trap the walk.
+pub fn dir_size(path: &Path, limits: WalkLimits) -> DirSize {
+    let started = Instant::now();
+    let mut size = DirSize {
+        bytes: 0,
+        complete: true,
+    };
+    let mut visited = 0;
+    let mut pending = vec![(path.to_path_buf(), 0)];
+
+    while let Some((dir, depth)) = pending.pop() {
+        if started.elapsed() > limits.budget {
+            size.complete = false;
+            break;
+        }
+        let Ok(read_dir) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            visited += 1;
+            if visited > limits.max_entries {
+                size.complete = false;
+                return size;
+            }
+            let Ok(metadata) = fs::symlink_metadata(entry.path()) else {
+                continue;
+            };
+            if !metadata.is_dir() {
+                size.bytes += metadata.len();
+            } else if depth < limits.max_depth {
+                pending.push((entry.path(), depth + 1));
+            } else {
+                size.complete = false;
+            }
+        }
+    }
+    size
+}
+
+// Lists up to `max` direct children of `dir` as (name, is_dir), skipping build/VCS dirs.
+// At most `max_scan` directory entries are read, however many are skipped.
+pub fn preview_entries(dir: &Path, max: usize, max_scan: usize) -> Vec<(String, bool)> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    read_dir
+        .take(max_scan)
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let is_dir = e.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            let skipped = is_dir && PREVIEW_SKIP_DIRS.contains(&name.as_str());
+            (!skipped).then_some((name, is_dir))
+        })
+        .take(max)
+        .collect()
+}
+
+// Computes several sizes at once, one worker per CPU
+pub fn dir_sizes(paths: &[PathBuf], limits: WalkLimits) -> Vec<DirSize> {
+    let results = Mutex::new(vec![DirSize::default(); paths.len()]);
+    dir_sizes_each(paths, limits, |i, size| results.lock().unwrap()[i] = size);
+    results.into_inner().unwrap()
+}
+
+// Like `dir_sizes`, but hands each size to `done` (with its index) as soon as it is known
+pub fn dir_sizes_each(paths: &[PathBuf], limits: WalkLimits, done: impl Fn(usize, DirSize) + Sync) {
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(paths.len().max(1));
+    let next = AtomicUsize::new(0);
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = paths.get(i) else { break };
+                    done(i, dir_size(path, limits));
+                }
+            });
+        }
+    });
+}
+
+// Human readable size (e.g., 18.4 GB)
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// Parses ages like "90s", "45m", "12h", "60d", "8w" or "1y"; a bare number counts days
+pub fn parse_age(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let (value, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(split) => s.split_at(split),
+        None => (s, "d"),
+    };
+    let value: u64 = value.parse().ok()?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        "y" => 365 * 86400,
+        _ => return None,
+    };
+    Some(Duration::from_secs(value.checked_mul(secs)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_clone_pid_only_matches_the_exact_shape() {
+        assert_eq!(temp_clone_pid(".tmp-foo-1234"), Some(1234));
+        assert_eq!(temp_clone_pid(".tmp-my-repo-42"), Some(42));
+        assert_eq!(temp_clone_pid(".tmp-foo"), None);
+        assert_eq!(temp_clone_pid(".tmp--1234"), None);
+        assert_eq!(temp_clone_pid(".tmp-foo-12a4"), None);
+        assert_eq!(temp_clone_pid(".tmp-foo-"), None);
+        assert_eq!(temp_clone_pid("foo-1234"), None);
+    }
+
+    #[test]
+    fn clean_stale_clones_keeps_live_and_foreign_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let own = temp_clone_dir(dir.path(), "mine");
+        let foreign = dir.path().join(".tmp-notes");
+        fs::create_dir(&own).unwrap();
+        fs::create_dir(&foreign).unwrap();
+        clean_stale_clones(dir.path());
+        assert!(own.exists());
+        assert!(foreign.exists());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn clean_stale_clones_removes_clones_of_dead_processes() {
+        let dir = tempfile::tempdir().unwrap();
+        // Past the largest pid Linux hands out
+        let stale = dir.path().join(".tmp-old-4194305");
+        fs::create_dir(&stale).unwrap();
+        clean_stale_clones(dir.path());
+        assert!(!stale.exists());
+    }
+
+    #[test]
+    fn rename_into_place_suffixes_taken_names() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("foo")).unwrap();
+        let temp = dir.path().join(".tmp-foo-1");
+        fs::create_dir(&temp).unwrap();
+        fs::write(temp.join("file"), "x").unwrap();
+
+        let name = rename_into_place(&temp, dir.path(), "foo").unwrap();
+        assert_eq!(name, "foo-2");
+        assert!(dir.path().join("foo-2/file").exists());
+        assert!(!temp.exists());
+    }
+
+    #[test]
+    fn rename_into_place_releases_the_name_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join(".tmp-gone-1");
+        assert!(rename_into_place(&missing, dir.path(), "foo").is_err());
+        assert!(!dir.path().join("foo").exists());
+    }
+
+    #[test]
+    fn rename_experiment_refuses_reserved_and_taken_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let exp = dir.path().join("exp");
+        fs::create_dir(&exp).unwrap();
+        fs::create_dir(dir.path().join("taken")).unwrap();
+        for name in [".trash", ".archive", ".tmp-x-1", "a/b", "..", "", "taken"] {
+            assert!(rename_experiment(&exp, name).is_err(), "{}", name);
+        }
+        assert!(exp.is_dir());
+
+        let renamed = rename_experiment(&exp, "better/").unwrap();
+        assert_eq!(renamed, dir.path().join("better"));
+        assert!(renamed.is_dir() && !exp.exists());
+    }
+
+    #[test]
+    fn move_experiment_keeps_the_name_and_refuses_taken_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        fs::create_dir_all(a.join("exp/src")).unwrap();
+        fs::write(a.join("exp/src/main.rs"), "fn main() {}").unwrap();
+        fs::create_dir_all(b.join("taken")).unwrap();
+        fs::create_dir(a.join("taken")).unwrap();
+
+        assert_eq!(
+            move_experiment(&a.join("taken"), &b).unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        let moved = move_experiment(&a.join("exp"), &b).unwrap();
+        assert_eq!(moved, b.join("exp"));
+        assert!(moved.join("src/main.rs").is_file());
+        assert!(!a.join("exp").exists());
+    }
+
+    #[test]
+    fn parse_age_reads_units_and_rejects_overflow() {
+        assert_eq!(parse_age("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_age(" 12h "), Some(Duration::from_secs(12 * 3600)));
+        assert_eq!(parse_age("2w"), Some(Duration::from_secs(14 * 86400)));
+        assert_eq!(parse_age("30"), Some(Duration::from_secs(30 * 86400)));
+        assert_eq!(parse_age("1y"), Some(Duration::from_secs(365 * 86400)));
+        assert_eq!(parse_age("d"), None);
+        assert_eq!(parse_age(""), None);
+        assert_eq!(parse_age("3mo"), None);
+        assert_eq!(parse_age(&format!("{}w", u64::MAX / 2)), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn project_editor_only_honours_programs_on_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(".try-rs-editor");
+        assert_eq!(project_editor(dir.path()), None);
+
+        fs::write(&file, "# comment\n\nsh\n").unwrap();
+        assert_eq!(project_editor(dir.path()).as_deref(), Some("sh"));
+
+        for editor in [
+            "sh -c 'touch pwned'",
+            "sh;id",
+            "$(id)",
+            "../bin/sh",
+            "-sh",
+            "no-such-editor-xyz",
+        ] {
+            fs::write(&file, editor).unwrap();
+            assert_eq!(project_editor(dir.path()), None, "{}", editor);
+        }
+    }
+
+    #[test]
+    fn bare_shorthands_are_nested_names_without_a_clone_host() {
+        let dir = tempfile::tempdir().unwrap();
+        let tries = dir.path();
+        assert!(!is_git_url("clients/acme", tries, None));
+        assert!(!is_git_url("2024/parser", tries, None));
+        assert_eq!(expand_git_url("owner/repo", None), "owner/repo");
+    }
+
+    #[test]
+    fn bare_shorthands_clone_from_the_clone_host_unless_the_owner_is_a_local_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let tries = dir.path();
+        let host = Some("github.com");
+        assert!(is_git_url("owner/repo", tries, host));
+        assert!(is_git_url("owner/repo#src", tries, host));
+        assert_eq!(
+            expand_git_url("owner/repo", host),
+            "https://github.com/owner/repo"
+        );
+        assert_eq!(
+            expand_git_url("owner/repo", Some("https://git.example.com/")),
+            "https://git.example.com/owner/repo"
+        );
+
+        fs::create_dir(tries.join("clients")).unwrap();
+        assert!(!is_git_url("clients/acme", tries, host));
+        assert!(!is_git_url("./owner/repo", tries, host));
+        assert!(!is_git_url("a/b/c", tries, host));
+    }
+
+    #[test]
+    fn prefixed_shorthands_always_clone() {
+        let dir = tempfile::tempdir().unwrap();
+        let tries = dir.path();
+        fs::create_dir(tries.join("clients")).unwrap();
+        for url in ["gh:clients/acme", "github:clients/acme"] {
+            assert!(is_git_url(url, tries, None), "{}", url);
+            assert_eq!(
+                expand_git_url(url, Some("codeberg.org")),
+                "https://github.com/clients/acme"
+            );
+        }
+        assert!(is_git_url("gl:group/sub/repo", tries, None));
+        assert_eq!(
+            expand_git_url("gl:group/sub/repo", None),
+            "https://gitlab.com/group/sub/repo"
+        );
+        assert!(is_git_url("https://example.com/x", tries, None));
+        assert!(is_git_url("git@github.com:o/r.git", tries, None));
+    }
+
+    #[test]
+    fn is_single_name_rejects_anything_that_leaves_the_directory() {
+        assert!(is_single_name("foo"));
+        assert!(is_single_name("foo.bar-1"));
+        for name in ["", ".", "..", "../x", "a/b", "a\\b", "/etc"] {
+            assert!(!is_single_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn git_init_leaves_the_repository_when_the_first_commit_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        // Nothing to commit in an empty directory
+        assert!(git_init(dir.path(), true).is_err());
+        assert!(dir.path().join(".git").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_dir_keeps_symlinks_as_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from");
+        fs::create_dir_all(from.join("sub")).unwrap();
+        fs::write(from.join("sub/file"), "x").unwrap();
+        std::os::unix::fs::symlink("sub", from.join("dir-link")).unwrap();
+        std::os::unix::fs::symlink("missing", from.join("dangling")).unwrap();
+
+        let to = dir.path().join("to");
+        copy_dir(&from, &to).unwrap();
+        assert_eq!(fs::read_to_string(to.join("sub/file")).unwrap(), "x");
+        for link in ["dir-link", "dangling"] {
+            let metadata = fs::symlink_metadata(to.join(link)).unwrap();
+            assert!(metadata.file_type().is_symlink(), "{}", link);
+        }
+        assert_eq!(
+            fs::read_link(to.join("dir-link")).unwrap(),
+            Path::new("sub")
+        );
+    }
+
+    #[test]
+    fn normalize_selection_trims_blanks_and_one_trailing_slash() {
+        assert_eq!(normalize_selection("  parser\n"), "parser");
+        assert_eq!(normalize_selection("parser/"), "parser");
+        assert_eq!(normalize_selection(" clients/acme/ "), "clients/acme");
+        assert_eq!(normalize_selection("/"), "");
+        assert_eq!(normalize_selection("   "), "");
+    }
+
+    #[test]
+    fn extract_repo_name_ignores_what_browsers_add_to_repository_urls() {
+        for (url, name) in [
+            ("https://github.com/tobi/try.git", "try"),
+            ("git@github.com:tobi/try.git", "try"),
+            ("https://github.com/tobi/try/", "try"),
+            ("https://github.com/tobi/try?tab=readme", "try"),
+            ("https://github.com/tobi/try#readme", "try"),
+            ("https://github.com/tobi/try/tree/main/src", "try"),
+            ("https://github.com/tobi/try/blob/main/README.md", "try"),
+            (
+                "https://gitlab.com/group/sub/project/-/tree/main",
+                "project",
+            ),
+            (
+                "https://gitlab.com/group/project/-/blob/main/x.rs?ref_type=heads",
+                "project",
+            ),
+            // Projects in GitLab subgroups may be named like GitHub's paths
+            ("https://gitlab.com/group/sub/tree", "tree"),
+            ("https://gitlab.com/group/sub/blob.git", "blob"),
+            ("https://gitlab.com/group/tree/project", "project"),
+        ] {
+            assert_eq!(extract_repo_name(url), name, "{}", url);
+        }
+    }
+}
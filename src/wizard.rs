@@ -0,0 +1,444 @@
+use crate::cli::Shell;
+use crate::shell;
+use anyhow::{Context, Result};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use std::fs;
+use std::io;
+use std::path::Path;
+use try_rs::config::{ThemeConfig, peek_tries_dir};
+use try_rs::theme::{ColorDepth, DEFAULT_THEME, PRESETS, Theme};
+use try_rs::utils::on_path;
+use try_rs::{Settings, logging};
+
+// Editors offered when found on PATH, most common first
+const EDITORS: &[&str] = &[
+    "code", "cursor", "zed", "subl", "nvim", "vim", "hx", "emacs", "nano",
+];
+
+// Shells offered for the integration, in the order of `--setup`'s list
+const SHELLS: &[Shell] = &[
+    Shell::Fish,
+    Shell::Zsh,
+    Shell::Bash,
+    Shell::PowerShell,
+    Shell::NuShell,
+    Shell::Elvish,
+    Shell::Xonsh,
+    Shell::Tcsh,
+];
+
+// The shell try-rs was most likely started from
+pub fn detect_shell() -> Option<Shell> {
+    if cfg!(windows) {
+        // On Windows, PowerShell is the most likely modern shell.
+        return Some(Shell::PowerShell);
+    }
+    // Nushell and xonsh don't show up in SHELL, but set their own variables
+    if std::env::var("NU_VERSION").is_ok() {
+        return Some(Shell::NuShell);
+    }
+    if std::env::var("XONSH_VERSION").is_ok() {
+        return Some(Shell::Xonsh);
+    }
+    let shell = std::env::var("SHELL").unwrap_or_default();
+    [
+        ("fish", Shell::Fish),
+        ("zsh", Shell::Zsh),
+        ("bash", Shell::Bash),
+        ("elvish", Shell::Elvish),
+        ("tcsh", Shell::Tcsh),
+    ]
+    .into_iter()
+    .find(|(name, _)| shell.contains(name))
+    .map(|(_, s)| s)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    TriesPath,
+    Shell,
+    Theme,
+    Editor,
+    Confirm,
+}
+
+impl Step {
+    fn title(self) -> &'static str {
+        match self {
+            Step::TriesPath => "Experiments directory",
+            Step::Shell => "Shell integration",
+            Step::Theme => "Theme",
+            Step::Editor => "Editor",
+            Step::Confirm => "Save",
+        }
+    }
+}
+
+// One answer of a list step: what is shown and what gets saved
+struct Choice<T> {
+    label: String,
+    value: T,
+}
+
+struct Wizard {
+    steps: Vec<Step>,
+    step: usize,
+    tries_path: String,
+    shells: Vec<Choice<Option<Shell>>>,
+    themes: Vec<Choice<&'static str>>,
+    editors: Vec<Choice<Option<String>>>,
+    cursors: [usize; 3], // Selected shell, theme and editor
+    theme: Theme,        // The wizard is drawn in the theme under the cursor
+    color_depth: ColorDepth,
+}
+
+impl Wizard {
+    // Without `skip_shell` the shell question comes second, the detected shell first
+    fn new(settings: &Settings, skip_shell: bool) -> Self {
+        let mut steps = vec![Step::TriesPath];
+        if !skip_shell {
+            steps.push(Step::Shell);
+        }
+        steps.extend([Step::Theme, Step::Editor, Step::Confirm]);
+
+        let detected = detect_shell();
+        let mut shells: Vec<Choice<Option<Shell>>> = detected
+            .into_iter()
+            .chain(SHELLS.iter().copied().filter(|&s| Some(s) != detected))
+            .map(|s| Choice {
+                label: if Some(s) == detected {
+                    format!("{:?} (detected)", s)
+                } else {
+                    format!("{:?}", s)
+                },
+                value: Some(s),
+            })
+            .collect();
+        shells.push(Choice {
+            label: "Skip: I'll load `try-rs init <shell>` from my startup file".to_string(),
+            value: None,
+        });
+
+        let theme_cursor = PRESETS
+            .iter()
+            .position(|(name, _)| *name == settings.theme_name)
+            .unwrap_or(0);
+        let themes = PRESETS
+            .iter()
+            .map(|(name, _)| Choice {
+                label: name.to_string(),
+                value: *name,
+            })
+            .collect();
+
+        let mut editors = vec![Choice {
+            label: match &settings.editor_cmd {
+                Some(cmd) => format!("{} (from the environment)", cmd),
+                None => "None".to_string(),
+            },
+            value: None,
+        }];
+        editors.extend(EDITORS.iter().filter(|cmd| on_path(cmd)).map(|cmd| Choice {
+            label: cmd.to_string(),
+            value: Some(cmd.to_string()),
+        }));
+
+        Self {
+            steps,
+            step: 0,
+            // What the config says, not a --tries-path given for this run only
+            tries_path: peek_tries_dir(None, None)
+                .map(|(path, _)| path.display().to_string())
+                .unwrap_or_default(),
+            shells,
+            themes,
+            editors,
+            cursors: [0, theme_cursor, 0],
+            theme: settings.theme.clone(),
+            color_depth: settings.color_depth,
+        }
+    }
+
+    fn current(&self) -> Step {
+        self.steps[self.step]
+    }
+
+    // The labels and cursor of a list step
+    fn list(&self) -> Option<(Vec<&str>, usize)> {
+        fn labels<T>(choices: &[Choice<T>]) -> Vec<&str> {
+            choices.iter().map(|c| c.label.as_str()).collect()
+        }
+        match self.current() {
+            Step::Shell => Some((labels(&self.shells), self.cursors[0])),
+            Step::Theme => Some((labels(&self.themes), self.cursors[1])),
+            Step::Editor => Some((labels(&self.editors), self.cursors[2])),
+            Step::TriesPath | Step::Confirm => None,
+        }
+    }
+
+    fn move_cursor(&mut self, down: bool) {
+        let (index, len) = match self.current() {
+            Step::Shell => (0, self.shells.len()),
+            Step::Theme => (1, self.themes.len()),
+            Step::Editor => (2, self.editors.len()),
+            Step::TriesPath | Step::Confirm => return,
+        };
+        let cursor = &mut self.cursors[index];
+        *cursor = if down {
+            (*cursor + 1).min(len - 1)
+        } else {
+            cursor.saturating_sub(1)
+        };
+        if index == 1 {
+            self.theme = ThemeConfig::default().theme(PRESETS[self.cursors[1]].0, self.color_depth);
+        }
+    }
+
+    fn shell(&self) -> Option<Shell> {
+        if self.steps.contains(&Step::Shell) {
+            self.shells[self.cursors[0]].value
+        } else {
+            None
+        }
+    }
+
+    fn summary(&self) -> String {
+        let shell = match self.shell() {
+            Some(s) => format!("install for {:?}", s),
+            None => "not installed".to_string(),
+        };
+        format!(
+            "Experiments: {}\nShell integration: {}\nTheme: {}\nEditor: {}\n\nEnter writes config.toml; you can change it at any time.",
+            self.tries_path.trim(),
+            shell,
+            self.themes[self.cursors[1]].label,
+            self.editors[self.cursors[2]].label
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        match self.current() {
+            Step::TriesPath => {
+                "Where new experiments are created. It is made if it doesn't exist; `~` works."
+            }
+            Step::Shell => {
+                "try-rs needs a small shell function to change your directory. Installing it writes the function and loads it from your shell's startup file."
+            }
+            Step::Theme => {
+                "Colors of the interface, previewed here as you move. Fine-tune them in [colors]."
+            }
+            Step::Editor => "Opens experiments with Ctrl+E and `try-rs -o`.",
+            Step::Confirm => "",
+        }
+    }
+
+    // The config.toml the answers amount to
+    fn config_text(&self) -> String {
+        let mut text = format!("tries_path = {:?}\n", self.tries_path.trim());
+        if let Some(editor) = &self.editors[self.cursors[2]].value {
+            text.push_str(&format!("editor = {:?}\n", editor));
+        }
+        let theme = self.themes[self.cursors[1]].value;
+        if theme != DEFAULT_THEME {
+            text.push_str(&format!("theme = {:?}\n", theme));
+        }
+        text
+    }
+}
+
+// First run: asks for the experiments directory, the shell integration, a theme and
+// an editor, and writes them to `config_file` (installing the integration last).
+// Returns false if the wizard was cancelled and the default config kept.
+pub fn run(config_file: &Path, settings: &Settings, skip_shell: bool) -> Result<bool> {
+    let mut wizard = Wizard::new(settings, skip_shell);
+
+    enable_raw_mode()?;
+    let mut stderr = io::stderr();
+    logging::hold();
+    execute!(stderr, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stderr))?;
+
+    let res = run_loop(&mut terminal, &mut wizard);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    logging::release();
+
+    if !res? {
+        eprintln!(
+            "Setup skipped; the defaults were saved to {}.",
+            config_file.display()
+        );
+        return Ok(false);
+    }
+    fs::write(config_file, wizard.config_text())
+        .with_context(|| format!("writing {}", config_file.display()))?;
+    eprintln!("Configuration saved to {}", config_file.display());
+    if let Some(s) = wizard.shell() {
+        shell::setup(s)?;
+    }
+    Ok(true)
+}
+
+// Returns true once the last step is confirmed, false if cancelled
+fn run_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stderr>>,
+    wizard: &mut Wizard,
+) -> Result<bool> {
+    loop {
+        terminal.draw(|f| {
+            draw(f, wizard);
+            wizard.color_depth.adapt_buffer(f.buffer_mut());
+        })?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if !key.is_press() {
+            continue;
+        }
+        match handle_key(wizard, key) {
+            Some(done) => return Ok(done),
+            None => continue,
+        }
+    }
+}
+
+// Some(true) when finished, Some(false) when cancelled
+fn handle_key(wizard: &mut Wizard, key: KeyEvent) -> Option<bool> {
+    let step = wizard.current();
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Some(false),
+        KeyCode::Esc if wizard.step == 0 => return Some(false),
+        KeyCode::Esc | KeyCode::BackTab => wizard.step = wizard.step.saturating_sub(1),
+        KeyCode::Enter if step == Step::Confirm => return Some(true),
+        // An empty path can't be saved
+        KeyCode::Enter | KeyCode::Tab
+            if step != Step::Confirm
+                && (step != Step::TriesPath || !wizard.tries_path.trim().is_empty()) =>
+        {
+            wizard.step += 1;
+        }
+        KeyCode::Up => wizard.move_cursor(false),
+        KeyCode::Down => wizard.move_cursor(true),
+        KeyCode::Backspace if step == Step::TriesPath => {
+            wizard.tries_path.pop();
+        }
+        KeyCode::Char(c) if step == Step::TriesPath => wizard.tries_path.push(c),
+        KeyCode::Char('k') => wizard.move_cursor(false),
+        KeyCode::Char('j') => wizard.move_cursor(true),
+        _ => {}
+    }
+    None
+}
+
+fn draw(f: &mut Frame, wizard: &Wizard) {
+    let theme = &wizard.theme;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .split(f.area());
+
+    let title = Paragraph::new(Line::from(vec![
+        Span::styled(
+            "🦀 try",
+            Style::default()
+                .fg(theme.title_try)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled("-", Style::default().fg(Color::DarkGray)),
+        Span::styled(
+            "rs",
+            Style::default()
+                .fg(theme.title_rs)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(" first-run setup", Style::default().fg(Color::DarkGray)),
+    ]))
+    .alignment(Alignment::Center);
+    f.render_widget(title, chunks[0]);
+
+    let step = wizard.current();
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        " {}/{} · {} ",
+        wizard.step + 1,
+        wizard.steps.len(),
+        step.title()
+    ));
+    let inner = block.inner(chunks[1]);
+    f.render_widget(block, chunks[1]);
+    let body = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(1)])
+        .split(inner);
+
+    if step == Step::Confirm {
+        let summary = Paragraph::new(wizard.summary())
+            .style(Style::default().fg(theme.status_message))
+            .wrap(Wrap { trim: false });
+        f.render_widget(summary, inner);
+    } else {
+        let description = Paragraph::new(wizard.description())
+            .style(Style::default().fg(theme.help_text))
+            .wrap(Wrap { trim: true });
+        f.render_widget(description, body[0]);
+    }
+
+    if step == Step::TriesPath {
+        let input = Paragraph::new(format!("{}▏", wizard.tries_path))
+            .style(Style::default().fg(theme.search_box))
+            .block(Block::default().borders(Borders::ALL));
+        let area = Rect {
+            height: body[1].height.min(3),
+            ..body[1]
+        };
+        f.render_widget(input, area);
+    }
+
+    if let Some((labels, cursor)) = wizard.list() {
+        let list = List::new(labels.into_iter().map(ListItem::new))
+            .highlight_style(theme.highlight_style().add_modifier(Modifier::BOLD))
+            .highlight_symbol("> ");
+        let mut state = ListState::default();
+        state.select(Some(cursor));
+        f.render_stateful_widget(list, body[1], &mut state);
+    }
+
+    let mut spans = Vec::new();
+    let keys: &[(&str, &str)] = match step {
+        Step::TriesPath => &[("Enter", ": Next  "), ("Esc", ": Skip setup")],
+        Step::Confirm => &[
+            ("Enter", ": Save  "),
+            ("Esc", ": Back  "),
+            ("Ctrl+C", ": Skip setup"),
+        ],
+        _ => &[
+            ("↑↓", ": Choose  "),
+            ("Enter", ": Next  "),
+            ("Esc", ": Back  "),
+            ("Ctrl+C", ": Skip setup"),
+        ],
+    };
+    for (key, label) in keys {
+        spans.push(Span::styled(
+            key.to_string(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::raw(*label));
+    }
+    let help = Paragraph::new(Line::from(spans))
+        .style(Style::default().fg(theme.help_text))
+        .alignment(Alignment::Center);
+    f.render_widget(help, chunks[2]);
+}